@@ -0,0 +1,59 @@
+//! Measure the time and peak memory it takes to load repodata, either owned with `read_repodata`
+//! or borrowed from a memory-mapped `RepodataFile`.
+//!
+//! Each mode should be run in its own process, since the peak memory is that of the process:
+//!
+//! ```text
+//! cargo run --release --example load_repodata -- owned path/to/repodata.json
+//! cargo run --release --example load_repodata -- borrowed path/to/repodata.json
+//! ```
+//!
+//! The peak memory is read from `/proc/self/status`, and is only reported on Linux.
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Instant;
+
+use libronda::{RepodataFile, read_repodata};
+
+/// Get the peak resident set size of this process, in kilobytes.
+fn peak_rss() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 || (args[1] != "owned" && args[1] != "borrowed") {
+        eprintln!("usage: {} owned|borrowed <repodata.json>", args[0]);
+        process::exit(2);
+    }
+
+    let start = Instant::now();
+    let records = if args[1] == "owned" {
+        let repodata = read_repodata(&args[2]).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+        repodata.packages().len() + repodata.packages_conda().len()
+    } else {
+        let file = RepodataFile::open(&args[2]).and_then(|file| file.parse().map(|repodata| {
+            repodata.packages().len() + repodata.packages_conda().len()
+        }));
+        file.unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        })
+    };
+    let elapsed = start.elapsed();
+
+    println!("mode:     {}", args[1]);
+    println!("records:  {}", records);
+    println!("time:     {:.1} ms", elapsed.as_secs_f64() * 1000.0);
+    match peak_rss() {
+        Some(kilobytes) => println!("peak RSS: {:.1} MB", kilobytes as f64 / 1024.0),
+        None => println!("peak RSS: unknown"),
+    }
+}
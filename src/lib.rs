@@ -4,18 +4,31 @@
 //!
 //! ## Examples
 //!
-#[macro_use] extern crate lazy_static;
 #[cfg(test)]
 #[macro_use] extern crate rstest;
-#[cfg(test)]
-#[macro_use] extern crate paste;
 
 mod version;
+mod match_spec;
 mod repodata;
 
 // Reexports
 pub use crate::version::CompOp;
+pub use crate::version::Pin;
+pub use crate::version::{CondaScheme, DebianScheme, DefaultScheme, Pep440Scheme, RpmScheme, SemVerScheme, VersionScheme};
+pub use crate::version::{LocalSegment, Pep440Version, PreRelease};
+pub use crate::version::{Pep440Specifier, Pep440SpecifierSet};
 pub use crate::version::Version;
+pub use crate::version::VersionBuf;
 pub use crate::version::VersionPart;
 pub use crate::version::VersionCompare;
+pub use crate::version::VersionParseError;
+pub use crate::version::VersionSpec;
+pub use crate::version::VersionSpecParseError;
 pub use crate::version::conda_parser;
+pub use crate::version::debian_parser;
+pub use crate::version::default_parser;
+pub use crate::version::pep440_parser;
+pub use crate::version::rpm_parser;
+pub use crate::version::semver_parser;
+pub use crate::match_spec::{BuildNumberSpec, MatchSpec, MatchSpecParseError, MergeError, SpecConflict};
+pub use crate::repodata::{ChannelPriority, ChannelRecord, ChannelSet, FieldChange, FormatPreference, IndexedRecord, Jlap, JlapError, JlapPatch, JlapUpdate, MergedRepodata, Noarch, PackageFormat, PatchInstructions, PatchReport, Record, RecordRef, Repodata, RepodataFile, RepodataIndex, RepodataRef, RepodataError, RepodataInfo, read_jlap, read_repodata, read_repodata_from, repodata_hash};
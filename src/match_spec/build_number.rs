@@ -0,0 +1,119 @@
+//! Build number spec module, which provides the `BuildNumberSpec` struct.
+//!
+//! The build number of a match spec is constrained with a number, such as `build_number=3`, or
+//! with a comparison operator and a number, such as `build_number='>=3'`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::version::CompOp;
+
+/// A constraint on the build number of a package.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::BuildNumberSpec;
+///
+/// let spec: BuildNumberSpec = ">=2".parse().unwrap();
+///
+/// assert!(spec.contains(3));
+/// assert!(!spec.contains(1));
+/// assert_eq!(spec.to_string(), ">=2");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuildNumberSpec {
+    operator: CompOp,
+    number: u64,
+}
+
+impl BuildNumberSpec {
+    /// Create a constraint matching build numbers that compare to `number` with `operator`.
+    pub fn new(operator: CompOp, number: u64) -> BuildNumberSpec {
+        BuildNumberSpec { operator, number }
+    }
+
+    /// Get the comparison operator of this constraint.
+    pub fn operator(&self) -> &CompOp {
+        &self.operator
+    }
+
+    /// Get the number build numbers are compared to.
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// Check whether the given build number satisfies this constraint.
+    pub fn contains(&self, build_number: u64) -> bool {
+        match self.operator {
+            CompOp::Eq => build_number == self.number,
+            CompOp::Ne => build_number != self.number,
+            CompOp::Lt => build_number < self.number,
+            CompOp::Le => build_number <= self.number,
+            CompOp::Ge => build_number >= self.number,
+            CompOp::Gt => build_number > self.number,
+        }
+    }
+}
+
+/// Parse a `BuildNumberSpec` from a number, optionally preceded by a comparison operator.
+impl FromStr for BuildNumberSpec {
+    type Err = ();
+
+    fn from_str(spec: &str) -> Result<BuildNumberSpec, ()> {
+        let spec = spec.trim();
+        let number_start = spec.find(|c: char| c.is_ascii_digit()).ok_or(())?;
+        let operator = match &spec[..number_start] {
+            "" => CompOp::Eq,
+            sign => CompOp::from_sign(sign)?,
+        };
+        let number = spec[number_start..].parse().map_err(|_| ())?;
+        Ok(BuildNumberSpec { operator, number })
+    }
+}
+
+impl fmt::Display for BuildNumberSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.operator {
+            CompOp::Eq => write!(f, "{}", self.number),
+            _ => write!(f, "{}{}", self.operator.sign(), self.number),
+        }
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use super::BuildNumberSpec;
+
+    #[rstest_parametrize(spec, build_number, expected,
+    case("3", 3, true),
+    case("3", 4, false),
+    case("==3", 3, true),
+    case("!=3", 3, false),
+    case("<3", 2, true),
+    case("<=3", 3, true),
+    case(">3", 3, false),
+    case(" >= 3 ", 3, true),
+    )]
+    fn contains(spec: &str, build_number: u64, expected: bool) {
+        assert_eq!(spec.parse::<BuildNumberSpec>().unwrap().contains(build_number), expected);
+    }
+
+    #[rstest_parametrize(spec,
+    case(""),
+    case(">="),
+    case("~=3"),
+    case("3a"),
+    case("-1"),
+    )]
+    fn invalid(spec: &str) {
+        assert!(spec.parse::<BuildNumberSpec>().is_err());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("==3".parse::<BuildNumberSpec>().unwrap().to_string(), "3");
+        assert_eq!("> 3".parse::<BuildNumberSpec>().unwrap().to_string(), ">3");
+    }
+}
@@ -0,0 +1,794 @@
+//! Match spec module, which provides the `MatchSpec` struct.
+//!
+//! A match spec is a query for packages, as found in the `depends` of every package record, such
+//! as `python >=3.6,<3.7.0a0` or `conda-forge::numpy[version='>=1.8',build=py36*]`.  A match spec
+//! has a package name, optionally prefixed with a channel and namespace, followed by an optional
+//! version spec and build string, and an optional bracket section with `key=value` constraints.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::repodata::Record;
+use crate::version::{Version, VersionSpec};
+use crate::version::version_spec::glob_match;
+use super::build_number::BuildNumberSpec;
+use super::parse_error::MatchSpecParseError;
+
+/// The subdirs conda knows, which are split off the end of a channel.
+const KNOWN_SUBDIRS: &[&str] = &[
+    "noarch", "emscripten-wasm32", "freebsd-64", "linux-32", "linux-64", "linux-aarch64",
+    "linux-armv6l", "linux-armv7l", "linux-ppc64", "linux-ppc64le", "linux-riscv64", "linux-s390x",
+    "osx-64", "osx-arm64", "wasi-wasm32", "win-32", "win-64", "win-arm64", "zos-z",
+];
+
+/// A parsed conda match spec.
+///
+/// Parsing follows conda: a version starting with `=`, such as in `numpy=1.8`, matches all
+/// versions starting with `1.8`, a bare version such as in `numpy 1.8` matches exactly `1.8`,
+/// and a version and build can be given as `numpy=1.8=py36_0`.  Values in brackets take
+/// precedence over positional ones.  Constraints matching anything, such as a version `*`, are
+/// left out.
+///
+/// The canonical string form is that of conda: exact versions and builds are written
+/// positionally, such as `numpy==1.8=py36_0`, while anything more complex goes into brackets.
+/// Version specs joining several specs keep the form they were parsed from, so that `numpy
+/// 1.8|1.9` is written `numpy[version='1.8|1.9']`.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{MatchSpec, Version};
+///
+/// let spec: MatchSpec = "conda-forge/linux-64::numpy >=1.8 py36*".parse().unwrap();
+///
+/// assert_eq!(spec.channel(), Some("conda-forge"));
+/// assert_eq!(spec.subdir(), Some("linux-64"));
+/// assert_eq!(spec.name(), "numpy");
+/// assert!(spec.version().unwrap().contains(&Version::from("1.11").unwrap()));
+/// assert_eq!(spec.build(), Some("py36*"));
+/// assert_eq!(spec.to_string(), "conda-forge/linux-64::numpy[version='>=1.8',build=py36*]");
+/// ```
+#[derive(Clone, Debug)]
+pub struct MatchSpec {
+    name: String,
+    channel: Option<String>,
+    subdir: Option<String>,
+    namespace: Option<String>,
+    version: Option<VersionSpec>,
+    version_text: Option<String>,
+    build: Option<String>,
+    build_number: Option<BuildNumberSpec>,
+    md5: Option<String>,
+    sha256: Option<String>,
+    features: BTreeSet<String>,
+    track_features: BTreeSet<String>,
+    license: Option<String>,
+}
+
+impl MatchSpec {
+    /// Create a match spec for all packages with the given name, which may be `*` to match any
+    /// name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::MatchSpec;
+    ///
+    /// let spec = MatchSpec::new("openssl").with_version(Some("1.1.1*".parse().unwrap()));
+    ///
+    /// assert_eq!(spec, "openssl=1.1.1".parse().unwrap());
+    /// ```
+    pub fn new(name: &str) -> MatchSpec {
+        MatchSpec {
+            name: name.to_string(),
+            channel: None,
+            subdir: None,
+            namespace: None,
+            version: None,
+            version_text: None,
+            build: None,
+            build_number: None,
+            md5: None,
+            sha256: None,
+            features: BTreeSet::new(),
+            track_features: BTreeSet::new(),
+            license: None,
+        }
+    }
+
+    /// Get the package name, which is `*` when any name matches.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the channel, without the subdir.
+    pub fn channel(&self) -> Option<&str> {
+        self.channel.as_deref()
+    }
+
+    /// Get the subdir, such as `linux-64`.
+    pub fn subdir(&self) -> Option<&str> {
+        self.subdir.as_deref()
+    }
+
+    /// Get the namespace.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Get the version spec.
+    pub fn version(&self) -> Option<&VersionSpec> {
+        self.version.as_ref()
+    }
+
+    /// Get the build string glob, in which `*` matches any sequence of characters.
+    pub fn build(&self) -> Option<&str> {
+        self.build.as_deref()
+    }
+
+    /// Get the build number constraint.
+    pub fn build_number(&self) -> Option<&BuildNumberSpec> {
+        self.build_number.as_ref()
+    }
+
+    /// Get the MD5 hash of the package file.
+    pub fn md5(&self) -> Option<&str> {
+        self.md5.as_deref()
+    }
+
+    /// Get the SHA-256 hash of the package file.
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+
+    /// Get the features, which are empty when no features are required.
+    pub fn features(&self) -> &BTreeSet<String> {
+        &self.features
+    }
+
+    /// Get the track features, which are empty when no track features are required.
+    pub fn track_features(&self) -> &BTreeSet<String> {
+        &self.track_features
+    }
+
+    /// Get the license.
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+
+    /// Check whether the given record matches this spec.
+    ///
+    /// The name and build are matched as globs, the version with the version spec, and hashes
+    /// case-insensitively.  When given, the features and track features of the record must be
+    /// exactly those of the spec.  The channel and namespace aren't checked, since a record
+    /// doesn't know which channel it belongs to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{MatchSpec, read_repodata};
+    ///
+    /// let repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+    /// let spec: MatchSpec = "openssl 1.1.1*".parse().unwrap();
+    ///
+    /// assert!(repodata.find(&spec).iter().all(|record| spec.matches(record)));
+    /// ```
+    pub fn matches(&self, record: &Record) -> bool {
+        if !glob_match(&self.name, record.name()) {
+            return false;
+        }
+        if let Some(version) = &self.version {
+            match Version::from(record.version()) {
+                Ok(record_version) if version.contains(&record_version) => {},
+                _ => return false,
+            }
+        }
+        if self.build.as_ref().is_some_and(|build| !glob_match(build, record.build())) {
+            return false;
+        }
+        if self.build_number.as_ref().is_some_and(|spec| !spec.contains(record.build_number())) {
+            return false;
+        }
+        let hash_matches = |spec: &Option<String>, record: Option<&str>| match spec {
+            Some(spec) => record.is_some_and(|record| spec.eq_ignore_ascii_case(record)),
+            None => true,
+        };
+        if !hash_matches(&self.md5, Some(record.md5())) || !hash_matches(&self.sha256, record.sha256()) {
+            return false;
+        }
+        if self.subdir.is_some() && self.subdir.as_deref() != record.subdir() {
+            return false;
+        }
+        if self.license.is_some() && self.license.as_deref() != record.license() {
+            return false;
+        }
+        let features_match = |spec: &BTreeSet<String>, record: Option<&str>| {
+            spec.is_empty() || *spec == split_features(record.unwrap_or_default()).collect()
+        };
+        features_match(&self.features, record.features())
+            && features_match(&self.track_features, record.track_features())
+    }
+
+    /// Set the package name.
+    pub fn with_name(mut self, name: &str) -> MatchSpec {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set the channel.  A subdir at the end of the channel, such as in `conda-forge/linux-64`,
+    /// is split off and sets the subdir.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::MatchSpec;
+    ///
+    /// let spec = MatchSpec::new("numpy").with_channel(Some("conda-forge/noarch"));
+    ///
+    /// assert_eq!(spec.channel(), Some("conda-forge"));
+    /// assert_eq!(spec.subdir(), Some("noarch"));
+    /// ```
+    pub fn with_channel(mut self, channel: Option<&str>) -> MatchSpec {
+        self.channel = None;
+        if let Some(channel) = channel {
+            let (channel, subdir) = split_channel(channel);
+            self.channel = channel.map(str::to_string);
+            if subdir.is_some() {
+                self.subdir = subdir.map(str::to_string);
+            }
+        }
+        self
+    }
+
+    /// Set the subdir.
+    pub fn with_subdir(mut self, subdir: Option<&str>) -> MatchSpec {
+        self.subdir = subdir.map(str::to_string);
+        self
+    }
+
+    /// Set the namespace.
+    pub fn with_namespace(mut self, namespace: Option<&str>) -> MatchSpec {
+        self.namespace = namespace.map(str::to_string);
+        self
+    }
+
+    /// Set the version spec.  A spec matching any version, `*`, is left out.
+    pub fn with_version(mut self, version: Option<VersionSpec>) -> MatchSpec {
+        self.version = version.filter(|version| *version != VersionSpec::Any);
+        self.version_text = None;
+        self
+    }
+
+    /// Set the version spec parsed from `text`, found at `offset` in the match spec string, and
+    /// keep the text to display the spec the way it was written.
+    fn with_version_text(self, text: &str, offset: usize) -> Result<MatchSpec, MatchSpecParseError> {
+        let version = text.parse().map_err(|error| MatchSpecParseError::InvalidVersion { offset, error })?;
+        let mut match_spec = self.with_version(Some(version));
+        match_spec.version_text = match_spec.version.as_ref().map(|_| text.to_string());
+        Ok(match_spec)
+    }
+
+    /// Set the build string glob.  A glob matching any build, `*`, is left out.
+    pub fn with_build(mut self, build: Option<&str>) -> MatchSpec {
+        self.build = build.filter(|build| *build != "*").map(str::to_string);
+        self
+    }
+
+    /// Set the build number constraint.
+    pub fn with_build_number(mut self, build_number: Option<BuildNumberSpec>) -> MatchSpec {
+        self.build_number = build_number;
+        self
+    }
+
+    /// Set the MD5 hash of the package file.
+    pub fn with_md5(mut self, md5: Option<&str>) -> MatchSpec {
+        self.md5 = md5.map(str::to_string);
+        self
+    }
+
+    /// Set the SHA-256 hash of the package file.
+    pub fn with_sha256(mut self, sha256: Option<&str>) -> MatchSpec {
+        self.sha256 = sha256.map(str::to_string);
+        self
+    }
+
+    /// Set the features.
+    pub fn with_features<I: IntoIterator<Item = String>>(mut self, features: I) -> MatchSpec {
+        self.features = features.into_iter().collect();
+        self
+    }
+
+    /// Set the track features.
+    pub fn with_track_features<I: IntoIterator<Item = String>>(mut self, track_features: I) -> MatchSpec {
+        self.track_features = track_features.into_iter().collect();
+        self
+    }
+
+    /// Set the license.
+    pub fn with_license(mut self, license: Option<&str>) -> MatchSpec {
+        self.license = license.map(str::to_string);
+        self
+    }
+}
+
+/// Split a known subdir off the end of a channel.  An empty channel or `*` is no channel.
+fn split_channel(channel: &str) -> (Option<&str>, Option<&str>) {
+    let channel = channel.trim().trim_end_matches('/');
+    let (channel, subdir) = match channel.rsplit_once('/') {
+        Some((rest, subdir)) if KNOWN_SUBDIRS.contains(&subdir) => (rest, Some(subdir)),
+        _ => (channel, None),
+    };
+    (Some(channel).filter(|channel| !channel.is_empty() && *channel != "*"), subdir)
+}
+
+/// Split a list of features separated by spaces or commas.
+fn split_features(features: &str) -> impl Iterator<Item = String> + '_ {
+    features.split([' ', ',']).filter(|feature| !feature.is_empty()).map(str::to_string)
+}
+
+/// Get the byte offset of `part`, which must be a substring of `whole`.
+fn offset_in(whole: &str, part: &str) -> usize {
+    part.as_ptr() as usize - whole.as_ptr() as usize
+}
+
+/// The `key=value` entries of a bracket section.
+type BracketEntries<'s> = Vec<(&'s str, &'s str)>;
+
+/// Split off the bracket section at the end of the spec, and parse its `key=value` entries.
+/// Values may be quoted with `'` or `"`, and entries are separated by commas or spaces.
+fn split_brackets(spec: &str) -> Result<(&str, BracketEntries<'_>), MatchSpecParseError> {
+    let open = match spec.find('[') {
+        Some(open) => open,
+        None => return Ok((spec, Vec::new())),
+    };
+    if !spec.ends_with(']') {
+        return Err(MatchSpecParseError::UnbalancedBracket { offset: open });
+    }
+
+    let mut entries = Vec::new();
+    let mut rest = &spec[open + 1..spec.len() - 1];
+    loop {
+        rest = rest.trim_start_matches([',', ' ']);
+        if rest.is_empty() {
+            break;
+        }
+        let invalid = MatchSpecParseError::InvalidBracketEntry { offset: offset_in(spec, rest) };
+        let key_end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-').unwrap_or(rest.len());
+        let key = &rest[..key_end];
+        let value = match rest[key_end..].strip_prefix('=') {
+            Some(value) if !key.is_empty() => value,
+            _ => return Err(invalid),
+        };
+        let (value, next) = match value.chars().next() {
+            Some(quote) if quote == '\'' || quote == '"' => match value[1..].find(quote) {
+                Some(end) => (&value[1..=end], &value[end + 2..]),
+                None => return Err(invalid),
+            },
+            _ => {
+                let end = value.find([',', ' ']).unwrap_or(value.len());
+                (&value[..end], &value[end..])
+            },
+        };
+        if value.is_empty() {
+            return Err(invalid);
+        }
+        entries.push((key, value));
+        rest = next;
+    }
+    Ok((&spec[..open], entries))
+}
+
+/// Split the version and build of a match spec, such as `>=1.8 py36_0` or `=1.8=py36_0`.
+///
+/// Like conda, the build is the trailing text after the first space or `=` that doesn't follow
+/// an operator, if that text has no operators.
+fn split_version_build(text: &str) -> (&str, Option<&str>) {
+    let bytes = text.as_bytes();
+    for i in 1..bytes.len() {
+        if (bytes[i] == b' ' || bytes[i] == b'=') && !b"=!|,<>~".contains(&bytes[i - 1]) {
+            let build = &text[i + 1..];
+            if !build.is_empty() && !build.contains(['-', '=', ',', '|', '<', '>', '~']) {
+                let build = build.trim();
+                return (&text[..i], Some(build).filter(|build| !build.is_empty()));
+            }
+        }
+    }
+    (text, None)
+}
+
+/// Parse a `MatchSpec` from a match spec string.  Comments starting with `#` are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{MatchSpec, MatchSpecParseError};
+///
+/// let spec: MatchSpec = "numpy=1.8=py36_0".parse().unwrap();
+/// assert_eq!(spec.to_string(), "numpy==1.8=py36_0");
+///
+/// assert_eq!(
+///     "numpy[version=1.8".parse::<MatchSpec>().unwrap_err(),
+///     MatchSpecParseError::UnbalancedBracket { offset: 5 },
+/// );
+/// ```
+impl FromStr for MatchSpec {
+    type Err = MatchSpecParseError;
+
+    fn from_str(original: &str) -> Result<MatchSpec, MatchSpecParseError> {
+        let spec = match original.find('#') {
+            Some(comment) => &original[..comment],
+            None => original,
+        };
+        let spec = spec.trim();
+        let (spec, brackets) = split_brackets(spec)?;
+        let spec = spec.trim();
+
+        // The channel and namespace are split off with `:`, from the right so that channel URLs
+        // keep their scheme
+        let mut prefixes = spec.rsplitn(3, ':');
+        let spec = prefixes.next().unwrap_or_default();
+        let namespace = prefixes.next().filter(|namespace| !namespace.is_empty());
+        let channel = prefixes.next();
+
+        let name_end = spec.find([' ', '=', '<', '>', '!', '~']).unwrap_or(spec.len());
+        let name = &spec[..name_end];
+        if name.is_empty() {
+            return Err(MatchSpecParseError::MissingName { offset: offset_in(original, spec) });
+        }
+        let mut match_spec = MatchSpec::new(name).with_channel(channel).with_namespace(namespace);
+
+        let rest = spec[name_end..].trim();
+        if !rest.is_empty() {
+            let (version, build) = split_version_build(rest);
+            let offset = offset_in(original, version);
+            let mut version = version.replace(' ', "");
+
+            // Translate `==1.8` to `1.8`, and `=1.8` to `1.8*`, unless a build is given
+            if version.starts_with('=') && !version.starts_with("===") && version != "=" && version != "==" {
+                let test = version[1..].to_string();
+                if version.starts_with("==") && build.is_none() {
+                    version = version[2..].to_string();
+                } else if !test.contains(['=', ',', '|']) {
+                    version = if build.is_none() && !test.ends_with('*') { test + "*" } else { test };
+                }
+            }
+            match_spec = match_spec.with_version_text(&version, offset)?.with_build(build);
+        }
+
+        for (key, value) in brackets {
+            let offset = offset_in(original, value);
+            match_spec = match key {
+                "name" => match_spec.with_name(value),
+                "channel" => match_spec.with_channel(Some(value)),
+                "subdir" => match_spec.with_subdir(Some(value)),
+                "namespace" => match_spec.with_namespace(Some(value)),
+                "version" => match_spec.with_version_text(value, offset)?,
+                "build" => match_spec.with_build(Some(value)),
+                "build_number" => match value.parse() {
+                    Ok(build_number) => match_spec.with_build_number(Some(build_number)),
+                    Err(()) => return Err(MatchSpecParseError::InvalidBuildNumber { offset }),
+                },
+                "md5" => match_spec.with_md5(Some(value)),
+                "sha256" => match_spec.with_sha256(Some(value)),
+                "features" => match_spec.with_features(split_features(value)),
+                "track_features" => match_spec.with_track_features(split_features(value)),
+                "license" => match_spec.with_license(Some(value)),
+                _ => return Err(MatchSpecParseError::UnknownKey {
+                    key: key.to_string(),
+                    offset: offset_in(original, key),
+                }),
+            };
+        }
+
+        Ok(match_spec)
+    }
+}
+
+/// Write a `key=value` bracket entry, quoting values that hold separators.
+fn bracket_entry(key: &str, value: &str, quote_chars: &[char]) -> String {
+    if value.contains(quote_chars) {
+        format!("{}='{}'", key, value)
+    } else {
+        format!("{}={}", key, value)
+    }
+}
+
+/// Match specs are equal when they have the same constraints, however their version specs are
+/// written.
+impl PartialEq for MatchSpec {
+    fn eq(&self, other: &MatchSpec) -> bool {
+        self.name == other.name
+            && self.channel == other.channel
+            && self.subdir == other.subdir
+            && self.namespace == other.namespace
+            && self.version == other.version
+            && self.build == other.build
+            && self.build_number == other.build_number
+            && self.md5 == other.md5
+            && self.sha256 == other.sha256
+            && self.features == other.features
+            && self.track_features == other.track_features
+            && self.license == other.license
+    }
+}
+
+/// Write a version spec made of several specs the way conda does: as it was written, without
+/// whitespace, and with versions ending with a bare `*`, such as `1.8*`, written as `1.8.*`.
+/// Bare versions such as `1.8` in `1.8|1.9` thus stay bare.
+fn conda_version_text(text: &str) -> String {
+    let text: String = text.split_whitespace().collect();
+    let mut written = String::with_capacity(text.len() + 2);
+    let mut start = 0;
+    for (end, delimiter) in text.match_indices(['|', ',', '(', ')']).chain(Some((text.len(), ""))) {
+        let spec = &text[start..end];
+        match spec.strip_suffix('*') {
+            Some(prefix) if !spec.starts_with(['=', '<', '>', '!', '~']) && !spec.ends_with(".*")
+                && !prefix.is_empty() && !prefix.trim_end_matches('*').contains('*') => {
+                written.push_str(prefix);
+                written.push_str(".*");
+            },
+            _ => written.push_str(spec),
+        }
+        written.push_str(delimiter);
+        start = end + delimiter.len();
+    }
+    written
+}
+
+impl fmt::Display for MatchSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut brackets = Vec::new();
+
+        match (&self.channel, &self.subdir) {
+            (Some(channel), Some(subdir)) => write!(f, "{}/{}:", channel, subdir)?,
+            (Some(channel), None) => write!(f, "{}:", channel)?,
+            (None, Some(subdir)) => brackets.push(format!("subdir={}", subdir)),
+            (None, None) => {},
+        }
+        match &self.namespace {
+            Some(namespace) => write!(f, "{}:", namespace)?,
+            None if self.channel.is_some() => write!(f, ":")?,
+            None => {},
+        }
+        write!(f, "{}", self.name)?;
+
+        let special = ['>', '<', '$', '^', '|', ','];
+        let mut version_exact = false;
+        if let Some(version) = &self.version {
+            let version = match (version, &self.version_text) {
+                (VersionSpec::All(_) | VersionSpec::AnyOf(_), Some(text)) => conda_version_text(text),
+                _ => version.to_string(),
+            };
+            if version.contains(special) || matches!(self.version, Some(VersionSpec::Glob(_) | VersionSpec::Exact(_))) {
+                brackets.push(format!("version='{}'", version));
+            } else if version.starts_with("!=") || version.starts_with("~=") {
+                if self.build.is_some() {
+                    brackets.push(format!("version='{}'", version));
+                } else {
+                    write!(f, "{}", version)?;
+                }
+            } else if let Some(prefix) = version.strip_suffix(".*").or_else(|| version.strip_suffix('*')) {
+                write!(f, "={}", prefix)?;
+            } else if version.starts_with("==") {
+                write!(f, "{}", version)?;
+                version_exact = true;
+            } else {
+                write!(f, "=={}", version)?;
+                version_exact = true;
+            }
+        }
+        if let Some(build) = &self.build {
+            if build.contains(special) {
+                brackets.push(format!("build='{}'", build));
+            } else if build.contains('*') || !version_exact {
+                brackets.push(format!("build={}", build));
+            } else {
+                write!(f, "={}", build)?;
+            }
+        }
+
+        let quote_chars = [',', ' ', '=', '<', '>'];
+        if let Some(build_number) = &self.build_number {
+            brackets.push(bracket_entry("build_number", &build_number.to_string(), &quote_chars));
+        }
+        if !self.track_features.is_empty() {
+            let track_features = self.track_features.iter().cloned().collect::<Vec<_>>().join(" ");
+            brackets.push(bracket_entry("track_features", &track_features, &quote_chars));
+        }
+        if !self.features.is_empty() {
+            let features = self.features.iter().cloned().collect::<Vec<_>>().join(" ");
+            brackets.push(bracket_entry("features", &features, &quote_chars));
+        }
+        if let Some(md5) = &self.md5 {
+            brackets.push(bracket_entry("md5", md5, &quote_chars));
+        }
+        if let Some(license) = &self.license {
+            brackets.push(bracket_entry("license", license, &quote_chars));
+        }
+        if let Some(sha256) = &self.sha256 {
+            brackets.push(bracket_entry("sha256", sha256, &quote_chars));
+        }
+
+        if !brackets.is_empty() {
+            write!(f, "[{}]", brackets.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::repodata::Record;
+    use crate::version::{CompOp, VersionSpecParseError};
+    use super::super::{BuildNumberSpec, MatchSpecParseError};
+    use super::MatchSpec;
+
+    #[rstest_parametrize(spec, canonical,
+    case("numpy", "numpy"),
+    case("numpy *", "numpy"),
+    case("numpy * *", "numpy"),
+    case("vc 9.*", "vc=9"),
+    case("python >=3.6,<3.7.0a0", "python[version='>=3.6,<3.7.0a0']"),
+    case("python >= 3.6 , < 3.7.0a0", "python[version='>=3.6,<3.7.0a0']"),
+    case("numpy 1.8", "numpy==1.8"),
+    case("numpy=1.8", "numpy=1.8"),
+    case("numpy==1.8", "numpy==1.8"),
+    case("numpy 1.8*", "numpy=1.8"),
+    case("numpy=1.8=py36_0", "numpy==1.8=py36_0"),
+    case("numpy==1.8=py36_0", "numpy==1.8=py36_0"),
+    case("numpy 1.8 py36_0", "numpy==1.8=py36_0"),
+    case("numpy 1.8.* py36*", "numpy=1.8[build=py36*]"),
+    case("numpy >=1.8 py36_0", "numpy[version='>=1.8',build=py36_0]"),
+    case("numpy !=1.8", "numpy!=1.8"),
+    case("numpy ~=1.8", "numpy~=1.8"),
+    case("numpy ~=1.8 py36_0", "numpy[version='~=1.8',build=py36_0]"),
+    case("numpy 1.*.3", "numpy[version='1.*.3']"),
+    case("numpy ===1.8", "numpy[version='===1.8']"),
+    case("numpy 2.7|>=3.6", "numpy[version='2.7|>=3.6']"),
+    case("numpy 1.8|1.9", "numpy[version='1.8|1.9']"),
+    case("numpy ==1.8|>2", "numpy[version='1.8|>2']"),
+    case("numpy =1.8|1.9", "numpy[version='=1.8|1.9']"),
+    case("numpy 1.8.0|=1.9", "numpy[version='1.8.0|=1.9']"),
+    case("numpy 1.8,<2", "numpy[version='1.8,<2']"),
+    case("numpy >=1.8,1.9", "numpy[version='>=1.8,1.9']"),
+    case("numpy 1.8.*|1.9*", "numpy[version='1.8.*|1.9.*']"),
+    case("numpy >=1.8|1.9,<2", "numpy[version='>=1.8|1.9,<2']"),
+    case("numpy 1.8|1.9 py*", "numpy[version='1.8|1.9',build=py*]"),
+    case("numpy[version='1.8 | 1.9*']", "numpy[version='1.8|1.9.*']"),
+    case("conda-forge::numpy", "conda-forge::numpy"),
+    case("conda-forge::numpy[version='>=1.8',build=py36*]", "conda-forge::numpy[version='>=1.8',build=py36*]"),
+    case("pkgs/main/win-64::openssl 1.1.1*", "pkgs/main/win-64::openssl=1.1.1"),
+    case("https://conda.anaconda.org/conda-forge::numpy", "https://conda.anaconda.org/conda-forge::numpy"),
+    case("ns:numpy", "ns:numpy"),
+    case("conda-forge:ns:numpy", "conda-forge:ns:numpy"),
+    case("::numpy", "numpy"),
+    case("numpy[subdir=linux-64]", "numpy[subdir=linux-64]"),
+    case("numpy[channel=conda-forge/linux-64]", "conda-forge/linux-64::numpy"),
+    case("numpy 1.8[version=1.9]", "numpy==1.9"),
+    case("numpy[version=\">=1.8\" build='py36*']", "numpy[version='>=1.8',build=py36*]"),
+    case("numpy[build_number=3]", "numpy[build_number=3]"),
+    case("numpy[build_number='>=3']", "numpy[build_number='>=3']"),
+    case("numpy[md5=abc, sha256=def]", "numpy[md5=abc,sha256=def]"),
+    case("numpy[features='mkl debug']", "numpy[features='debug mkl']"),
+    case("numpy[track_features=mkl,license=BSD]", "numpy[track_features=mkl,license=BSD]"),
+    case("numpy[license='BSD 3-Clause']", "numpy[license='BSD 3-Clause']"),
+    case("*[md5=abc]", "*[md5=abc]"),
+    case("  numpy >=1.8  # a comment", "numpy[version='>=1.8']"),
+    )]
+    fn canonical_form(spec: &str, canonical: &str) {
+        let parsed: MatchSpec = spec.parse().unwrap();
+        assert_eq!(parsed.to_string(), canonical);
+        // The canonical form parses to the same spec
+        assert_eq!(canonical.parse::<MatchSpec>().unwrap(), parsed);
+    }
+
+    #[test]
+    fn fields() {
+        let spec: MatchSpec = "conda-forge/osx-64:ns:numpy >=1.8,<2 py36*[build_number='>=2', md5=abc, \
+            sha256=def, features='mkl debug', track_features=blas, license=BSD]".parse().unwrap();
+        assert_eq!(spec.channel(), Some("conda-forge"));
+        assert_eq!(spec.subdir(), Some("osx-64"));
+        assert_eq!(spec.namespace(), Some("ns"));
+        assert_eq!(spec.name(), "numpy");
+        assert_eq!(spec.version(), Some(&">=1.8,<2".parse().unwrap()));
+        assert_eq!(spec.build(), Some("py36*"));
+        assert_eq!(spec.build_number(), Some(&BuildNumberSpec::new(CompOp::Ge, 2)));
+        assert_eq!(spec.md5(), Some("abc"));
+        assert_eq!(spec.sha256(), Some("def"));
+        assert_eq!(spec.features(), &["debug", "mkl"].iter().map(|f| f.to_string()).collect::<BTreeSet<_>>());
+        assert_eq!(spec.track_features().len(), 1);
+        assert_eq!(spec.license(), Some("BSD"));
+    }
+
+    #[rstest_parametrize(spec, error,
+    case("", MatchSpecParseError::MissingName { offset: 0 }),
+    case(">=1.8", MatchSpecParseError::MissingName { offset: 0 }),
+    case("conda-forge::>=1.8", MatchSpecParseError::MissingName { offset: 13 }),
+    case("numpy >=1.8,,<2", MatchSpecParseError::InvalidVersion {
+        offset: 6,
+        error: VersionSpecParseError::EmptyConstraint { offset: 6 },
+    }),
+    case("numpy[version='>=1.8,,<2']", MatchSpecParseError::InvalidVersion {
+        offset: 15,
+        error: VersionSpecParseError::EmptyConstraint { offset: 6 },
+    }),
+    case("numpy[version=1.8", MatchSpecParseError::UnbalancedBracket { offset: 5 }),
+    case("numpy[version]", MatchSpecParseError::InvalidBracketEntry { offset: 6 }),
+    case("numpy[version=]", MatchSpecParseError::InvalidBracketEntry { offset: 6 }),
+    case("numpy[md5=abc,version='1.8]", MatchSpecParseError::InvalidBracketEntry { offset: 14 }),
+    case("numpy[color=blue]", MatchSpecParseError::UnknownKey { key: "color".to_string(), offset: 6 }),
+    case("numpy[build_number=x]", MatchSpecParseError::InvalidBuildNumber { offset: 19 }),
+    )]
+    fn invalid(spec: &str, error: MatchSpecParseError) {
+        assert_eq!(spec.parse::<MatchSpec>().unwrap_err(), error);
+    }
+
+    #[test]
+    fn builder() {
+        let spec = MatchSpec::new("numpy")
+            .with_channel(Some("conda-forge"))
+            .with_version(Some("1.8.*".parse().unwrap()))
+            .with_build(Some("*"))
+            .with_build_number(Some(BuildNumberSpec::new(CompOp::Eq, 1)));
+        assert_eq!(spec.build(), None);
+        assert_eq!(spec.to_string(), "conda-forge::numpy=1.8[build_number=1]");
+    }
+
+    #[rstest_parametrize(spec, expected,
+    case("numpy", true),
+    case("num*", true),
+    case("*", true),
+    case("scipy", false),
+    case("numpy 1.11.*", true),
+    case("numpy >=1.12", false),
+    case("numpy 1.11.3 py36*", true),
+    case("numpy 1.11.3 py37*", false),
+    case("numpy[build_number=6]", true),
+    case("numpy[build_number='>6']", false),
+    case("numpy[md5=E7B1F1C2A3D4E5F60718293A4B5C6D7E]", true),
+    case("numpy[md5=00000000000000000000000000000000]", false),
+    case("numpy[sha256=abc]", false),
+    case("numpy[subdir=win-64]", true),
+    case("numpy[subdir=linux-64]", false),
+    case("numpy[features=mkl]", true),
+    case("numpy[features='mkl debug']", false),
+    case("numpy[track_features=mkl]", false),
+    case("numpy[license=BSD]", true),
+    case("numpy[license=MIT]", false),
+    )]
+    fn matches(spec: &str, expected: bool) {
+        let record: Record = serde_json::from_str(r#"{
+            "build": "py36hd5b3723_6", "build_number": 6, "depends": [], "features": "mkl",
+            "license": "BSD", "md5": "e7b1f1c2a3d4e5f60718293a4b5c6d7e", "name": "numpy",
+            "sha256": "0796c952afdd59db7b25ba7a99ae3b2897bfa8078a7568de86022c4a1061b050",
+            "size": 3857251, "subdir": "win-64", "timestamp": 1568210574512, "version": "1.11.3"
+        }"#).unwrap();
+        assert_eq!(spec.parse::<MatchSpec>().unwrap().matches(&record), expected);
+    }
+
+    #[test]
+    fn parse_repodata_depends() {
+        // Every dependency in the bundled repodata is a valid match spec
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests/data/current_repodata.json");
+        let repodata: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let mut count = 0;
+        for section in &["packages", "packages.conda"] {
+            for record in repodata[section].as_object().unwrap().values() {
+                for depends in record["depends"].as_array().unwrap() {
+                    let depends = depends.as_str().unwrap();
+                    let spec: MatchSpec = depends.parse().unwrap();
+                    assert_eq!(spec.to_string().parse::<MatchSpec>().unwrap(), spec, "{}", depends);
+                    count += 1;
+                }
+            }
+        }
+        assert!(count > 0);
+    }
+}
@@ -0,0 +1,406 @@
+//! Match spec merging module, which provides `MatchSpec::merge` and the `MergeError` and
+//! `SpecConflict` errors.
+//!
+//! When several packages depend on the same package, their match specs are merged into a single
+//! spec that is satisfied by the records satisfying all of them, the way conda does when it
+//! collects the constraints on a package.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+
+use crate::version::version_spec::glob_match;
+use super::build_number::BuildNumberSpec;
+use super::match_spec::MatchSpec;
+
+/// The constraints of two match specs on a field that contradict each other, so that no record
+/// satisfies both specs.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::MatchSpec;
+///
+/// let a: MatchSpec = "conda-forge::openssl".parse().unwrap();
+/// let b: MatchSpec = "pkgs/main::openssl".parse().unwrap();
+/// let err = a.merge(&b).unwrap_err();
+///
+/// assert_eq!(err.field(), "channel");
+/// assert_eq!(err.to_string(), "conflicting channel constraints 'conda-forge' and 'pkgs/main'");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecConflict {
+    field: &'static str,
+    left: String,
+    right: String,
+}
+
+impl SpecConflict {
+    fn new<T: fmt::Display + ?Sized>(field: &'static str, left: &T, right: &T) -> SpecConflict {
+        SpecConflict { field, left: left.to_string(), right: right.to_string() }
+    }
+
+    /// Get the name of the conflicting field, such as `version` or `build`.
+    pub fn field(&self) -> &str {
+        self.field
+    }
+
+    /// Get the constraint of the spec that was merged into.
+    pub fn left(&self) -> &str {
+        &self.left
+    }
+
+    /// Get the constraint of the spec that was merged.
+    pub fn right(&self) -> &str {
+        &self.right
+    }
+}
+
+impl fmt::Display for SpecConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "conflicting {} constraints '{}' and '{}'", self.field, self.left, self.right)
+    }
+}
+
+impl Error for SpecConflict {}
+
+/// Error returned when two match specs can't be merged.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{MatchSpec, MergeError};
+///
+/// let a: MatchSpec = "openssl * py*".parse().unwrap();
+/// let b: MatchSpec = "openssl * *_0".parse().unwrap();
+///
+/// match a.merge(&b).unwrap_err() {
+///     MergeError::Unrepresentable { field, .. } => assert_eq!(field, "build"),
+///     other => panic!("unexpected error {:?}", other),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    /// The constraints of the specs on a field contradict each other, so no record satisfies
+    /// both specs.
+    Conflict(SpecConflict),
+
+    /// Some records satisfy the constraints of both specs on a field, but a single match spec
+    /// can't express them, such as the build globs `py*` and `*_0`, or the build numbers `>=1`
+    /// and `<3`.
+    Unrepresentable {
+        field: &'static str,
+        left: String,
+        right: String,
+    },
+}
+
+impl MergeError {
+    fn unrepresentable<T: fmt::Display + ?Sized>(field: &'static str, left: &T, right: &T) -> MergeError {
+        MergeError::Unrepresentable { field, left: left.to_string(), right: right.to_string() }
+    }
+
+    /// Get the name of the field that couldn't be merged, such as `version` or `build`.
+    pub fn field(&self) -> &str {
+        match self {
+            MergeError::Conflict(conflict) => conflict.field(),
+            MergeError::Unrepresentable { field, .. } => field,
+        }
+    }
+}
+
+impl From<SpecConflict> for MergeError {
+    fn from(conflict: SpecConflict) -> MergeError {
+        MergeError::Conflict(conflict)
+    }
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeError::Conflict(conflict) => write!(f, "{}", conflict),
+            MergeError::Unrepresentable { field, left, right } =>
+                write!(f, "{} constraints '{}' and '{}' can't be merged into one", field, left, right),
+        }
+    }
+}
+
+impl Error for MergeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MergeError::Conflict(conflict) => Some(conflict),
+            MergeError::Unrepresentable { .. } => None,
+        }
+    }
+}
+
+/// Merge two optional constraints that must be equal when both are given.
+fn merge_equal<'s>(
+    field: &'static str,
+    left: Option<&'s str>,
+    right: Option<&'s str>,
+    equal: fn(&str, &str) -> bool,
+) -> Result<Option<&'s str>, SpecConflict> {
+    match (left, right) {
+        (Some(left), Some(right)) if !equal(left, right) => Err(SpecConflict::new(field, left, right)),
+        (left, right) => Ok(left.or(right)),
+    }
+}
+
+/// Check whether every build string matching the glob `narrow` also matches the glob `wide`.
+///
+/// This holds when `wide` matches `narrow` itself, since each `*` of `narrow` is then swallowed
+/// by a `*` of `wide`.  Some globs with a common subset don't pass this check, such as `py*` and
+/// `*_0`.
+fn glob_is_subset(narrow: &str, wide: &str) -> bool {
+    glob_match(wide, narrow)
+}
+
+/// Check whether some build string matches both globs `a` and `b`.
+fn globs_intersect(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    // Whether the first `i` characters of `a` and the first `j` characters of `b` can match the
+    // same text
+    let mut reachable = vec![vec![false; b.len() + 1]; a.len() + 1];
+    reachable[0][0] = true;
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            if !reachable[i][j] {
+                continue;
+            }
+            // A `*` may match nothing, or swallow the next character of the other glob
+            if i < a.len() && a[i] == b'*' {
+                reachable[i + 1][j] = true;
+            }
+            if j < b.len() && b[j] == b'*' {
+                reachable[i][j + 1] = true;
+            }
+            if i < a.len() && j < b.len() {
+                match (a[i], b[j]) {
+                    (b'*', _) => reachable[i][j + 1] = true,
+                    (_, b'*') => reachable[i + 1][j] = true,
+                    (x, y) if x == y => reachable[i + 1][j + 1] = true,
+                    _ => {},
+                }
+            }
+        }
+    }
+    reachable[a.len()][b.len()]
+}
+
+/// Get a build number in every range that the numbers of `a` and `b` split the build numbers
+/// into, which all behave the same for both specs.
+fn build_number_probes(a: &BuildNumberSpec, b: &BuildNumberSpec) -> impl Iterator<Item = u64> {
+    IntoIterator::into_iter([a.number(), b.number()])
+        .flat_map(|number| [number.saturating_sub(1), number, number.saturating_add(1)])
+        .chain([0, u64::MAX])
+}
+
+/// Check whether every build number satisfying `narrow` also satisfies `wide`.
+fn build_number_is_subset(narrow: &BuildNumberSpec, wide: &BuildNumberSpec) -> bool {
+    build_number_probes(narrow, wide).all(|number| !narrow.contains(number) || wide.contains(number))
+}
+
+/// Check whether some build number satisfies both `a` and `b`.
+fn build_numbers_intersect(a: &BuildNumberSpec, b: &BuildNumberSpec) -> bool {
+    build_number_probes(a, b).any(|number| a.contains(number) && b.contains(number))
+}
+
+impl MatchSpec {
+    /// Merge this spec with `other` into a spec that is satisfied by the records that satisfy
+    /// both.
+    ///
+    /// The version specs are intersected.  Other constraints given by only one spec are kept,
+    /// while constraints given by both specs must agree: names, channels, subdirs, namespaces,
+    /// licenses, hashes and features must be equal, and of two build globs or build number
+    /// constraints one must imply the other.  Otherwise, or when no version satisfies both
+    /// version specs, a `MergeError::Conflict` describing the first contradiction is returned.
+    /// Build globs or build number constraints that some records satisfy together, but that
+    /// don't imply one another, give a `MergeError::Unrepresentable`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::MatchSpec;
+    ///
+    /// let a: MatchSpec = "openssl >=1.1.1".parse().unwrap();
+    /// let b: MatchSpec = "conda-forge::openssl <1.1.2a".parse().unwrap();
+    ///
+    /// assert_eq!(a.merge(&b).unwrap().to_string(), "conda-forge::openssl[version='>=1.1.1,<1.1.2a']");
+    ///
+    /// let c: MatchSpec = "openssl <1.1".parse().unwrap();
+    /// assert_eq!(a.merge(&c).unwrap_err().field(), "version");
+    /// ```
+    pub fn merge(&self, other: &MatchSpec) -> Result<MatchSpec, MergeError> {
+        let name = match (self.name(), other.name()) {
+            ("*", name) | (name, "*") => name,
+            (left, right) if left == right => left,
+            (left, right) => return Err(SpecConflict::new("name", left, right).into()),
+        };
+
+        let exact = |a: &str, b: &str| a == b;
+        let ignore_case = |a: &str, b: &str| a.eq_ignore_ascii_case(b);
+        let channel = merge_equal("channel", self.channel(), other.channel(), exact)?;
+        let subdir = merge_equal("subdir", self.subdir(), other.subdir(), exact)?;
+        let namespace = merge_equal("namespace", self.namespace(), other.namespace(), exact)?;
+        let md5 = merge_equal("md5", self.md5(), other.md5(), ignore_case)?;
+        let sha256 = merge_equal("sha256", self.sha256(), other.sha256(), ignore_case)?;
+        let license = merge_equal("license", self.license(), other.license(), exact)?;
+
+        let version = match (self.version(), other.version()) {
+            (Some(left), Some(right)) => {
+                let intersection = left.intersect(right);
+                if intersection.is_empty() {
+                    return Err(SpecConflict::new("version", left, right).into());
+                }
+                Some(intersection)
+            },
+            (left, right) => left.or(right).cloned(),
+        };
+
+        let build = match (self.build(), other.build()) {
+            (Some(left), Some(right)) if glob_is_subset(left, right) => Some(left),
+            (Some(left), Some(right)) if glob_is_subset(right, left) => Some(right),
+            (Some(left), Some(right)) if globs_intersect(left, right) =>
+                return Err(MergeError::unrepresentable("build", left, right)),
+            (Some(left), Some(right)) => return Err(SpecConflict::new("build", left, right).into()),
+            (left, right) => left.or(right),
+        };
+
+        let build_number = match (self.build_number(), other.build_number()) {
+            (Some(left), Some(right)) if build_number_is_subset(left, right) => Some(left),
+            (Some(left), Some(right)) if build_number_is_subset(right, left) => Some(right),
+            (Some(left), Some(right)) if build_numbers_intersect(left, right) =>
+                return Err(MergeError::unrepresentable("build_number", left, right)),
+            (Some(left), Some(right)) => return Err(SpecConflict::new("build_number", left, right).into()),
+            (left, right) => left.or(right),
+        };
+
+        let join = |features: &BTreeSet<String>| features.iter().cloned().collect::<Vec<_>>().join(" ");
+        let features = match (self.features(), other.features()) {
+            (left, right) if left.is_empty() || left == right => right,
+            (left, right) if right.is_empty() => left,
+            (left, right) => return Err(SpecConflict::new("features", &join(left), &join(right)).into()),
+        };
+        let track_features = match (self.track_features(), other.track_features()) {
+            (left, right) if left.is_empty() || left == right => right,
+            (left, right) if right.is_empty() => left,
+            (left, right) => return Err(SpecConflict::new("track_features", &join(left), &join(right)).into()),
+        };
+
+        Ok(MatchSpec::new(name)
+            .with_channel(channel)
+            .with_subdir(subdir)
+            .with_namespace(namespace)
+            .with_version(version)
+            .with_build(build)
+            .with_build_number(build_number.cloned())
+            .with_md5(md5)
+            .with_sha256(sha256)
+            .with_features(features.iter().cloned())
+            .with_track_features(track_features.iter().cloned())
+            .with_license(license))
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use crate::match_spec::{MatchSpec, MergeError};
+
+    #[rstest_parametrize(a, b, merged,
+    case("openssl", "openssl", "openssl"),
+    case("openssl >=1.1.1", "openssl <1.1.2a", "openssl[version='>=1.1.1,<1.1.2a']"),
+    // Pre-releases such as `1.1.1a` start with `1.1.1`, but sort before it
+    case("openssl >=1.1.1", "openssl 1.1.1*", "openssl[version='>=1.1.1,1.1.1.*']"),
+    case("openssl 1.1.*", "openssl >=1.0", "openssl=1.1"),
+    case("openssl", "openssl 1.1.1d h0c8e037_0", "openssl==1.1.1d=h0c8e037_0"),
+    case("*[md5=abc]", "openssl", "openssl[md5=abc]"),
+    case("conda-forge::openssl", "openssl[subdir=win-64]", "conda-forge/win-64::openssl"),
+    case("openssl * h*", "openssl * h0c8e037_0", "openssl[build=h0c8e037_0]"),
+    case("openssl * h0*", "openssl * h*", "openssl[build=h0*]"),
+    case("openssl[build_number='>=1']", "openssl[build_number=3]", "openssl[build_number=3]"),
+    case("openssl[build_number='>=1']", "openssl[build_number='>2']", "openssl[build_number='>2']"),
+    case("openssl[features=mkl]", "openssl", "openssl[features=mkl]"),
+    case("openssl[track_features=vc9]", "openssl[track_features=vc9]", "openssl[track_features=vc9]"),
+    case("openssl[license=MIT]", "openssl", "openssl[license=MIT]"),
+    )]
+    fn merge(a: &str, b: &str, merged: &str) {
+        let a: MatchSpec = a.parse().unwrap();
+        let b: MatchSpec = b.parse().unwrap();
+        assert_eq!(a.merge(&b).unwrap().to_string(), merged);
+        assert_eq!(b.merge(&a).unwrap().to_string().parse::<MatchSpec>().unwrap(), merged.parse().unwrap());
+    }
+
+    #[test]
+    fn merge_hashes_ignoring_case() {
+        let a: MatchSpec = "openssl[md5=ABC]".parse().unwrap();
+        let b: MatchSpec = "openssl[md5=abc]".parse().unwrap();
+        assert_eq!(a.merge(&b).unwrap().md5(), Some("ABC"));
+        assert_eq!(b.merge(&a).unwrap().md5(), Some("abc"));
+    }
+
+    #[rstest_parametrize(a, b, field, message,
+    case("openssl", "numpy", "name", "conflicting name constraints 'openssl' and 'numpy'"),
+    case("conda-forge::openssl", "pkgs/main::openssl", "channel",
+        "conflicting channel constraints 'conda-forge' and 'pkgs/main'"),
+    case("openssl[subdir=win-64]", "openssl[subdir=win-32]", "subdir",
+        "conflicting subdir constraints 'win-64' and 'win-32'"),
+    case("openssl >=1.1.1", "openssl <1.1", "version", "conflicting version constraints '>=1.1.1' and '<1.1'"),
+    case("openssl 1.1.1d", "openssl 1.1.1c", "version", "conflicting version constraints '==1.1.1d' and '==1.1.1c'"),
+    case("openssl * h0c8e037_0", "openssl * he774522_0", "build",
+        "conflicting build constraints 'h0c8e037_0' and 'he774522_0'"),
+    case("openssl[build_number='>=3']", "openssl[build_number='<3']", "build_number",
+        "conflicting build_number constraints '>=3' and '<3'"),
+    case("openssl[build_number=1]", "openssl[build_number=2]", "build_number",
+        "conflicting build_number constraints '1' and '2'"),
+    case("openssl[md5=abc]", "openssl[md5=def]", "md5", "conflicting md5 constraints 'abc' and 'def'"),
+    case("openssl[features=mkl]", "openssl[features=nomkl]", "features",
+        "conflicting features constraints 'mkl' and 'nomkl'"),
+    case("openssl[license=MIT]", "openssl[license=BSD]", "license", "conflicting license constraints 'MIT' and 'BSD'"),
+    )]
+    fn conflict(a: &str, b: &str, field: &str, message: &str) {
+        let a: MatchSpec = a.parse().unwrap();
+        let b: MatchSpec = b.parse().unwrap();
+        match a.merge(&b).unwrap_err() {
+            MergeError::Conflict(conflict) => {
+                assert_eq!(conflict.field(), field);
+                assert_eq!(conflict.to_string(), message);
+            },
+            other => panic!("unexpected error {:?}", other),
+        }
+        assert_eq!(b.merge(&a).unwrap_err().field(), field);
+    }
+
+    #[rstest_parametrize(a, b, field, message,
+    case("openssl * py*", "openssl * *_0", "build", "build constraints 'py*' and '*_0' can't be merged into one"),
+    case("openssl * *a*", "openssl * *b*", "build", "build constraints '*a*' and '*b*' can't be merged into one"),
+    case("openssl[build_number='>=1']", "openssl[build_number='<3']", "build_number",
+        "build_number constraints '>=1' and '<3' can't be merged into one"),
+    case("openssl[build_number='!=1']", "openssl[build_number='!=2']", "build_number",
+        "build_number constraints '!=1' and '!=2' can't be merged into one"),
+    )]
+    fn unrepresentable(a: &str, b: &str, field: &str, message: &str) {
+        let a: MatchSpec = a.parse().unwrap();
+        let b: MatchSpec = b.parse().unwrap();
+        let err = a.merge(&b).unwrap_err();
+        assert!(matches!(err, MergeError::Unrepresentable { .. }), "{:?}", err);
+        assert_eq!(err.field(), field);
+        assert_eq!(err.to_string(), message);
+        assert!(matches!(b.merge(&a).unwrap_err(), MergeError::Unrepresentable { .. }));
+    }
+
+    #[rstest_parametrize(a, b, expected,
+    case("py*", "*_0", true),
+    case("*a*", "*b*", true),
+    case("py*", "np*", false),
+    case("h0c8e037_0", "h0c8e037_0", true),
+    case("h0c8e037_0", "he774522_0", false),
+    case("*_1", "*_0", false),
+    case("a*b", "*", true),
+    )]
+    fn globs_intersect(a: &str, b: &str, expected: bool) {
+        assert_eq!(super::globs_intersect(a, b), expected);
+        assert_eq!(super::globs_intersect(b, a), expected);
+    }
+}
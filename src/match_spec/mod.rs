@@ -0,0 +1,10 @@
+pub mod build_number;
+#[allow(clippy::module_inception)]
+pub mod match_spec;
+pub mod merge;
+pub mod parse_error;
+
+pub use self::build_number::BuildNumberSpec;
+pub use self::match_spec::MatchSpec;
+pub use self::merge::{MergeError, SpecConflict};
+pub use self::parse_error::MatchSpecParseError;
@@ -0,0 +1,123 @@
+//! Match spec parse error module.
+//!
+//! This module provides the `MatchSpecParseError` enum, which is returned when a match spec such
+//! as `conda-forge::numpy[version='>=1.8']` can't be parsed.  Every variant carries the byte offset
+//! into the match spec string at which the problem was found.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::version::VersionSpecParseError;
+
+/// Error returned when a match spec string can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchSpecParseError {
+    /// The match spec has no package name, such as `>=1.2`.
+    MissingName {
+        offset: usize,
+    },
+
+    /// The version spec is invalid.  The offset is that of the version spec in the match spec
+    /// string, while the offset of the version spec error is relative to the version spec, with
+    /// spaces removed.
+    InvalidVersion {
+        offset: usize,
+        error: VersionSpecParseError,
+    },
+
+    /// The build number constraint is not a number, optionally preceded by a comparison
+    /// operator, such as `>=2`.
+    InvalidBuildNumber {
+        offset: usize,
+    },
+
+    /// The opening `[` of the bracket section isn't closed by a `]` at the end of the spec.
+    UnbalancedBracket {
+        offset: usize,
+    },
+
+    /// An entry of the bracket section is not a `key=value` pair, or its value is empty.
+    InvalidBracketEntry {
+        offset: usize,
+    },
+
+    /// The key of a bracket entry is not a field of a match spec.
+    UnknownKey {
+        key: String,
+        offset: usize,
+    },
+}
+
+impl MatchSpecParseError {
+    /// Get the byte offset into the match spec string at which the error was found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::MatchSpec;
+    ///
+    /// let err = "numpy[color=blue]".parse::<MatchSpec>().unwrap_err();
+    ///
+    /// assert_eq!(err.offset(), 6);
+    /// ```
+    pub fn offset(&self) -> usize {
+        match *self {
+            MatchSpecParseError::MissingName { offset } => offset,
+            MatchSpecParseError::InvalidVersion { offset, .. } => offset,
+            MatchSpecParseError::InvalidBuildNumber { offset } => offset,
+            MatchSpecParseError::UnbalancedBracket { offset } => offset,
+            MatchSpecParseError::InvalidBracketEntry { offset } => offset,
+            MatchSpecParseError::UnknownKey { offset, .. } => offset,
+        }
+    }
+}
+
+impl fmt::Display for MatchSpecParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MatchSpecParseError::MissingName { offset } =>
+                write!(f, "no package name found (at offset {})", offset),
+            MatchSpecParseError::InvalidVersion { offset, ref error } =>
+                write!(f, "invalid version spec (at offset {}): {}", offset, error),
+            MatchSpecParseError::InvalidBuildNumber { offset } =>
+                write!(f, "invalid build number (at offset {})", offset),
+            MatchSpecParseError::UnbalancedBracket { offset } =>
+                write!(f, "unbalanced bracket (at offset {})", offset),
+            MatchSpecParseError::InvalidBracketEntry { offset } =>
+                write!(f, "key-value mismatch in brackets (at offset {})", offset),
+            MatchSpecParseError::UnknownKey { ref key, offset } =>
+                write!(f, "unknown key '{}' (at offset {})", key, offset),
+        }
+    }
+}
+
+impl Error for MatchSpecParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            MatchSpecParseError::InvalidVersion { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use super::MatchSpecParseError;
+    use crate::version::VersionSpecParseError;
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            format!("{}", MatchSpecParseError::InvalidVersion {
+                offset: 6,
+                error: VersionSpecParseError::EmptyConstraint { offset: 5 },
+            }),
+            "invalid version spec (at offset 6): empty version constraint (at offset 5)",
+        );
+        assert_eq!(
+            format!("{}", MatchSpecParseError::UnknownKey { key: "color".to_string(), offset: 6 }),
+            "unknown key 'color' (at offset 6)",
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! Borrowed repodata module, which provides the `RepodataRef` and `RecordRef` structs.
+//!
+//! The repodata of large channels, such as `conda-forge/linux-64`, is hundreds of megabytes of
+//! JSON.  Reading it into a `Repodata` allocates a string for every name, version, build, hash
+//! and dependency of every record.  A `RepodataRef` instead borrows those strings from the JSON
+//! it was parsed from, which is usually a `RepodataFile` mapped into memory, and only allocates
+//! for strings that contain JSON escapes.
+//!
+//! Only the fields needed to select packages are read; the others are skipped.  Use `Repodata`
+//! when all fields are needed, or when the repodata is to be written back out.
+
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+use serde::de::{Deserialize, Deserializer, Error, Visitor};
+use serde_derive::Deserialize;
+
+use crate::version::{Version, VersionParseError};
+use super::error::RepodataError;
+
+/// A JSON string, borrowed from the JSON unless it contains escapes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct JsonStr<'a>(Cow<'a, str>);
+
+impl<'a> JsonStr<'a> {
+    /// Check whether the string is borrowed from the JSON.
+    #[cfg(test)]
+    fn is_borrowed(&self) -> bool {
+        match self.0 {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        }
+    }
+}
+
+impl<'a> Deref for JsonStr<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> Borrow<str> for JsonStr<'a> {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for JsonStr<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<JsonStr<'a>, D::Error> {
+        struct JsonStrVisitor<'a>(PhantomData<&'a ()>);
+
+        impl<'de: 'a, 'a> Visitor<'de> for JsonStrVisitor<'a> {
+            type Value = JsonStr<'a>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a string")
+            }
+
+            fn visit_borrowed_str<E: Error>(self, value: &'de str) -> Result<JsonStr<'a>, E> {
+                Ok(JsonStr(Cow::Borrowed(value)))
+            }
+
+            fn visit_str<E: Error>(self, value: &str) -> Result<JsonStr<'a>, E> {
+                Ok(JsonStr(Cow::Owned(value.to_string())))
+            }
+
+            fn visit_string<E: Error>(self, value: String) -> Result<JsonStr<'a>, E> {
+                Ok(JsonStr(Cow::Owned(value)))
+            }
+        }
+
+        deserializer.deserialize_str(JsonStrVisitor(PhantomData))
+    }
+}
+
+/// A package record borrowed from the repodata JSON.
+///
+/// Only the fields needed to select packages are read.  See `Record` for the full record.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{RepodataFile, Version};
+///
+/// let file = RepodataFile::open("tests/data/current_repodata.json").unwrap();
+/// let repodata = file.parse().unwrap();
+/// let record = repodata.package("aiohttp-3.6.1-py36he774522_0.tar.bz2").unwrap();
+///
+/// assert_eq!(record.name(), "aiohttp");
+/// assert_eq!(record.parse_version().unwrap(), Version::from("3.6.1").unwrap());
+/// assert!(record.depends().any(|depends| depends == "python >=3.6,<3.7.0a0"));
+/// ```
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RecordRef<'a> {
+    #[serde(borrow)]
+    build: JsonStr<'a>,
+    build_number: u64,
+    #[serde(borrow, default)]
+    constrains: Option<Vec<JsonStr<'a>>>,
+    #[serde(borrow)]
+    depends: Vec<JsonStr<'a>>,
+    #[serde(borrow, default)]
+    features: Option<JsonStr<'a>>,
+    #[serde(borrow, default)]
+    license: Option<JsonStr<'a>>,
+    #[serde(borrow)]
+    md5: JsonStr<'a>,
+    #[serde(borrow)]
+    name: JsonStr<'a>,
+    #[serde(borrow, default)]
+    sha256: Option<JsonStr<'a>>,
+    size: u64,
+    #[serde(borrow, default)]
+    subdir: Option<JsonStr<'a>>,
+    #[serde(default)]
+    timestamp: Option<u64>,
+    #[serde(borrow, default)]
+    track_features: Option<JsonStr<'a>>,
+    #[serde(borrow)]
+    version: JsonStr<'a>,
+}
+
+impl<'a> RecordRef<'a> {
+    /// Get the package name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the version string.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Parse the version string into a `Version` borrowing from this record.
+    pub fn parse_version(&self) -> Result<Version<'_>, VersionParseError> {
+        Version::from(&self.version)
+    }
+
+    /// Get the build string.
+    pub fn build(&self) -> &str {
+        &self.build
+    }
+
+    /// Get the build number.
+    pub fn build_number(&self) -> u64 {
+        self.build_number
+    }
+
+    /// Get the match specs of the packages this package depends on.
+    pub fn depends(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.depends.iter().map(|depends| &**depends)
+    }
+
+    /// Get the match specs constraining packages that are installed along with this package,
+    /// without being dependencies.  Missing and `null` constraints are empty.
+    pub fn constrains(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.constrains.as_deref().unwrap_or_default().iter().map(|constrains| &**constrains)
+    }
+
+    /// Get the MD5 hash of the package file.
+    pub fn md5(&self) -> &str {
+        &self.md5
+    }
+
+    /// Get the SHA-256 hash of the package file, which older repodata leaves out.
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+
+    /// Get the size of the package file, in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Get the subdir, such as `win-64`.
+    pub fn subdir(&self) -> Option<&str> {
+        self.subdir.as_deref()
+    }
+
+    /// Get the features, separated by spaces or commas.
+    pub fn features(&self) -> Option<&str> {
+        self.features.as_deref()
+    }
+
+    /// Get the track features, separated by spaces or commas.
+    pub fn track_features(&self) -> Option<&str> {
+        self.track_features.as_deref()
+    }
+
+    /// Get the license.
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+
+    /// Get the build time, in milliseconds since the Unix epoch.
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+}
+
+/// The `info` section of borrowed repodata.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+struct InfoRef<'a> {
+    #[serde(borrow, default)]
+    subdir: Option<JsonStr<'a>>,
+}
+
+/// The repodata of a channel subdir, borrowed from its JSON.
+///
+/// Strings are borrowed from the JSON, except for those containing escapes.  Only the fields
+/// needed to select packages are read.  See `Repodata` for the full repodata.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::RepodataRef;
+///
+/// let json = br#"{"info": {"subdir": "noarch"}, "packages": {}}"#;
+/// let repodata = RepodataRef::from_slice(json).unwrap();
+///
+/// assert_eq!(repodata.subdir(), Some("noarch"));
+/// assert_eq!(repodata.packages().count(), 0);
+/// ```
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RepodataRef<'a> {
+    #[serde(borrow, default)]
+    info: InfoRef<'a>,
+    #[serde(borrow, default)]
+    packages: HashMap<JsonStr<'a>, RecordRef<'a>>,
+    #[serde(borrow, default, rename = "packages.conda")]
+    packages_conda: HashMap<JsonStr<'a>, RecordRef<'a>>,
+    #[serde(borrow, default)]
+    removed: Vec<JsonStr<'a>>,
+}
+
+impl<'a> RepodataRef<'a> {
+    /// Parse repodata JSON, borrowing from `json`.
+    ///
+    /// An error is returned if `json` doesn't hold valid repodata.  Compressed repodata must be
+    /// decompressed first.
+    pub fn from_slice(json: &'a [u8]) -> Result<RepodataRef<'a>, RepodataError> {
+        RepodataRef::parse(json, None)
+    }
+
+    /// Parse repodata JSON, with `path` being the file it comes from, if any.
+    fn parse(json: &'a [u8], path: Option<&Path>) -> Result<RepodataRef<'a>, RepodataError> {
+        serde_json::from_slice(json).map_err(|error| RepodataError::json(path, error))
+    }
+
+    /// Get the subdir of the repodata, such as `win-64`.
+    pub fn subdir(&self) -> Option<&str> {
+        self.info.subdir.as_deref()
+    }
+
+    /// Get the `.tar.bz2` package records, with their file names.
+    pub fn packages(&self) -> impl ExactSizeIterator<Item = (&str, &RecordRef<'a>)> {
+        self.packages.iter().map(|(file_name, record)| (&**file_name, record))
+    }
+
+    /// Get the `.conda` package records, with their file names.
+    pub fn packages_conda(&self) -> impl ExactSizeIterator<Item = (&str, &RecordRef<'a>)> {
+        self.packages_conda.iter().map(|(file_name, record)| (&**file_name, record))
+    }
+
+    /// Get the record of the `.tar.bz2` or `.conda` package file with the given name.
+    pub fn package(&self, file_name: &str) -> Option<&RecordRef<'a>> {
+        self.packages.get(file_name).or_else(|| self.packages_conda.get(file_name))
+    }
+
+    /// Get the file names of the packages that were removed from the channel.
+    pub fn removed(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.removed.iter().map(|file_name| &**file_name)
+    }
+}
+
+/// A repodata JSON file mapped into memory, to be parsed into a `RepodataRef`.
+///
+/// The file must not be modified while it's mapped, since the parsed repodata borrows from it.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::RepodataFile;
+///
+/// let file = RepodataFile::open("tests/data/current_repodata.json").unwrap();
+/// let repodata = file.parse().unwrap();
+///
+/// assert_eq!(repodata.subdir(), Some("win-64"));
+/// assert_eq!(repodata.packages().count(), 237);
+/// ```
+pub struct RepodataFile {
+    path: PathBuf,
+    map: Option<Mmap>,
+}
+
+impl RepodataFile {
+    /// Map the repodata JSON file at `path` into memory.
+    ///
+    /// An error is returned if the file can't be opened or mapped.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<RepodataFile, RepodataError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|error| RepodataError::io(Some(path), error))?;
+        let len = file.metadata().map_err(|error| RepodataError::io(Some(path), error))?.len();
+        // Empty files can't be mapped on all platforms
+        let map = if len == 0 {
+            None
+        } else {
+            // Safe as long as the file isn't modified while it's mapped, as documented above
+            Some(unsafe { Mmap::map(&file) }.map_err(|error| RepodataError::io(Some(path), error))?)
+        };
+        Ok(RepodataFile { path: path.to_path_buf(), map })
+    }
+
+    /// Get the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the contents of the file.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.map.as_deref().unwrap_or(&[])
+    }
+
+    /// Parse the repodata, borrowing from the mapped file.
+    ///
+    /// An error is returned if the file doesn't hold valid repodata.
+    pub fn parse(&self) -> Result<RepodataRef<'_>, RepodataError> {
+        RepodataRef::parse(self.as_bytes(), Some(&self.path))
+    }
+}
+
+impl fmt::Debug for RepodataFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RepodataFile").field("path", &self.path).field("len", &self.as_bytes().len()).finish()
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use crate::repodata::{Repodata, RepodataError, read_repodata};
+    use crate::repodata::record::test_record;
+    use super::{RepodataFile, RepodataRef};
+
+    #[test]
+    fn same_as_owned() {
+        let repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+        let file = RepodataFile::open("tests/data/current_repodata.json").unwrap();
+        let borrowed = file.parse().unwrap();
+
+        assert_eq!(borrowed.subdir(), repodata.info().subdir());
+        assert_eq!(borrowed.packages().len(), repodata.packages().len());
+        assert_eq!(borrowed.packages_conda().len(), repodata.packages_conda().len());
+        assert_eq!(borrowed.removed().collect::<Vec<_>>(), repodata.removed());
+        for (file_name, record) in borrowed.packages().chain(borrowed.packages_conda()) {
+            let owned = &repodata.packages().get(file_name).or_else(|| repodata.packages_conda().get(file_name)).unwrap();
+            assert_eq!(record.name(), owned.name());
+            assert_eq!(record.version(), owned.version());
+            assert_eq!(record.build(), owned.build());
+            assert_eq!(record.build_number(), owned.build_number());
+            assert_eq!(record.depends().collect::<Vec<_>>(), owned.depends());
+            assert_eq!(record.constrains().collect::<Vec<_>>(), owned.constrains().unwrap_or_default());
+            assert_eq!(record.md5(), owned.md5());
+            assert_eq!(record.sha256(), owned.sha256());
+            assert_eq!(record.size(), owned.size());
+            assert_eq!(record.subdir(), owned.subdir());
+            assert_eq!(record.timestamp(), owned.timestamp());
+            assert_eq!(record.license(), owned.license());
+            assert_eq!(record.features(), owned.features());
+            assert_eq!(record.track_features(), owned.track_features());
+            assert!(record.parse_version().is_ok());
+        }
+    }
+
+    #[test]
+    fn borrows_unless_escaped() {
+        let mut record = test_record("a", "1.0", "0", 0);
+        record["depends"] = json!(["python >=3.6", "café"]);
+        record["about"] = json!({"home": "https://example.com"});
+        record["constrains"] = json!(null);
+        // An escaped string can't be borrowed from the input
+        let json = json!({"packages": {"a-1.0-0.tar.bz2": record}}).to_string().replace('é', "\\u00e9");
+        let repodata = RepodataRef::from_slice(json.as_bytes()).unwrap();
+        let owned: Repodata = serde_json::from_str(&json).unwrap();
+        let (file_name, record) = repodata.packages.iter().next().unwrap();
+        assert!(file_name.is_borrowed());
+        assert!(record.name.is_borrowed());
+        assert!(record.version.is_borrowed());
+        assert!(record.depends[0].is_borrowed());
+        assert!(!record.depends[1].is_borrowed());
+        assert_eq!(record.depends().collect::<Vec<_>>(), vec!["python >=3.6", "café"]);
+        assert_eq!(record.depends().collect::<Vec<_>>(), owned.packages()["a-1.0-0.tar.bz2"].depends());
+        assert_eq!(record.constrains().count(), 0);
+        assert_eq!(owned.packages()["a-1.0-0.tar.bz2"].constrains(), None);
+        assert_eq!(repodata.package("a-1.0-0.tar.bz2"), Some(record));
+        assert_eq!(repodata.package("a-1.0-0.conda"), None);
+    }
+
+    #[test]
+    fn errors() {
+        match RepodataRef::from_slice(br#"{"packages": {"a-1-0.tar.bz2": {"name": 1}}}"#) {
+            Err(RepodataError::Schema { line: 1, .. }) => {},
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let err = RepodataFile::open("tests/data/missing.json").unwrap_err();
+        assert_eq!(err.path(), Some(Path::new("tests/data/missing.json")));
+
+        let path = std::env::temp_dir().join(format!("libronda-{}-empty.json", std::process::id()));
+        std::fs::write(&path, b"").unwrap();
+        let file = RepodataFile::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        match file.unwrap().parse() {
+            Err(RepodataError::Json { ref path, .. }) => assert!(path.is_some()),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! Channel set module, which provides the `ChannelSet` and `MergedRepodata` structs.
+//!
+//! An environment draws packages from several channels, such as `pkgs/main` and `conda-forge`,
+//! and from two subdirs of each: the platform subdir, such as `win-64`, and `noarch`.  A
+//! `ChannelSet` holds the repodata of all of them, in the order of channel priority, and merges
+//! it into a `MergedRepodata` whose queries honor conda's channel priority modes.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::match_spec::MatchSpec;
+use crate::version::Version;
+use crate::version::version_spec::glob_match;
+use super::package_format::FormatPreference;
+use super::record::Record;
+use super::repodata::Repodata;
+
+/// How channel priority restricts the records that are considered for a package, like conda's
+/// `channel_priority` setting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ChannelPriority {
+    /// Only consider the records of the highest priority channel that has the package.
+    Strict,
+
+    /// Consider the records of all channels, preferring those of higher priority channels over
+    /// higher versions.  This is the default.
+    #[default]
+    Flexible,
+
+    /// Consider the records of all channels, preferring higher versions whatever their channel.
+    Disabled,
+}
+
+impl fmt::Display for ChannelPriority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ChannelPriority::Strict => "strict",
+            ChannelPriority::Flexible => "flexible",
+            ChannelPriority::Disabled => "disabled",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The repodata of several channels and subdirs, in the order of channel priority.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{ChannelPriority, ChannelSet, read_repodata};
+///
+/// let repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+/// let channels = ChannelSet::new(ChannelPriority::Strict)
+///     .with_repodata("https://repo.anaconda.com/pkgs/main", repodata);
+/// let merged = channels.merge();
+/// let record = &merged.records("openssl")[0];
+///
+/// assert_eq!(record.channel(), "https://repo.anaconda.com/pkgs/main");
+/// assert_eq!(record.subdir(), Some("win-64"));
+/// assert_eq!(record.file_name(), "openssl-1.1.1d-he774522_0.tar.bz2");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ChannelSet {
+    priority: ChannelPriority,
+    repodata: Vec<(String, Repodata)>,
+}
+
+impl ChannelSet {
+    /// Create an empty channel set with the given channel priority mode.
+    pub fn new(priority: ChannelPriority) -> ChannelSet {
+        ChannelSet { priority, repodata: Vec::new() }
+    }
+
+    /// Add the repodata of a subdir of the channel at `channel_url`, and return the channel set.
+    /// See `add`.
+    pub fn with_repodata(mut self, channel_url: &str, repodata: Repodata) -> ChannelSet {
+        self.add(channel_url, repodata);
+        self
+    }
+
+    /// Add the repodata of a subdir of the channel at `channel_url`.
+    ///
+    /// Channels have a lower priority than the channels added before them.  The subdirs of a
+    /// channel share its priority, so that the `noarch` subdir may be added after another
+    /// channel.  Trailing slashes of the URL are ignored.
+    pub fn add(&mut self, channel_url: &str, repodata: Repodata) {
+        self.repodata.push((channel_url.trim_end_matches('/').to_string(), repodata));
+    }
+
+    /// Get the channel priority mode.
+    pub fn priority(&self) -> ChannelPriority {
+        self.priority
+    }
+
+    /// Get the URLs of the channels, from the highest to the lowest priority.
+    pub fn channels(&self) -> Vec<&str> {
+        let mut channels: Vec<&str> = Vec::new();
+        for (channel, _) in &self.repodata {
+            if !channels.contains(&channel.as_str()) {
+                channels.push(channel);
+            }
+        }
+        channels
+    }
+
+    /// Merge the repodata of all channels and subdirs, using the `.conda` file of packages served
+    /// in both formats.
+    ///
+    /// Records whose version doesn't parse are kept, and ordered after the other records of their
+    /// channel, or after all other records when channel priority is disabled.
+    pub fn merge(&self) -> MergedRepodata<'_> {
+        let channels = self.channels();
+        let mut records: HashMap<&str, Vec<ChannelRecord>> = HashMap::new();
+        for (channel, repodata) in &self.repodata {
+            let rank = channels.iter().position(|url| url == channel).unwrap_or(0);
+            for (file_name, record) in repodata.records(FormatPreference::default()) {
+                let version = Version::from(&record.version).ok();
+                let subdir = repodata.info.subdir().or_else(|| record.subdir());
+                records.entry(&record.name).or_default()
+                    .push(ChannelRecord { channel, rank, subdir, file_name, version, record });
+            }
+        }
+        for records in records.values_mut() {
+            records.sort_by(|a, b| a.cmp(b, self.priority));
+        }
+        MergedRepodata { priority: self.priority, records }
+    }
+}
+
+/// A record of a `MergedRepodata`, tagged with its channel and subdir.
+#[derive(Clone, Debug)]
+pub struct ChannelRecord<'r> {
+    channel: &'r str,
+    rank: usize,
+    subdir: Option<&'r str>,
+    file_name: &'r str,
+    version: Option<Version<'r>>,
+    record: &'r Record,
+}
+
+impl<'r> ChannelRecord<'r> {
+    /// Get the URL of the channel of the record.
+    pub fn channel(&self) -> &'r str {
+        self.channel
+    }
+
+    /// Get the subdir of the record, such as `win-64` or `noarch`.
+    pub fn subdir(&self) -> Option<&'r str> {
+        self.subdir
+    }
+
+    /// Get the file name of the package.
+    pub fn file_name(&self) -> &'r str {
+        self.file_name
+    }
+
+    /// Get the parsed version of the record, or `None` if it doesn't parse.
+    pub fn version(&self) -> Option<&Version<'r>> {
+        self.version.as_ref()
+    }
+
+    /// Get the record.
+    pub fn record(&self) -> &'r Record {
+        self.record
+    }
+
+    /// Order the records by preference, the most preferred first.  Unparseable versions sort
+    /// below all others.
+    fn cmp(&self, other: &ChannelRecord, priority: ChannelPriority) -> Ordering {
+        let newest = other.version.cmp(&self.version)
+            .then_with(|| other.record.build_number.cmp(&self.record.build_number))
+            .then_with(|| other.record.timestamp().cmp(&self.record.timestamp()));
+        match priority {
+            ChannelPriority::Strict | ChannelPriority::Flexible => self.rank.cmp(&other.rank).then(newest),
+            ChannelPriority::Disabled => newest.then_with(|| self.rank.cmp(&other.rank)),
+        }
+    }
+}
+
+/// Check whether the channel at `url` is the channel named `channel` by a match spec, which is
+/// either its URL or its name, such as `conda-forge`.
+fn channel_matches(channel: &str, url: &str) -> bool {
+    let channel = channel.trim_end_matches('/');
+    url == channel || url.ends_with(&format!("/{}", channel))
+}
+
+/// The merged repodata of a `ChannelSet`.
+///
+/// Records are grouped by package name, and ordered by preference according to the channel
+/// priority mode.  With strict priority, only the records of the highest priority channel that
+/// has a package are returned.
+#[derive(Clone, Debug)]
+pub struct MergedRepodata<'r> {
+    priority: ChannelPriority,
+    records: HashMap<&'r str, Vec<ChannelRecord<'r>>>,
+}
+
+impl<'r> MergedRepodata<'r> {
+    /// Get the channel priority mode.
+    pub fn priority(&self) -> ChannelPriority {
+        self.priority
+    }
+
+    /// Get the names of the packages, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &'r str> + '_ {
+        self.records.keys().cloned()
+    }
+
+    /// Get the records of the package `name` allowed by the channel priority mode, the most
+    /// preferred first.
+    pub fn records(&self, name: &str) -> &[ChannelRecord<'r>] {
+        let records = self.records.get(name).map_or(&[][..], Vec::as_slice);
+        match (self.priority, records.first()) {
+            (ChannelPriority::Strict, Some(first)) => {
+                let end = records.iter().position(|record| record.rank != first.rank).unwrap_or(records.len());
+                &records[..end]
+            },
+            _ => records,
+        }
+    }
+
+    /// Find the records that match `spec`, the most preferred first.
+    ///
+    /// When the spec names a channel, the records of that channel are searched, regardless of
+    /// channel priority.  Otherwise the records allowed by the channel priority mode are.
+    pub fn find(&self, spec: &MatchSpec) -> Vec<&ChannelRecord<'r>> {
+        let mut names: Vec<&str> = self.names().filter(|name| glob_match(spec.name(), name)).collect();
+        names.sort_unstable();
+        let mut found = Vec::new();
+        for name in names {
+            let records = match spec.channel() {
+                Some(_) => self.records.get(name).map_or(&[][..], Vec::as_slice),
+                None => self.records(name),
+            };
+            found.extend(records.iter().filter(|record| {
+                spec.channel().is_none_or(|channel| channel_matches(channel, record.channel))
+                    && spec.matches(record.record)
+            }));
+        }
+        found
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::repodata::{Repodata, read_repodata};
+    use crate::repodata::record::test_record;
+    use super::{ChannelPriority, ChannelSet};
+
+    const MAIN: &str = "https://repo.anaconda.com/pkgs/main";
+    const FORGE: &str = "https://conda.anaconda.org/conda-forge";
+
+    fn conda_forge(subdir: &str, packages: &[(&str, &str, u64)]) -> Repodata {
+        let packages: serde_json::Map<String, serde_json::Value> = packages.iter()
+            .map(|&(name, version, build_number)| {
+                let build = format!("h0_{}", build_number);
+                let mut record = test_record(name, version, &build, build_number);
+                record["subdir"] = json!(subdir);
+                (format!("{}-{}-{}.conda", name, version, build), record)
+            })
+            .collect();
+        serde_json::from_value(json!({"info": {"subdir": subdir}, "packages.conda": packages})).unwrap()
+    }
+
+    fn channel_set(priority: ChannelPriority) -> ChannelSet {
+        ChannelSet::new(priority)
+            .with_repodata(&format!("{}/", FORGE), conda_forge("win-64", &[("openssl", "1.1.1c", 0)]))
+            .with_repodata(MAIN, read_repodata("tests/data/current_repodata.json").unwrap())
+            .with_repodata(FORGE, conda_forge("noarch", &[("tzdata", "2019c", 0), ("openssl", "1.1.1c", 1)]))
+    }
+
+    fn openssl(priority: ChannelPriority) -> Vec<(String, String)> {
+        let channels = channel_set(priority);
+        let merged = channels.merge();
+        merged.records("openssl").iter()
+            .map(|record| (record.channel().rsplit('/').next().unwrap().to_string(), record.file_name().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn channels() {
+        assert_eq!(channel_set(ChannelPriority::Strict).channels(), vec![FORGE, MAIN]);
+    }
+
+    #[test]
+    fn strict() {
+        assert_eq!(openssl(ChannelPriority::Strict), vec![
+            ("conda-forge".to_string(), "openssl-1.1.1c-h0_1.conda".to_string()),
+            ("conda-forge".to_string(), "openssl-1.1.1c-h0_0.conda".to_string()),
+        ]);
+
+        let channels = channel_set(ChannelPriority::Strict);
+        let merged = channels.merge();
+        assert_eq!(merged.records("tzdata")[0].subdir(), Some("noarch"));
+        assert_eq!(merged.records("vc").len(), 5);
+        assert!(merged.records("vc").iter().all(|record| record.channel() == MAIN));
+        assert!(merged.records("missing").is_empty());
+    }
+
+    #[test]
+    fn flexible() {
+        let records = openssl(ChannelPriority::Flexible);
+        assert_eq!(records.len(), 10);
+        assert_eq!(records[1], ("conda-forge".to_string(), "openssl-1.1.1c-h0_0.conda".to_string()));
+        assert_eq!(records[2].0, "main");
+        assert!(records[2].1.starts_with("openssl-1.1.1d-"));
+    }
+
+    #[test]
+    fn disabled() {
+        let records = openssl(ChannelPriority::Disabled);
+        assert_eq!(records.len(), 10);
+        assert_eq!(records[0].0, "main");
+        assert!(records[0].1.starts_with("openssl-1.1.1d-"));
+        // Of equal versions and build numbers, records without a timestamp come last
+        assert_eq!(records[4], ("conda-forge".to_string(), "openssl-1.1.1c-h0_1.conda".to_string()));
+    }
+
+    #[test]
+    fn find() {
+        let channels = channel_set(ChannelPriority::Strict);
+        let merged = channels.merge();
+        let found = merged.find(&"openssl >=1.1.1d".parse().unwrap());
+        assert!(found.is_empty());
+
+        let found = merged.find(&"pkgs/main::openssl >=1.1.1d".parse().unwrap());
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|record| record.channel() == MAIN));
+
+        let found = merged.find(&"conda-forge::openssl[subdir=noarch]".parse().unwrap());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].file_name(), "openssl-1.1.1c-h0_1.conda");
+
+        let found = merged.find(&"*[subdir=noarch]".parse().unwrap());
+        assert_eq!(found.len(), 2);
+    }
+
+    #[test]
+    fn unparseable_version() {
+        let packages = [("tzdata", "2019 c", 1), ("tzdata", "2019a", 0)];
+        for &priority in &[ChannelPriority::Strict, ChannelPriority::Disabled] {
+            let channels = channel_set(priority)
+                .with_repodata(FORGE, conda_forge("noarch", &packages));
+            let merged = channels.merge();
+            let records = merged.records("tzdata");
+            let versions: Vec<_> = records.iter().map(|record| record.record().version()).collect();
+            assert_eq!(versions, vec!["2019c", "2019a", "2019 c"]);
+            assert!(records[2].version().is_none());
+            assert_eq!(merged.find(&"tzdata".parse().unwrap()).len(), 3);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Repodata error module.
+//!
+//! This module provides the `RepodataError` enum, which is returned when repodata can't be read.
+//! Errors in the JSON carry the line and column at which the problem was found, and all errors
+//! carry the path of the file being read, if any.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::error::Category;
+
+/// Error returned when repodata can't be read.
+#[derive(Debug)]
+pub enum RepodataError {
+    /// The repodata couldn't be read, for example because the file doesn't exist.
+    Io {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+
+    /// The repodata is not valid JSON.
+    Json {
+        path: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        source: serde_json::Error,
+    },
+
+    /// The repodata is valid JSON, but doesn't have the structure of repodata, for example
+    /// because a record is missing its `version`.
+    Schema {
+        path: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        source: serde_json::Error,
+    },
+}
+
+impl RepodataError {
+    /// Create an error for an I/O error while reading the repodata at `path`.
+    pub(crate) fn io(path: Option<&Path>, source: io::Error) -> RepodataError {
+        RepodataError::Io { path: path.map(Path::to_path_buf), source }
+    }
+
+    /// Create an error for a JSON error while reading the repodata at `path`.
+    pub(crate) fn json(path: Option<&Path>, source: serde_json::Error) -> RepodataError {
+        let path = path.map(Path::to_path_buf);
+        let (line, column) = (source.line(), source.column());
+        match source.classify() {
+            Category::Io => RepodataError::Io { path, source: source.into() },
+            Category::Syntax | Category::Eof => RepodataError::Json { path, line, column, source },
+            Category::Data => RepodataError::Schema { path, line, column, source },
+        }
+    }
+
+    /// Get the path of the repodata file, if the repodata was read from a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use libronda::read_repodata;
+    ///
+    /// let err = read_repodata("missing/repodata.json").unwrap_err();
+    ///
+    /// assert_eq!(err.path(), Some(Path::new("missing/repodata.json")));
+    /// ```
+    pub fn path(&self) -> Option<&Path> {
+        match *self {
+            RepodataError::Io { ref path, .. } => path.as_deref(),
+            RepodataError::Json { ref path, .. } => path.as_deref(),
+            RepodataError::Schema { ref path, .. } => path.as_deref(),
+        }
+    }
+
+    /// Get the line and column in the JSON at which the error was found, both starting at 1.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match *self {
+            RepodataError::Io { .. } => None,
+            RepodataError::Json { line, column, .. } => Some((line, column)),
+            RepodataError::Schema { line, column, .. } => Some((line, column)),
+        }
+    }
+}
+
+impl fmt::Display for RepodataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (description, path) = match *self {
+            RepodataError::Io { ref path, .. } => ("failed to read repodata", path),
+            RepodataError::Json { ref path, .. } => ("invalid JSON in repodata", path),
+            RepodataError::Schema { ref path, .. } => ("invalid repodata", path),
+        };
+        write!(f, "{}", description)?;
+        if let Some(path) = path {
+            write!(f, " {}", path.display())?;
+        }
+        match *self {
+            RepodataError::Io { ref source, .. } => write!(f, ": {}", source),
+            RepodataError::Json { ref source, .. } | RepodataError::Schema { ref source, .. } =>
+                write!(f, ": {}", source),
+        }
+    }
+}
+
+impl Error for RepodataError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            RepodataError::Io { ref source, .. } => Some(source),
+            RepodataError::Json { ref source, .. } => Some(source),
+            RepodataError::Schema { ref source, .. } => Some(source),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Repodata index module, which provides the `RepodataIndex` struct.
+//!
+//! Looking up the records of a package in a `Repodata` means scanning all of its records, and
+//! parsing their versions to order them.  A `RepodataIndex` does both once, when it's built: it
+//! groups the records by package name, and sorts the records of each package by version, build
+//! number and timestamp.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::version::{Version, VersionSpec};
+use super::package_format::FormatPreference;
+use super::record::Record;
+use super::repodata::Repodata;
+
+/// A record in a `RepodataIndex`, with its file name and parsed version.
+#[derive(Clone, Debug)]
+pub struct IndexedRecord<'r> {
+    file_name: &'r str,
+    version: Version<'r>,
+    record: &'r Record,
+}
+
+impl<'r> IndexedRecord<'r> {
+    /// Get the file name of the package.
+    pub fn file_name(&self) -> &'r str {
+        self.file_name
+    }
+
+    /// Get the parsed version of the record.
+    pub fn version(&self) -> &Version<'r> {
+        &self.version
+    }
+
+    /// Get the record.
+    pub fn record(&self) -> &'r Record {
+        self.record
+    }
+
+    /// Order records by version, then build number, then timestamp, with records without a
+    /// timestamp first.
+    fn cmp(&self, other: &IndexedRecord) -> Ordering {
+        self.version.cmp(&other.version)
+            .then_with(|| self.record.build_number.cmp(&other.record.build_number))
+            .then_with(|| self.record.timestamp().cmp(&other.record.timestamp()))
+    }
+}
+
+/// An index of the records of a `Repodata` by package name.
+///
+/// The records of each package are sorted from the oldest to the latest: by version, then build
+/// number, then timestamp.  Versions are parsed once, when the index is built, and records with
+/// a version that can't be parsed are left out.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{RepodataIndex, read_repodata};
+///
+/// let repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+/// let index = RepodataIndex::new(&repodata);
+///
+/// assert_eq!(index.latest("openssl").unwrap().file_name(), "openssl-1.1.1d-he774522_0.tar.bz2");
+///
+/// let versions: Vec<&str> = index.versions("openssl").iter().map(|version| version.as_str()).collect();
+/// assert_eq!(versions, vec!["1.0.2s", "1.0.2t", "1.1.1c", "1.1.1d"]);
+///
+/// let spec = "1.0.*".parse().unwrap();
+/// assert_eq!(index.find("openssl", &spec).count(), 4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RepodataIndex<'r> {
+    records: HashMap<&'r str, Vec<IndexedRecord<'r>>>,
+}
+
+impl<'r> RepodataIndex<'r> {
+    /// Build the index of `repodata`, using the `.conda` file of packages served in both formats.
+    pub fn new(repodata: &'r Repodata) -> RepodataIndex<'r> {
+        RepodataIndex::with_preference(repodata, FormatPreference::default())
+    }
+
+    /// Build the index of `repodata`, selecting the file of packages served in both formats by
+    /// `preference`.
+    pub fn with_preference(repodata: &'r Repodata, preference: FormatPreference) -> RepodataIndex<'r> {
+        let mut records: HashMap<&'r str, Vec<IndexedRecord<'r>>> = HashMap::new();
+        for (file_name, record) in repodata.records(preference) {
+            if let Ok(version) = Version::from(&record.version) {
+                records.entry(&record.name).or_default().push(IndexedRecord { file_name, version, record });
+            }
+        }
+        for records in records.values_mut() {
+            records.sort_by(IndexedRecord::cmp);
+        }
+        RepodataIndex { records }
+    }
+
+    /// Get the names of the packages in the index, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &'r str> + '_ {
+        self.records.keys().cloned()
+    }
+
+    /// Get the number of packages in the index.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Check whether the index has no packages.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Get the records of the package `name`, from the oldest to the latest.
+    pub fn records(&self, name: &str) -> &[IndexedRecord<'r>] {
+        self.records.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get the latest record of the package `name`: the one with the highest version, build
+    /// number and timestamp.
+    pub fn latest(&self, name: &str) -> Option<&IndexedRecord<'r>> {
+        self.records(name).last()
+    }
+
+    /// Get the distinct versions of the package `name`, from the lowest to the highest.
+    pub fn versions(&self, name: &str) -> Vec<&Version<'r>> {
+        let mut versions: Vec<&Version<'r>> = self.records(name).iter().map(IndexedRecord::version).collect();
+        versions.dedup();
+        versions
+    }
+
+    /// Get the records of the package `name` with a version satisfying `spec`, from the oldest to
+    /// the latest.
+    pub fn find<'i>(&'i self, name: &str, spec: &'i VersionSpec) -> impl DoubleEndedIterator<Item = &'i IndexedRecord<'r>> {
+        self.records(name).iter().filter(move |record| spec.contains(&record.version))
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use crate::repodata::{FormatPreference, PackageFormat, Repodata, read_repodata};
+    use crate::repodata::record::test_record;
+    use super::RepodataIndex;
+
+    #[test]
+    fn sorted() {
+        let repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+        let index = RepodataIndex::new(&repodata);
+        let file_names: Vec<&str> = index.records("vc").iter().map(|record| record.file_name()).collect();
+        assert_eq!(file_names, vec![
+            "vc-9-h7299396_0.conda",
+            "vc-9-h7299396_1.conda",
+            "vc-14.1-h21ff451_0.conda",
+            "vc-14.1-h21ff451_4.conda",
+            "vc-14.1-h0510ff6_4.conda",
+        ]);
+        assert_eq!(index.latest("vc").unwrap().record().build(), "h0510ff6_4");
+        assert_eq!(index.records("missing").len(), 0);
+        assert!(index.latest("missing").is_none());
+        assert_eq!(index.names().count(), index.len());
+        assert_eq!(index.records.values().map(Vec::len).sum::<usize>(), 3614);
+    }
+
+    #[test]
+    fn find() {
+        let repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+        let index = RepodataIndex::with_preference(&repodata, FormatPreference::Only(PackageFormat::TarBz2));
+        let spec = ">=1.0.2t,<1.1.1d".parse().unwrap();
+        let found: Vec<&str> = index.find("openssl", &spec).map(|record| record.file_name()).collect();
+        assert_eq!(found, vec!["openssl-1.0.2t-vc9h3cc03e0_0.tar.bz2", "openssl-1.0.2t-vc14h62dcd97_0.tar.bz2"]);
+        assert_eq!(index.find("openssl", &"3.*".parse().unwrap()).count(), 0);
+        assert_eq!(index.find("openssl", &"*".parse().unwrap()).next_back().unwrap().version().as_str(), "1.1.1d");
+    }
+
+    #[test]
+    fn invalid_versions() {
+        let repodata: Repodata = serde_json::from_value(serde_json::json!({"packages": {
+            "a-1.0-0.tar.bz2": test_record("a", "1.0", "0", 0),
+            "a-x!1-0.tar.bz2": test_record("a", "x!1", "0", 0),
+        }})).unwrap();
+        let index = RepodataIndex::new(&repodata);
+        assert_eq!(index.records("a").len(), 1);
+        assert_eq!(index.versions("a").len(), 1);
+    }
+}
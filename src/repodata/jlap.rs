@@ -0,0 +1,396 @@
+//! JLAP module, which provides the `Jlap` struct for incremental repodata updates.
+//!
+//! Channels publish `repodata.jlap` next to `repodata.json`: a log of the changes to the
+//! repodata, as JSON Patches (RFC 6902).  A client that has a copy of the repodata downloads the
+//! log, and applies the patches that its copy hasn't seen yet, instead of downloading the whole
+//! repodata again.
+//!
+//! The log is a text file.  Its first line is the initialization vector of a chain of hashes,
+//! each following line is a patch, and the second to last line holds the metadata, with the hash
+//! of the latest repodata.  The last line is the hash of the chain: each line is hashed with
+//! keyed BLAKE2b-256, using the hash of the line before it as key.  A patch goes `from` the
+//! BLAKE2b-256 hash of a `repodata.json` file `to` the hash of the next one.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use blake2::digest::consts::U32;
+use blake2::digest::{Digest, KeyInit, Mac};
+use blake2::{Blake2b, Blake2bMac};
+use json_patch::{Patch, PatchError};
+use serde_derive::Deserialize;
+use serde_json::Value;
+
+use super::error::RepodataError;
+use super::repodata::Repodata;
+
+/// Error returned when a JLAP file can't be read or applied.
+#[derive(Debug)]
+pub enum JlapError {
+    /// The JLAP file couldn't be read.
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+
+    /// A line of the JLAP file is malformed.  Lines are numbered from 1.
+    Format {
+        line: usize,
+        message: String,
+    },
+
+    /// The hash of the chain doesn't match the hash on the last line, so the file is corrupt or
+    /// truncated.
+    ChecksumMismatch {
+        expected: String,
+        found: String,
+    },
+
+    /// The patches don't lead from the hash of the cached repodata to the latest repodata, so
+    /// the whole repodata has to be downloaded again.
+    MissingPatches {
+        have: String,
+        latest: String,
+    },
+
+    /// A patch couldn't be applied to the repodata.
+    Patch {
+        to: String,
+        source: PatchError,
+    },
+
+    /// The cached or the patched repodata isn't valid repodata.
+    Repodata(RepodataError),
+}
+
+impl JlapError {
+    fn format<T: fmt::Display>(line: usize, message: T) -> JlapError {
+        JlapError::Format { line, message: message.to_string() }
+    }
+}
+
+impl fmt::Display for JlapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JlapError::Io { ref path, ref source } => write!(f, "failed to read {}: {}", path.display(), source),
+            JlapError::Format { line, ref message } => write!(f, "invalid JLAP line {}: {}", line, message),
+            JlapError::ChecksumMismatch { ref expected, ref found } =>
+                write!(f, "JLAP checksum mismatch: expected {}, found {}", expected, found),
+            JlapError::MissingPatches { ref have, ref latest } =>
+                write!(f, "no JLAP patches lead from repodata {} to {}", have, latest),
+            JlapError::Patch { ref to, ref source } => write!(f, "failed to apply JLAP patch to {}: {}", to, source),
+            JlapError::Repodata(ref source) => write!(f, "{}", source),
+        }
+    }
+}
+
+impl Error for JlapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            JlapError::Io { ref source, .. } => Some(source),
+            JlapError::Patch { ref source, .. } => Some(source),
+            JlapError::Repodata(ref source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<RepodataError> for JlapError {
+    fn from(error: RepodataError) -> JlapError {
+        JlapError::Repodata(error)
+    }
+}
+
+/// Get the BLAKE2b-256 hash of a `repodata.json` file, in hexadecimal, as used by JLAP patches.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::repodata_hash;
+///
+/// assert_eq!(repodata_hash(b"{}"), "c09da522dac261c3d2566230bed10d2f3ef13f8e7654576c12e857e07f786098");
+/// ```
+pub fn repodata_hash(json: &[u8]) -> String {
+    hex::encode(Blake2b::<U32>::digest(json))
+}
+
+/// A patch of a JLAP file.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct JlapPatch {
+    from: String,
+    to: String,
+    patch: Patch,
+}
+
+impl JlapPatch {
+    /// Get the hash of the repodata the patch applies to.
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    /// Get the hash of the repodata the patch results in.
+    pub fn to(&self) -> &str {
+        &self.to
+    }
+
+    /// Get the JSON Patch.
+    pub fn patch(&self) -> &Patch {
+        &self.patch
+    }
+}
+
+/// The metadata line of a JLAP file.
+#[derive(Deserialize)]
+struct Metadata {
+    latest: String,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+/// The repodata updated by `Jlap::apply`.
+#[derive(Clone, Debug)]
+pub struct JlapUpdate {
+    repodata: Repodata,
+    hash: String,
+    applied: usize,
+}
+
+impl JlapUpdate {
+    /// Get the updated repodata.
+    pub fn repodata(&self) -> &Repodata {
+        &self.repodata
+    }
+
+    /// Take the updated repodata.
+    pub fn into_repodata(self) -> Repodata {
+        self.repodata
+    }
+
+    /// Get the hash of the updated repodata, which is the hash the server's `repodata.json` has.
+    /// It's the hash to look up in the next JLAP file.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Get the number of patches that were applied.
+    pub fn applied(&self) -> usize {
+        self.applied
+    }
+}
+
+/// A verified JLAP file.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::read_jlap;
+///
+/// let jlap = read_jlap("tests/data/jlap/repodata.jlap").unwrap();
+/// let cached = std::fs::read("tests/data/jlap/repodata.json").unwrap();
+/// let update = jlap.apply(&cached).unwrap();
+///
+/// assert_eq!(update.applied(), 2);
+/// assert_eq!(update.hash(), jlap.latest());
+/// assert_eq!(update.repodata().packages_conda().len(), 3);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Jlap {
+    iv: String,
+    patches: Vec<JlapPatch>,
+    latest: String,
+    url: Option<String>,
+    checksum: String,
+}
+
+impl Jlap {
+    /// Get the initialization vector of the hash chain, in hexadecimal.
+    pub fn iv(&self) -> &str {
+        &self.iv
+    }
+
+    /// Get the patches, from the oldest to the latest.
+    pub fn patches(&self) -> &[JlapPatch] {
+        &self.patches
+    }
+
+    /// Get the hash of the latest repodata.
+    pub fn latest(&self) -> &str {
+        &self.latest
+    }
+
+    /// Get the URL of the repodata, relative to the JLAP file, if given.
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Get the hash of the chain, on the last line, in hexadecimal.
+    pub fn checksum(&self) -> &str {
+        &self.checksum
+    }
+
+    /// Apply the patches that `cached`, the contents of a `repodata.json` file, hasn't seen yet,
+    /// and return the updated repodata with its hash.
+    ///
+    /// The patches to apply are found by following the chain of patches back from the latest
+    /// repodata to the hash of `cached`.  If the chain doesn't reach it, for example because
+    /// the cache is older than the oldest patch, `JlapError::MissingPatches` is returned.
+    ///
+    /// The hash of the updated repodata is the hash of the server's `repodata.json`.  Writing the
+    /// updated repodata with `write_to` only reproduces that file, and its hash, if the server
+    /// formats its repodata like conda-index does.
+    pub fn apply(&self, cached: &[u8]) -> Result<JlapUpdate, JlapError> {
+        let have = repodata_hash(cached);
+        let mut chain: Vec<&JlapPatch> = Vec::new();
+        let mut hash = self.latest.as_str();
+        while hash != have {
+            // Bounded by the number of patches, so that a cycle in the patches can't loop forever
+            let patch = match self.patches.iter().rev().find(|patch| patch.to == hash) {
+                Some(patch) if chain.len() < self.patches.len() => patch,
+                _ => return Err(JlapError::MissingPatches { have, latest: self.latest.clone() }),
+            };
+            chain.push(patch);
+            hash = &patch.from;
+        }
+
+        let mut repodata: Value = serde_json::from_slice(cached).map_err(|error| RepodataError::json(None, error))?;
+        for patch in chain.iter().rev() {
+            json_patch::patch(&mut repodata, &patch.patch)
+                .map_err(|source| JlapError::Patch { to: patch.to.clone(), source })?;
+        }
+        let repodata = serde_json::from_value(repodata).map_err(|error| RepodataError::json(None, error))?;
+        Ok(JlapUpdate { repodata, hash: self.latest.clone(), applied: chain.len() })
+    }
+}
+
+/// Parse a JLAP file, verifying its hash chain.
+impl FromStr for Jlap {
+    type Err = JlapError;
+
+    fn from_str(text: &str) -> Result<Jlap, JlapError> {
+        let lines: Vec<&str> = text.strip_suffix('\n').unwrap_or(text).split('\n').collect();
+        if lines.len() < 3 {
+            return Err(JlapError::format(lines.len(), "expected an IV, a metadata line and a checksum"));
+        }
+        let (iv, lines) = lines.split_first().unwrap_or((&"", &[]));
+        let (checksum, lines) = lines.split_last().unwrap_or((&"", &[]));
+        let (metadata, patches) = lines.split_last().unwrap_or((&"", &[]));
+
+        let mut hash = hex::decode(iv).map_err(|error| JlapError::format(1, error))?;
+        if hash.len() != 32 {
+            return Err(JlapError::format(1, "expected a 32 byte IV"));
+        }
+        for line in lines {
+            let mut mac = <Blake2bMac<U32> as KeyInit>::new_from_slice(&hash).map_err(|error| JlapError::format(1, error))?;
+            mac.update(line.as_bytes());
+            hash = mac.finalize().into_bytes().to_vec();
+        }
+        let found = hex::encode(&hash);
+        if *checksum != found {
+            return Err(JlapError::ChecksumMismatch { expected: checksum.to_string(), found });
+        }
+
+        let patches = patches.iter().enumerate()
+            .map(|(index, line)| serde_json::from_str(line).map_err(|error| JlapError::format(index + 2, error)))
+            .collect::<Result<Vec<JlapPatch>, JlapError>>()?;
+        let metadata: Metadata = serde_json::from_str(metadata)
+            .map_err(|error| JlapError::format(patches.len() + 2, error))?;
+        Ok(Jlap {
+            iv: iv.to_string(),
+            patches,
+            latest: metadata.latest,
+            url: metadata.url,
+            checksum: found,
+        })
+    }
+}
+
+/// Read the JLAP file at `path`, verifying its hash chain.
+pub fn read_jlap<P: AsRef<Path>>(path: P) -> Result<Jlap, JlapError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|source| JlapError::Io { path: path.to_path_buf(), source })?;
+    text.parse()
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::repodata::{read_repodata, read_repodata_from};
+    use super::{Jlap, JlapError, read_jlap, repodata_hash};
+
+    fn jlap_text() -> String {
+        fs::read_to_string("tests/data/jlap/repodata.jlap").unwrap()
+    }
+
+    #[test]
+    fn verify() {
+        let jlap = read_jlap("tests/data/jlap/repodata.jlap").unwrap();
+        assert_eq!(jlap.iv(), "0".repeat(64));
+        assert_eq!(jlap.patches().len(), 3);
+        assert_eq!(jlap.url(), Some("repodata.json"));
+        assert_eq!(jlap.latest(), "4fcb7a99241283f5c255b38e513f1cbbaecf85804167813ab64213ae92c71271");
+        assert_eq!(jlap.checksum(), "89b0db005c7f03b9b7ed97ce2b3265df4507cf9c59e67ee82435e51ae8a2038d");
+        assert_eq!(jlap.patches()[2].to(), jlap.latest());
+    }
+
+    #[test]
+    fn apply() {
+        let jlap: Jlap = jlap_text().parse().unwrap();
+        let cached = fs::read("tests/data/jlap/repodata.json").unwrap();
+        assert_eq!(repodata_hash(&cached), jlap.patches()[1].from());
+
+        let update = jlap.apply(&cached).unwrap();
+        assert_eq!(update.applied(), 2);
+        let latest = fs::read("tests/data/jlap/repodata_latest.json").unwrap();
+        assert_eq!(update.repodata(), &read_repodata("tests/data/jlap/repodata_latest.json").unwrap());
+        assert_eq!(update.repodata().removed(), ["tzdata-2019c-0.conda"]);
+        // The fixture is formatted like conda-index does, so writing it reproduces the hash
+        assert_eq!(update.repodata().to_string().as_bytes(), &latest[..]);
+        assert_eq!(repodata_hash(&latest), update.hash());
+
+        // The latest repodata needs no patches
+        let update = jlap.apply(&latest).unwrap();
+        assert_eq!(update.applied(), 0);
+        assert_eq!(update.into_repodata(), read_repodata_from(&latest[..]).unwrap());
+    }
+
+    #[test]
+    fn missing_patches() {
+        let jlap: Jlap = jlap_text().parse().unwrap();
+        match jlap.apply(b"{}") {
+            Err(JlapError::MissingPatches { ref have, ref latest }) => {
+                assert_eq!(have, &repodata_hash(b"{}"));
+                assert_eq!(latest, jlap.latest());
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn corrupt() {
+        let text = jlap_text();
+        let tampered = text.replacen("python >=2.7", "python >=3.7", 1);
+        match tampered.parse::<Jlap>() {
+            Err(JlapError::ChecksumMismatch { .. }) => {},
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let lines: Vec<&str> = text.lines().collect();
+        let truncated = format!("{}\n", lines[..lines.len() - 2].join("\n"));
+        match truncated.parse::<Jlap>() {
+            Err(JlapError::ChecksumMismatch { .. }) => {},
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        match "zz\n{}\n00".parse::<Jlap>() {
+            Err(JlapError::Format { line: 1, .. }) => {},
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(read_jlap("tests/data/jlap/missing.jlap").unwrap_err().to_string().starts_with("failed to read"));
+    }
+}
@@ -0,0 +1,20 @@
+pub mod borrowed;
+pub mod channel_set;
+pub mod error;
+pub mod index;
+pub mod jlap;
+pub mod package_format;
+pub mod patch;
+pub mod record;
+#[allow(clippy::module_inception)]
+pub mod repodata;
+
+pub use self::borrowed::{RecordRef, RepodataFile, RepodataRef};
+pub use self::channel_set::{ChannelPriority, ChannelRecord, ChannelSet, MergedRepodata};
+pub use self::error::RepodataError;
+pub use self::index::{IndexedRecord, RepodataIndex};
+pub use self::jlap::{Jlap, JlapError, JlapPatch, JlapUpdate, read_jlap, repodata_hash};
+pub use self::package_format::{FormatPreference, PackageFormat};
+pub use self::patch::{FieldChange, PatchInstructions, PatchReport};
+pub use self::record::{Noarch, Record};
+pub use self::repodata::{Repodata, RepodataInfo, read_repodata, read_repodata_from};
@@ -0,0 +1,78 @@
+//! Package format module, which provides the `PackageFormat` and `FormatPreference` enums.
+//!
+//! Packages are built as `.tar.bz2` files and, more recently, as `.conda` files.  Channels often
+//! serve the same package in both formats, listed in the `packages` and `packages.conda` sections
+//! of the repodata.  A `FormatPreference` selects which of the two files of a package to use.
+
+use std::fmt;
+
+/// The file format of a package.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PackageFormat {
+    /// A `.conda` file, listed in the `packages.conda` section.
+    Conda,
+
+    /// A `.tar.bz2` file, listed in the `packages` section.
+    TarBz2,
+}
+
+impl PackageFormat {
+    /// Get the file extension of the format, including the leading dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            PackageFormat::Conda => ".conda",
+            PackageFormat::TarBz2 => ".tar.bz2",
+        }
+    }
+
+    /// Get the format of a package file from its name, if it has a known extension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::PackageFormat;
+    ///
+    /// assert_eq!(PackageFormat::from_file_name("openssl-1.1.1d-he774522_0.conda"), Some(PackageFormat::Conda));
+    /// assert_eq!(PackageFormat::from_file_name("openssl-1.1.1d-he774522_0.tar.bz2"), Some(PackageFormat::TarBz2));
+    /// assert_eq!(PackageFormat::from_file_name("openssl-1.1.1d-he774522_0.zip"), None);
+    /// ```
+    pub fn from_file_name(file_name: &str) -> Option<PackageFormat> {
+        [PackageFormat::Conda, PackageFormat::TarBz2].iter()
+            .cloned()
+            .find(|format| file_name.ends_with(format.extension()))
+    }
+}
+
+impl fmt::Display for PackageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+/// Which file to use for a package that is served in both formats.
+///
+/// conda prefers `.conda` files, and falls back to `.tar.bz2` files for packages that haven't
+/// been built as `.conda` files, which is the default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FormatPreference {
+    /// Use the file of the given format if there is one, or else the file of the other format.
+    Prefer(PackageFormat),
+
+    /// Only use files of the given format, leaving out packages that aren't available in it.
+    Only(PackageFormat),
+}
+
+impl FormatPreference {
+    /// Get the preferred format.
+    pub fn format(self) -> PackageFormat {
+        match self {
+            FormatPreference::Prefer(format) | FormatPreference::Only(format) => format,
+        }
+    }
+}
+
+impl Default for FormatPreference {
+    fn default() -> FormatPreference {
+        FormatPreference::Prefer(PackageFormat::Conda)
+    }
+}
@@ -0,0 +1,455 @@
+//! Repodata patch module, which provides `Repodata::apply_patch` and the `PatchInstructions` and
+//! `PatchReport` structs.
+//!
+//! Channels fix the metadata of packages they already published, such as a dependency that
+//! turned out to be too loose, by publishing patch instructions in `patch_instructions.json`.
+//! conda-index applies them to the repodata it generates, the same way `apply_patch` does.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::error::RepodataError;
+use super::package_format::PackageFormat;
+use super::record::Record;
+use super::repodata::Repodata;
+
+/// The fields of a record, by name.
+type Fields = Map<String, Value>;
+
+/// The dependency conda-index adds to revoked packages, which no package provides, so that they
+/// can't be installed.
+const REVOKED_DEPENDENCY: &str = "package_has_been_revoked";
+
+/// The patch instructions of a channel subdir, as published in `patch_instructions.json`.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::PatchInstructions;
+///
+/// let instructions: PatchInstructions = serde_json::from_str(r#"{
+///     "packages": {"openssl-1.1.1d-he774522_0.tar.bz2": {"license": "OpenSSL"}},
+///     "patch_instructions_version": 1,
+///     "remove": ["openssl-1.0.2s-he774522_0.tar.bz2"],
+///     "revoke": []
+/// }"#).unwrap();
+///
+/// assert_eq!(instructions.remove(), ["openssl-1.0.2s-he774522_0.tar.bz2"]);
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PatchInstructions {
+    #[serde(default)]
+    pub(crate) packages: BTreeMap<String, Fields>,
+    #[serde(default, rename = "packages.conda")]
+    pub(crate) packages_conda: BTreeMap<String, Fields>,
+    #[serde(default = "default_patch_instructions_version")]
+    pub(crate) patch_instructions_version: u8,
+    #[serde(default)]
+    pub(crate) remove: Vec<String>,
+    #[serde(default)]
+    pub(crate) revoke: Vec<String>,
+}
+
+/// Patch instructions without a version have the layout of version 1.
+fn default_patch_instructions_version() -> u8 {
+    1
+}
+
+impl PatchInstructions {
+    /// Create empty patch instructions.
+    pub fn new() -> PatchInstructions {
+        PatchInstructions { patch_instructions_version: 1, ..PatchInstructions::default() }
+    }
+
+    /// Set the given fields of the record of the package file `file_name`, and return the
+    /// instructions.
+    ///
+    /// Fields of a `.tar.bz2` file are also set on the `.conda` file of the same package.  Nested
+    /// objects are merged with the objects they replace, and other values replace the old value.
+    pub fn with_fields(mut self, file_name: &str, fields: Fields) -> PatchInstructions {
+        let packages = match PackageFormat::from_file_name(file_name) {
+            Some(PackageFormat::Conda) => &mut self.packages_conda,
+            _ => &mut self.packages,
+        };
+        merge_fields(packages.entry(file_name.to_string()).or_default(), &fields);
+        self
+    }
+
+    /// Revoke the package file `file_name`, and return the instructions.
+    pub fn with_revoke(mut self, file_name: &str) -> PatchInstructions {
+        self.revoke.push(file_name.to_string());
+        self
+    }
+
+    /// Remove the package file `file_name`, and return the instructions.
+    pub fn with_remove(mut self, file_name: &str) -> PatchInstructions {
+        self.remove.push(file_name.to_string());
+        self
+    }
+
+    /// Get the fields to set on `.tar.bz2` records, and on the `.conda` records of the same
+    /// packages, by file name.
+    pub fn packages(&self) -> &BTreeMap<String, Fields> {
+        &self.packages
+    }
+
+    /// Get the fields to set on `.conda` records, by file name.
+    pub fn packages_conda(&self) -> &BTreeMap<String, Fields> {
+        &self.packages_conda
+    }
+
+    /// Get the version of the patch instructions format.
+    pub fn patch_instructions_version(&self) -> u8 {
+        self.patch_instructions_version
+    }
+
+    /// Get the file names of the packages to remove.
+    pub fn remove(&self) -> &[String] {
+        &self.remove
+    }
+
+    /// Get the file names of the packages to revoke.
+    pub fn revoke(&self) -> &[String] {
+        &self.revoke
+    }
+}
+
+/// A change to a field of a record made by `Repodata::apply_patch`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    file_name: String,
+    field: String,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+impl FieldChange {
+    /// Get the file name of the changed package.
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Get the name of the changed field, such as `depends`.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Get the value of the field before the change, or `None` if the field was added.
+    pub fn before(&self) -> Option<&Value> {
+        self.before.as_ref()
+    }
+
+    /// Get the value of the field after the change, or `None` if the field was removed.
+    pub fn after(&self) -> Option<&Value> {
+        self.after.as_ref()
+    }
+}
+
+/// The report of the changes `Repodata::apply_patch` made.
+///
+/// Only actual changes are reported: fields set to the value they already had, and packages
+/// that are already revoked or aren't in the repodata, are left out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PatchReport {
+    changes: Vec<FieldChange>,
+    revoked: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl PatchReport {
+    /// Get the changed fields, ordered by file name and field.
+    pub fn changes(&self) -> &[FieldChange] {
+        &self.changes
+    }
+
+    /// Get the file names of the revoked packages.
+    pub fn revoked(&self) -> &[String] {
+        &self.revoked
+    }
+
+    /// Get the file names of the removed packages.
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// Check whether the patch changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.revoked.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Format the report with a line per change.
+impl fmt::Display for PatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |value: &Option<Value>| value.as_ref().map_or("(none)".to_string(), Value::to_string);
+        for change in &self.changes {
+            writeln!(f, "{}: {}: {} -> {}", change.file_name, change.field, show(&change.before), show(&change.after))?;
+        }
+        for file_name in &self.revoked {
+            writeln!(f, "{}: revoked", file_name)?;
+        }
+        for file_name in &self.removed {
+            writeln!(f, "{}: removed", file_name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Merge `fields` into `base` the way conda-index does: nested objects are merged, and other
+/// values replace the old value.
+fn merge_fields(base: &mut Fields, fields: &Fields) {
+    for (key, value) in fields {
+        match (base.get_mut(key), value) {
+            (Some(Value::Object(base)), Value::Object(value)) => merge_fields(base, value),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            },
+        }
+    }
+}
+
+/// Get the name of the `.conda` file of the same package as the `.tar.bz2` file `file_name`.
+fn conda_file_name(file_name: &str) -> String {
+    file_name.replace(PackageFormat::TarBz2.extension(), PackageFormat::Conda.extension())
+}
+
+/// A record with its fields patched, to be put in place once all records were patched.
+type PatchedRecords = Vec<(String, Record, Vec<FieldChange>)>;
+
+/// Patch the records of `section` with `patches`, without changing `section`.  Patches of files
+/// that aren't in `section` are skipped.
+fn patch_records<'p, I>(section: &HashMap<String, Record>, patches: I) -> Result<PatchedRecords, RepodataError>
+where
+    I: IntoIterator<Item = (String, &'p Fields)>,
+{
+    let mut patched: BTreeMap<String, (Fields, Fields)> = BTreeMap::new();
+    for (file_name, fields) in patches {
+        let record = match section.get(&file_name) {
+            Some(record) => record,
+            None => continue,
+        };
+        if !patched.contains_key(&file_name) {
+            let old = match serde_json::to_value(record).map_err(|error| RepodataError::json(None, error))? {
+                Value::Object(old) => old,
+                _ => unreachable!("records serialize to objects"),
+            };
+            patched.insert(file_name.clone(), (old.clone(), old));
+        }
+        if let Some((_, new)) = patched.get_mut(&file_name) {
+            merge_fields(new, fields);
+        }
+    }
+
+    let mut records = Vec::new();
+    for (file_name, (old, new)) in patched {
+        let fields: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        let changes = fields.into_iter()
+            .filter(|field| old.get(*field) != new.get(*field))
+            .map(|field| FieldChange {
+                file_name: file_name.clone(),
+                field: field.clone(),
+                before: old.get(field).cloned(),
+                after: new.get(field).cloned(),
+            })
+            .collect();
+        let record = serde_json::from_value(Value::Object(new)).map_err(|error| RepodataError::json(None, error))?;
+        records.push((file_name, record, changes));
+    }
+    Ok(records)
+}
+
+impl Repodata {
+    /// Apply the patch instructions of a channel, the way conda-index does, and report what
+    /// changed.
+    ///
+    /// First the fields of records are set.  The fields given for a `.tar.bz2` file are also set
+    /// on the `.conda` file of the same package.  Then the packages to revoke are marked as
+    /// revoked and given a dependency that can't be satisfied, and finally the packages to
+    /// remove are moved to the `removed` list.  Both apply to the `.conda` file of the same
+    /// package as well.  Instructions for packages that aren't in the repodata are skipped.
+    ///
+    /// An error is returned, and the repodata left unchanged, if a patched record isn't a valid
+    /// record, for example because a field was set to a value of the wrong type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{PatchInstructions, read_repodata};
+    /// use serde_json::json;
+    ///
+    /// let mut repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+    /// let fields = json!({"depends": ["vc >=14.1,<15.0a0"]}).as_object().unwrap().clone();
+    /// let instructions = PatchInstructions::new()
+    ///     .with_fields("openssl-1.1.1d-he774522_0.tar.bz2", fields)
+    ///     .with_remove("openssl-1.0.2s-he774522_0.conda");
+    /// let report = repodata.apply_patch(&instructions).unwrap();
+    ///
+    /// assert_eq!(report.changes()[0].field(), "depends");
+    /// assert_eq!(report.removed(), ["openssl-1.0.2s-he774522_0.conda"]);
+    /// assert_eq!(repodata.packages()["openssl-1.1.1d-he774522_0.tar.bz2"].depends(), ["vc >=14.1,<15.0a0"]);
+    /// assert!(repodata.removed().iter().any(|file_name| file_name == "openssl-1.0.2s-he774522_0.conda"));
+    /// ```
+    pub fn apply_patch(&mut self, instructions: &PatchInstructions) -> Result<PatchReport, RepodataError> {
+        let packages = patch_records(&self.packages, instructions.packages.iter()
+            .map(|(file_name, fields)| (file_name.clone(), fields)))?;
+        let packages_conda = patch_records(&self.packages_conda, instructions.packages.iter()
+            .map(|(file_name, fields)| (conda_file_name(file_name), fields))
+            .chain(instructions.packages_conda.iter().map(|(file_name, fields)| (file_name.clone(), fields))))?;
+
+        let mut report = PatchReport::default();
+        for (section, patched) in [(&mut self.packages, packages), (&mut self.packages_conda, packages_conda)] {
+            for (file_name, record, changes) in patched {
+                report.changes.extend(changes);
+                section.insert(file_name, record);
+            }
+        }
+        report.changes.sort_by(|a, b| (&a.file_name, &a.field).cmp(&(&b.file_name, &b.field)));
+
+        for file_name in &instructions.revoke {
+            for (section, file_name) in self.sections_mut(file_name) {
+                let record = match section.get_mut(&file_name) {
+                    Some(record) => record,
+                    None => continue,
+                };
+                let revoked = record.extra.get("revoked") == Some(&Value::Bool(true));
+                if !revoked || !record.depends.iter().any(|depends| depends == REVOKED_DEPENDENCY) {
+                    record.extra.insert("revoked".to_string(), Value::Bool(true));
+                    record.depends.push(REVOKED_DEPENDENCY.to_string());
+                    report.revoked.push(file_name);
+                }
+            }
+        }
+
+        for file_name in &instructions.remove {
+            for (section, file_name) in self.sections_mut(file_name) {
+                if section.remove(&file_name).is_some() {
+                    report.removed.push(file_name);
+                }
+            }
+        }
+        self.removed.extend(report.removed.iter().cloned());
+        self.removed.sort();
+
+        Ok(report)
+    }
+
+    /// Get both sections of records, each with the name the package file `file_name` has in it.
+    fn sections_mut(&mut self, file_name: &str) -> Vec<(&mut HashMap<String, Record>, String)> {
+        vec![(&mut self.packages, file_name.to_string()), (&mut self.packages_conda, conda_file_name(file_name))]
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use serde_json::{Map, Value, json};
+
+    use crate::repodata::{Repodata, RepodataError};
+    use crate::repodata::record::test_record;
+    use super::{PatchInstructions, REVOKED_DEPENDENCY};
+
+    fn repodata() -> Repodata {
+        let record = |build: &str| {
+            let mut record = test_record("a", "1.0", build, 0);
+            record["depends"] = json!(["python >=3.6"]);
+            record["license"] = json!("MIT");
+            record
+        };
+        serde_json::from_value(json!({
+            "packages": {"a-1.0-0.tar.bz2": record("0"), "a-1.0-1.tar.bz2": record("1")},
+            "packages.conda": {"a-1.0-0.conda": record("0"), "a-1.0-2.conda": record("2")},
+            "removed": ["z-1.0-0.tar.bz2"],
+        })).unwrap()
+    }
+
+    fn fields(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn set_fields() {
+        let mut repodata = repodata();
+        let instructions: PatchInstructions = serde_json::from_value(json!({
+            "packages": {
+                "a-1.0-0.tar.bz2": {"depends": ["python >=3.6,<3.8"], "constrains": ["b >=2"]},
+                "a-1.0-9.tar.bz2": {"license": "BSD"},
+            },
+            "packages.conda": {"a-1.0-2.conda": {"license": "MIT", "license_family": "MIT"}},
+        })).unwrap();
+        let report = repodata.apply_patch(&instructions).unwrap();
+
+        for file_name in &["a-1.0-0.tar.bz2", "a-1.0-0.conda"] {
+            let record = repodata.packages_of(crate::PackageFormat::from_file_name(file_name).unwrap()).get(*file_name).unwrap();
+            assert_eq!(record.depends(), ["python >=3.6,<3.8"]);
+            assert_eq!(record.constrains(), Some(&["b >=2".to_string()][..]));
+        }
+        assert_eq!(repodata.packages_conda["a-1.0-2.conda"].license_family(), Some("MIT"));
+        assert_eq!(repodata.packages["a-1.0-1.tar.bz2"].depends(), ["python >=3.6"]);
+        assert!(!repodata.packages.contains_key("a-1.0-9.tar.bz2"));
+
+        let changes: Vec<(&str, &str)> = report.changes().iter().map(|change| (change.file_name(), change.field())).collect();
+        assert_eq!(changes, vec![
+            ("a-1.0-0.conda", "constrains"),
+            ("a-1.0-0.conda", "depends"),
+            ("a-1.0-0.tar.bz2", "constrains"),
+            ("a-1.0-0.tar.bz2", "depends"),
+            ("a-1.0-2.conda", "license_family"),
+        ]);
+        assert_eq!(report.changes()[0].before(), None);
+        assert_eq!(report.changes()[1].before(), Some(&json!(["python >=3.6"])));
+        assert_eq!(report.to_string().lines().nth(1), Some(r#"a-1.0-0.conda: depends: ["python >=3.6"] -> ["python >=3.6,<3.8"]"#));
+    }
+
+    #[test]
+    fn revoke_and_remove() {
+        let mut repodata = repodata();
+        let instructions = PatchInstructions::new()
+            .with_revoke("a-1.0-0.tar.bz2")
+            .with_remove("a-1.0-1.tar.bz2")
+            .with_remove("a-1.0-2.conda")
+            .with_remove("a-1.0-9.tar.bz2");
+        let report = repodata.apply_patch(&instructions).unwrap();
+
+        assert_eq!(report.revoked(), ["a-1.0-0.tar.bz2", "a-1.0-0.conda"]);
+        assert_eq!(report.removed(), ["a-1.0-1.tar.bz2", "a-1.0-2.conda"]);
+        assert_eq!(repodata.removed(), ["a-1.0-1.tar.bz2", "a-1.0-2.conda", "z-1.0-0.tar.bz2"]);
+        assert_eq!(repodata.packages.keys().collect::<Vec<_>>(), vec!["a-1.0-0.tar.bz2"]);
+        let record = &repodata.packages_conda["a-1.0-0.conda"];
+        assert_eq!(record.depends(), ["python >=3.6", REVOKED_DEPENDENCY]);
+        assert_eq!(record.extra()["revoked"], json!(true));
+
+        // Applying the instructions again changes nothing
+        let report = repodata.apply_patch(&instructions).unwrap();
+        assert!(report.is_empty(), "{}", report);
+        assert_eq!(repodata.packages["a-1.0-0.tar.bz2"].depends().len(), 2);
+    }
+
+    #[test]
+    fn invalid_patch() {
+        let mut repodata = repodata();
+        let instructions = PatchInstructions::new()
+            .with_fields("a-1.0-1.tar.bz2", fields(json!({"license": "BSD"})))
+            .with_fields("a-1.0-0.tar.bz2", fields(json!({"depends": null})));
+        match repodata.apply_patch(&instructions) {
+            Err(RepodataError::Schema { .. }) => {},
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(repodata, self::repodata());
+    }
+
+    #[test]
+    fn nested_fields() {
+        let instructions = PatchInstructions::new()
+            .with_fields("a-1.0-0.tar.bz2", fields(json!({"about": {"home": "https://example.com"}})))
+            .with_fields("a-1.0-0.tar.bz2", fields(json!({"about": {"license": "MIT"}})));
+        assert_eq!(Value::Object(instructions.packages()["a-1.0-0.tar.bz2"].clone()),
+            json!({"about": {"home": "https://example.com", "license": "MIT"}}));
+
+        let mut repodata = repodata();
+        repodata.apply_patch(&instructions).unwrap();
+        assert_eq!(repodata.packages_conda["a-1.0-0.conda"].extra()["about"]["license"], "MIT");
+    }
+}
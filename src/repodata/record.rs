@@ -0,0 +1,302 @@
+//! Package record module, which provides the `Record` struct.
+//!
+//! A record describes a single package file of a channel, as listed in the `packages` and
+//! `packages.conda` sections of the repodata.  All fields documented by conda are modeled, and
+//! any other fields are kept as they are, so that writing a record back out loses nothing.
+
+use std::collections::BTreeMap;
+
+use serde::de::Deserializer;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The `noarch` field of a record.  Older repodata marks generic noarch packages with `true`,
+/// while newer repodata gives the kind of noarch package, `generic` or `python`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Noarch {
+    /// The legacy form, where `true` means a generic noarch package.
+    Boolean(bool),
+
+    /// The kind of noarch package, such as `generic` or `python`.
+    Kind(String),
+}
+
+/// Deserialize an optional field, keeping a field given as `null` apart from a missing one, as
+/// `Some(None)`, so that the `null` is written back out.
+pub(crate) fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    <Option<T> as serde::Deserialize>::deserialize(deserializer).map(Some)
+}
+
+/// A package record of the repodata.
+///
+/// Optional fields that are missing and optional fields given as `null` both read as `None`, but
+/// are written back out as they were.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::read_repodata;
+///
+/// let repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+/// let record = &repodata.packages()["aiohttp-3.6.1-py36he774522_0.tar.bz2"];
+///
+/// assert_eq!(record.name(), "aiohttp");
+/// assert_eq!(record.version(), "3.6.1");
+/// assert_eq!(record.license(), Some("Apache 2.0"));
+/// assert!(record.depends().iter().any(|depends| depends == "python >=3.6,<3.7.0a0"));
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Record {
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) app_entry: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) app_type: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) arch: Option<Option<String>>,
+    pub(crate) build: String,
+    pub(crate) build_number: u64,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) constrains: Option<Option<Vec<String>>>,
+    pub(crate) depends: Vec<String>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) features: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) icon: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) legacy_bz2_md5: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) legacy_bz2_size: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) license: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) license_family: Option<Option<String>>,
+    pub(crate) md5: String,
+    pub(crate) name: String,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) namespace: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) namespace_in_name: Option<Option<bool>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) noarch: Option<Option<Noarch>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) platform: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) sha256: Option<Option<String>>,
+    pub(crate) size: u64,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) subdir: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) summary: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) timestamp: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) track_features: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable", rename = "type", skip_serializing_if = "Option::is_none")]
+    pub(crate) package_type: Option<Option<String>>,
+    pub(crate) version: String,
+    #[serde(flatten)]
+    pub(crate) extra: BTreeMap<String, Value>,
+}
+
+impl Record {
+    /// Get the package name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the version string.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Get the build string.
+    pub fn build(&self) -> &str {
+        &self.build
+    }
+
+    /// Get the build number.
+    pub fn build_number(&self) -> u64 {
+        self.build_number
+    }
+
+    /// Get the match specs of the packages this package depends on.
+    pub fn depends(&self) -> &[String] {
+        &self.depends
+    }
+
+    /// Get the match specs constraining packages that are installed along with this package,
+    /// without being dependencies.
+    pub fn constrains(&self) -> Option<&[String]> {
+        self.constrains.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the MD5 hash of the package file.
+    pub fn md5(&self) -> &str {
+        &self.md5
+    }
+
+    /// Get the SHA-256 hash of the package file, which older repodata leaves out.
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the size of the package file, in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Get the MD5 hash of the `.tar.bz2` file with the same contents as this `.conda` file.
+    pub fn legacy_bz2_md5(&self) -> Option<&str> {
+        self.legacy_bz2_md5.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the size of the `.tar.bz2` file with the same contents as this `.conda` file.
+    pub fn legacy_bz2_size(&self) -> Option<u64> {
+        self.legacy_bz2_size.flatten()
+    }
+
+    /// Get the subdir, such as `win-64`.
+    pub fn subdir(&self) -> Option<&str> {
+        self.subdir.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the architecture, such as `x86_64`.
+    pub fn arch(&self) -> Option<&str> {
+        self.arch.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the platform, such as `win`.
+    pub fn platform(&self) -> Option<&str> {
+        self.platform.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the kind of noarch package, if this is a noarch package.
+    pub fn noarch(&self) -> Option<&Noarch> {
+        self.noarch.as_ref().and_then(Option::as_ref)
+    }
+
+    /// Get the features, separated by spaces or commas.
+    pub fn features(&self) -> Option<&str> {
+        self.features.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the track features, separated by spaces or commas.
+    pub fn track_features(&self) -> Option<&str> {
+        self.track_features.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the license.
+    pub fn license(&self) -> Option<&str> {
+        self.license.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the license family, such as `BSD`.
+    pub fn license_family(&self) -> Option<&str> {
+        self.license_family.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the build time, in milliseconds since the Unix epoch.
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp.flatten()
+    }
+
+    /// Get the namespace, such as `python`.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get whether the namespace is part of the package name.
+    pub fn namespace_in_name(&self) -> Option<bool> {
+        self.namespace_in_name.flatten()
+    }
+
+    /// Get the summary.
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the package type, such as `app`.
+    pub fn package_type(&self) -> Option<&str> {
+        self.package_type.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the entry point of an app package.
+    pub fn app_entry(&self) -> Option<&str> {
+        self.app_entry.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the type of an app package, such as `desk`.
+    pub fn app_type(&self) -> Option<&str> {
+        self.app_type.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the file name of the icon of an app package.
+    pub fn icon(&self) -> Option<&str> {
+        self.icon.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the fields that aren't modeled by this struct, such as `app_cli_opts`.
+    pub fn extra(&self) -> &BTreeMap<String, Value> {
+        &self.extra
+    }
+}
+
+/// Get the JSON of a record with only the required fields, for the tests of the repodata module.
+/// Other fields can be added by indexing the returned object.
+#[cfg(test)]
+pub(crate) fn test_record(name: &str, version: &str, build: &str, build_number: u64) -> Value {
+    serde_json::json!({
+        "build": build, "build_number": build_number, "depends": [], "md5": "abc", "name": name,
+        "size": 1, "version": version
+    })
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::{Noarch, Record, test_record};
+
+    #[test]
+    fn minimal_record() {
+        let record: Record = serde_json::from_value(test_record("a", "1.0", "0", 0)).unwrap();
+        assert_eq!(record.timestamp(), None);
+        assert_eq!(record.sha256(), None);
+        assert_eq!(record.constrains(), None);
+        assert!(record.extra().is_empty());
+    }
+
+    #[test]
+    fn noarch() {
+        let parse = |noarch: Value| -> Record {
+            let mut record = test_record("a", "1.0", "0", 0);
+            record["noarch"] = noarch;
+            serde_json::from_value(record).unwrap()
+        };
+        assert_eq!(parse(json!(true)).noarch(), Some(&Noarch::Boolean(true)));
+        assert_eq!(parse(json!("python")).noarch(), Some(&Noarch::Kind("python".to_string())));
+    }
+
+    #[test]
+    fn unknown_fields_round_trip() {
+        let mut value = test_record("a", "1.0", "0", 0);
+        for (field, field_value) in json!({
+            "app_cli_opts": [{"args": "--port %s", "name": "port"}], "depends": ["python"],
+            "license": null, "noarch": null, "timestamp": 1568316198231u64, "type": "app", "zzz": null
+        }).as_object().unwrap() {
+            value[field] = field_value.clone();
+        }
+        let record: Record = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(record.package_type(), Some("app"));
+        assert_eq!(record.license(), None);
+        assert_eq!(record.noarch(), None);
+        assert_eq!(record.extra().keys().collect::<Vec<_>>(), vec!["app_cli_opts", "zzz"]);
+        assert_eq!(serde_json::to_value(&record).unwrap(), value);
+    }
+}
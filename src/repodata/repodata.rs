@@ -0,0 +1,654 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use serde::ser::{self, Serialize};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json::ser::{Formatter, PrettyFormatter, Serializer};
+
+use crate::match_spec::MatchSpec;
+use crate::version::Version;
+use super::error::RepodataError;
+use super::package_format::{FormatPreference, PackageFormat};
+use super::record::{Record, nullable};
+
+/// The `info` section of the repodata.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RepodataInfo {
+    #[serde(default, deserialize_with = "nullable", skip_serializing_if = "Option::is_none")]
+    pub(crate) subdir: Option<Option<String>>,
+    #[serde(flatten)]
+    pub(crate) extra: BTreeMap<String, Value>,
+}
+
+impl RepodataInfo {
+    /// Get the subdir of the repodata, such as `win-64`.
+    pub fn subdir(&self) -> Option<&str> {
+        self.subdir.as_ref().and_then(Option::as_deref)
+    }
+
+    /// Get the fields other than `subdir`, such as `base_url` or `platform`.
+    pub fn extra(&self) -> &BTreeMap<String, Value> {
+        &self.extra
+    }
+}
+
+/// The repodata of a channel subdir.
+///
+/// Older channels leave out sections such as `removed` and `packages.conda`, which are then
+/// empty.  Sections other than those documented by conda are kept as they are.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Repodata {
+    #[serde(default)]
+    pub(crate) info: RepodataInfo,
+    #[serde(default)]
+    pub(crate) packages: HashMap<String, Record>,
+    #[serde(default, rename = "packages.conda")]
+    pub(crate) packages_conda: HashMap<String, Record>,
+    #[serde(default = "default_repodata_version")]
+    pub(crate) repodata_version: u8,
+    #[serde(default)]
+    pub(crate) removed: Vec<String>,
+    #[serde(flatten)]
+    pub(crate) extra: BTreeMap<String, Value>,
+}
+
+/// A JSON formatter that writes like Python's `json.dumps` with an indent of two spaces: it
+/// escapes the characters that `json.dumps` escapes by default but `serde_json` doesn't, those
+/// outside of ASCII and DEL.  Characters outside of the basic multilingual plane become a
+/// surrogate pair.
+struct AsciiFormatter(PrettyFormatter<'static>);
+
+impl AsciiFormatter {
+    fn new() -> AsciiFormatter {
+        AsciiFormatter(PrettyFormatter::with_indent(b"  "))
+    }
+}
+
+impl Formatter for AsciiFormatter {
+    fn begin_array<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.begin_array(writer)
+    }
+
+    fn end_array<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_array(writer)
+    }
+
+    fn begin_array_value<W: ?Sized + Write>(&mut self, writer: &mut W, first: bool) -> io::Result<()> {
+        self.0.begin_array_value(writer, first)
+    }
+
+    fn end_array_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_array_value(writer)
+    }
+
+    fn begin_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.begin_object(writer)
+    }
+
+    fn end_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_object(writer)
+    }
+
+    fn begin_object_key<W: ?Sized + Write>(&mut self, writer: &mut W, first: bool) -> io::Result<()> {
+        self.0.begin_object_key(writer, first)
+    }
+
+    fn begin_object_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.begin_object_value(writer)
+    }
+
+    fn end_object_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_object_value(writer)
+    }
+
+    fn write_string_fragment<W: ?Sized + Write>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()> {
+        let mut start = 0;
+        for (i, c) in fragment.char_indices() {
+            if !c.is_ascii() || c == '\x7f' {
+                writer.write_all(&fragment.as_bytes()[start..i])?;
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    write!(writer, "\\u{:04x}", unit)?;
+                }
+                start = i + c.len_utf8();
+            }
+        }
+        writer.write_all(&fragment.as_bytes()[start..])
+    }
+}
+
+/// A value serialized with the keys of all objects sorted, by going through a `Value`.  This is
+/// only used for small values, such as a single record, so that the repodata isn't copied whole.
+struct Sorted<'a, T>(&'a T);
+
+impl<T: Serialize> Serialize for Sorted<'_, T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde_json::to_value(self.0).map_err(ser::Error::custom)?.serialize(serializer)
+    }
+}
+
+/// A top level section of the repodata, to be written in the order of its key.
+enum Section<'a> {
+    Info(&'a RepodataInfo),
+    Records(&'a HashMap<String, Record>),
+    Removed(&'a [String]),
+    Version(u8),
+    Extra(&'a Value),
+}
+
+impl Serialize for Section<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Section::Info(info) => Sorted(info).serialize(serializer),
+            Section::Records(records) => {
+                let mut records: Vec<(&String, &Record)> = records.iter().collect();
+                records.sort_unstable_by_key(|&(file_name, _)| file_name);
+                serializer.collect_map(records.into_iter().map(|(file_name, record)| (file_name, Sorted(record))))
+            },
+            Section::Removed(removed) => removed.serialize(serializer),
+            Section::Version(version) => version.serialize(serializer),
+            Section::Extra(value) => value.serialize(serializer),
+        }
+    }
+}
+
+/// Adapts a `fmt::Formatter` to `io::Write`, for output that is known to be ASCII.
+struct FmtWriter<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl Write for FmtWriter<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s = std::str::from_utf8(buf).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        self.0.write_str(s).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Get the name, version and build string identifying the package of a record, whatever its
+/// format.
+fn package_key(record: &Record) -> (&str, &str, &str) {
+    (&record.name, &record.version, &record.build)
+}
+
+/// Repodata without a version predates versioning, and has the layout of version 1.
+fn default_repodata_version() -> u8 {
+    1
+}
+
+impl Repodata {
+    /// Get the `info` section.
+    pub fn info(&self) -> &RepodataInfo {
+        &self.info
+    }
+
+    /// Get the `.tar.bz2` package records, by file name.
+    pub fn packages(&self) -> &HashMap<String, Record> {
+        &self.packages
+    }
+
+    /// Get the `.conda` package records, by file name.
+    pub fn packages_conda(&self) -> &HashMap<String, Record> {
+        &self.packages_conda
+    }
+
+    /// Get the package records of the given format, by file name.
+    pub fn packages_of(&self, format: PackageFormat) -> &HashMap<String, Record> {
+        match format {
+            PackageFormat::Conda => &self.packages_conda,
+            PackageFormat::TarBz2 => &self.packages,
+        }
+    }
+
+    /// Iterate over the package records of both `packages` and `packages.conda`, with their file
+    /// names, yielding each package only once.
+    ///
+    /// A package, identified by its name, version and build string, is often served as both a
+    /// `.conda` and a `.tar.bz2` file.  The `preference` selects which of the two is yielded, the
+    /// way conda does when it loads repodata.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{FormatPreference, PackageFormat, read_repodata};
+    ///
+    /// let repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+    ///
+    /// assert_eq!(repodata.records(FormatPreference::default()).count(), 3614);
+    /// assert_eq!(repodata.records(FormatPreference::Only(PackageFormat::TarBz2)).count(), 237);
+    /// ```
+    pub fn records(&self, preference: FormatPreference) -> impl Iterator<Item = (&str, &Record)> {
+        let preferred = self.packages_of(preference.format());
+        let fallback = match preference {
+            FormatPreference::Prefer(PackageFormat::Conda) => Some(&self.packages),
+            FormatPreference::Prefer(PackageFormat::TarBz2) => Some(&self.packages_conda),
+            FormatPreference::Only(_) => None,
+        };
+        let seen: HashSet<(&str, &str, &str)> = match fallback {
+            Some(_) => preferred.values().map(package_key).collect(),
+            None => HashSet::new(),
+        };
+        preferred.iter()
+            .chain(fallback.into_iter().flatten().filter(move |(_, record)| !seen.contains(&package_key(record))))
+            .map(|(file_name, record)| (file_name.as_str(), record))
+    }
+
+    /// Get the version of the repodata format.
+    pub fn repodata_version(&self) -> u8 {
+        self.repodata_version
+    }
+
+    /// Get the file names of the packages that were removed from the channel.
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// Get the sections other than those documented by conda.
+    pub fn extra(&self) -> &BTreeMap<String, Value> {
+        &self.extra
+    }
+
+    /// Write the repodata as JSON to `writer`, formatted byte for byte like conda-index does:
+    /// with keys sorted, an indent of two spaces, non-ASCII characters escaped, and a trailing
+    /// newline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::read_repodata;
+    ///
+    /// let repodata = read_repodata("tests/data/current_repodata.json").unwrap();
+    /// let mut written = Vec::new();
+    /// repodata.write_to(&mut written).unwrap();
+    ///
+    /// assert_eq!(written, std::fs::read("tests/data/current_repodata.json").unwrap());
+    /// ```
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<(), RepodataError> {
+        let mut sections: BTreeMap<&str, Section> = self.extra.iter()
+            .map(|(key, value)| (key.as_str(), Section::Extra(value)))
+            .collect();
+        sections.insert("info", Section::Info(&self.info));
+        sections.insert("packages", Section::Records(&self.packages));
+        sections.insert("packages.conda", Section::Records(&self.packages_conda));
+        sections.insert("removed", Section::Removed(&self.removed));
+        sections.insert("repodata_version", Section::Version(self.repodata_version));
+
+        let mut serializer = Serializer::with_formatter(&mut writer, AsciiFormatter::new());
+        sections.serialize(&mut serializer).map_err(|error| RepodataError::json(None, error))?;
+        writer.write_all(b"\n").map_err(|error| RepodataError::io(None, error))
+    }
+
+    /// Find all records of both `packages` and `packages.conda` that match the given spec,
+    /// ordered by conda's preference: the highest version first, then the highest build number,
+    /// then the newest timestamp.  Records with a version that can't be parsed only match specs
+    /// without a version constraint, and come last.
+    pub fn find(&self, spec: &MatchSpec) -> Vec<&Record> {
+        let mut found: Vec<(Option<Version>, &Record)> = self.packages.values()
+            .chain(self.packages_conda.values())
+            .filter(|record| spec.matches(record))
+            .map(|record| (Version::from(&record.version).ok(), record))
+            .collect();
+        found.sort_by(|(version_a, a), (version_b, b)| {
+            version_b.cmp(version_a)
+                .then_with(|| b.build_number.cmp(&a.build_number))
+                .then_with(|| b.timestamp().cmp(&a.timestamp()))
+        });
+        found.into_iter().map(|(_, record)| record).collect()
+    }
+}
+
+/// Format the repodata as JSON, the way `write_to` writes it.
+impl fmt::Display for Repodata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_to(FmtWriter(f)).map_err(|_| fmt::Error)
+    }
+}
+
+/// The compression of a repodata file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Compression {
+    None,
+    Bzip2,
+    Zstd,
+    Gzip,
+}
+
+impl Compression {
+    /// Detect the compression from the extension of `path`, such as `.bz2` in
+    /// `repodata.json.bz2`.
+    fn from_extension(path: &Path) -> Option<Compression> {
+        match path.extension()?.to_str()? {
+            "bz2" => Some(Compression::Bzip2),
+            "zst" => Some(Compression::Zstd),
+            "gz" => Some(Compression::Gzip),
+            "json" => Some(Compression::None),
+            _ => None,
+        }
+    }
+
+    /// Detect the compression from the magic bytes at the start of the data.
+    fn from_magic(magic: &[u8]) -> Compression {
+        if magic.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else if magic.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Wrap `reader` in a reader that decompresses while reading.
+    fn decoder<'r, R: BufRead + 'r>(self, reader: R) -> io::Result<Box<dyn Read + 'r>> {
+        // `serde_json` reads byte by byte, so the decoders are buffered
+        Ok(match self {
+            Compression::None => Box::new(reader),
+            Compression::Bzip2 => Box::new(BufReader::new(bzip2::bufread::MultiBzDecoder::new(reader))),
+            Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+            Compression::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        })
+    }
+}
+
+/// Read the repodata JSON file at `path`.
+///
+/// Files compressed with bzip2, zstd or gzip, such as `repodata.json.bz2` or
+/// `repodata.json.zst`, are decompressed while reading.  The compression is detected from the
+/// extension of the file or, if the extension isn't known, from the magic bytes at its start.
+///
+/// An error is returned if the file can't be read or doesn't hold valid repodata.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{RepodataError, read_repodata};
+///
+/// assert!(read_repodata("tests/data/current_repodata.json").is_ok());
+///
+/// match read_repodata("missing/repodata.json") {
+///     Err(RepodataError::Io { .. }) => {},
+///     _ => panic!("expected an I/O error"),
+/// }
+/// ```
+pub fn read_repodata<P: AsRef<Path>>(path: P) -> Result<Repodata, RepodataError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|error| RepodataError::io(Some(path), error))?;
+    parse_repodata(file, Some(path))
+}
+
+/// Read repodata JSON from `reader`, such as an HTTP response body.
+///
+/// Like `read_repodata`, data compressed with bzip2, zstd or gzip is decompressed while reading,
+/// with the compression detected from the magic bytes at its start.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::read_repodata_from;
+///
+/// let repodata = read_repodata_from(&br#"{"info": {"subdir": "noarch"}}"#[..]).unwrap();
+///
+/// assert_eq!(repodata.info().subdir(), Some("noarch"));
+/// ```
+pub fn read_repodata_from<R: Read>(reader: R) -> Result<Repodata, RepodataError> {
+    parse_repodata(reader, None)
+}
+
+/// Read repodata JSON from `reader`, with `path` being the file it comes from, if any.
+pub(crate) fn parse_repodata<R: Read>(reader: R, path: Option<&Path>) -> Result<Repodata, RepodataError> {
+    let mut reader = BufReader::new(reader);
+    let compression = match path.and_then(Compression::from_extension) {
+        Some(compression) => compression,
+        None => Compression::from_magic(reader.fill_buf().map_err(|error| RepodataError::io(path, error))?),
+    };
+    let reader = compression.decoder(reader).map_err(|error| RepodataError::io(path, error))?;
+    serde_json::from_reader(reader).map_err(|error| RepodataError::json(path, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+    use crate::repodata::record::test_record;
+
+    #[test]
+    fn test_load_repodata() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("tests/data/current_repodata.json");
+        println!("{}", d.display());
+        let _u: Repodata = read_repodata(d).unwrap();
+        assert_eq!(_u.info.subdir(), Some("win-64"));
+    }
+
+    fn load_repodata() -> Repodata {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("tests/data/current_repodata.json");
+        read_repodata(d).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Reading and writing the repodata loses no fields
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("tests/data/current_repodata.json");
+        let value: Value = serde_json::from_str(&std::fs::read_to_string(&d).unwrap()).unwrap();
+        let repodata: Repodata = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&repodata).unwrap(), value);
+
+        let value = serde_json::json!({
+            "info": {"subdir": "noarch", "base_url": "https://example.com"},
+            "packages": {}, "packages.conda": {}, "removed": [], "repodata_version": 1,
+            "signatures": {"a-1-0.tar.bz2": {}}
+        });
+        let repodata: Repodata = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(repodata.info().extra()["base_url"], "https://example.com");
+        assert_eq!(repodata.extra().keys().collect::<Vec<_>>(), vec!["signatures"]);
+        assert_eq!(serde_json::to_value(&repodata).unwrap(), value);
+
+        let value = serde_json::json!({
+            "info": {"subdir": null}, "packages": {}, "packages.conda": {}, "removed": [],
+            "repodata_version": 1
+        });
+        let repodata: Repodata = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(repodata.info().subdir(), None);
+        assert_eq!(serde_json::to_value(&repodata).unwrap(), value);
+    }
+
+    #[test]
+    fn test_write_to() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("tests/data/current_repodata.json");
+        let repodata = read_repodata(&d).unwrap();
+        assert_eq!(repodata.to_string(), std::fs::read_to_string(&d).unwrap());
+
+        let repodata = parse_repodata(&br#"{"info": {"subdir": "noarch", "arch": null}, "abc": 1, "zzz": "\u00e9\ud83d\ude00\u007f\n"}"#[..], None).unwrap();
+        let mut written = Vec::new();
+        repodata.write_to(&mut written).unwrap();
+        assert_eq!(std::str::from_utf8(&written).unwrap(), concat!(
+            "{\n",
+            "  \"abc\": 1,\n",
+            "  \"info\": {\n",
+            "    \"arch\": null,\n",
+            "    \"subdir\": \"noarch\"\n",
+            "  },\n",
+            "  \"packages\": {},\n",
+            "  \"packages.conda\": {},\n",
+            "  \"removed\": [],\n",
+            "  \"repodata_version\": 1,\n",
+            "  \"zzz\": \"\\u00e9\\ud83d\\ude00\\u007f\\n\"\n",
+            "}\n",
+        ));
+        assert_eq!(repodata.to_string().as_bytes(), &written[..]);
+
+        match repodata.write_to(&mut [0; 16][..]) {
+            Err(RepodataError::Io { path: None, .. }) => {},
+            result => panic!("expected an I/O error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_missing_sections() {
+        let repodata = parse_repodata(&br#"{"info": {"subdir": "noarch"}, "packages": {}}"#[..], None).unwrap();
+        assert!(repodata.packages_conda.is_empty());
+        assert!(repodata.removed.is_empty());
+        assert_eq!(repodata.repodata_version, 1);
+
+        let repodata = parse_repodata(&b"{}"[..], None).unwrap();
+        assert_eq!(repodata.info.subdir(), None);
+        assert!(repodata.packages.is_empty());
+    }
+
+    #[test]
+    fn test_io_error() {
+        match read_repodata("tests/data/missing.json") {
+            Err(RepodataError::Io { path, source }) => {
+                assert_eq!(path, Some(PathBuf::from("tests/data/missing.json")));
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+            },
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_json_error() {
+        let err = parse_repodata(&b"{\n  \"info\": {,\n}"[..], Some(Path::new("repodata.json"))).unwrap_err();
+        match err {
+            RepodataError::Json { line: 2, column: 12, .. } => {},
+            ref other => panic!("unexpected error {:?}", other),
+        }
+        assert_eq!(err.path(), Some(Path::new("repodata.json")));
+        assert!(err.to_string().starts_with("invalid JSON in repodata repodata.json: "), "{}", err);
+
+        match parse_repodata(&b"{\"packages\": "[..], None).unwrap_err() {
+            RepodataError::Json { line: 1, column: 13, .. } => {},
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_schema_error() {
+        let json = br#"{"packages": {"a-1-0.tar.bz2": {"name": "a", "version": 1}}}"#;
+        let err = parse_repodata(&json[..], None).unwrap_err();
+        match err {
+            RepodataError::Schema { line: 1, .. } => {},
+            ref other => panic!("unexpected error {:?}", other),
+        }
+        assert_eq!(err.position().map(|(line, _)| line), Some(1));
+        assert!(err.to_string().starts_with("invalid repodata: invalid type: integer `1`"), "{}", err);
+    }
+
+    #[rstest_parametrize(extension,
+    case("bz2"),
+    case("zst"),
+    case("gz"),
+    )]
+    fn test_compressed(extension: &str) {
+        let json = std::fs::read("tests/data/current_repodata.json").unwrap();
+        let compressed = match extension {
+            "bz2" => {
+                let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::fast());
+                encoder.write_all(&json).unwrap();
+                encoder.finish().unwrap()
+            },
+            "zst" => zstd::encode_all(&json[..], 0).unwrap(),
+            _ => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+                encoder.write_all(&json).unwrap();
+                encoder.finish().unwrap()
+            },
+        };
+        let expected = load_repodata();
+        assert_eq!(read_repodata_from(&compressed[..]).unwrap(), expected);
+
+        let path = std::env::temp_dir().join(format!("libronda-{}-repodata.json.{}", std::process::id(), extension));
+        std::fs::write(&path, &compressed).unwrap();
+        let repodata = read_repodata(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(repodata.unwrap(), expected);
+
+        // A corrupt stream is an error rather than truncated repodata
+        let corrupt = &compressed[..compressed.len() / 2];
+        assert!(read_repodata_from(corrupt).is_err());
+    }
+
+    #[test]
+    fn test_extension_over_magic() {
+        // A `.json` file is read as JSON, even if it happens to start like compressed data
+        let err = parse_repodata(&b"BZh91AY"[..], Some(Path::new("repodata.json"))).unwrap_err();
+        match err {
+            RepodataError::Json { line: 1, column: 1, .. } => {},
+            ref other => panic!("unexpected error {:?}", other),
+        }
+        let err = parse_repodata(&b"{}"[..], Some(Path::new("repodata.json.zst"))).unwrap_err();
+        match err {
+            RepodataError::Io { .. } => {},
+            ref other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_records() {
+        let record = |build: &str| test_record("a", "1.0", build, 0);
+        let repodata: Repodata = serde_json::from_value(serde_json::json!({
+            "packages": {"a-1.0-0.tar.bz2": record("0"), "a-1.0-1.tar.bz2": record("1")},
+            "packages.conda": {"a-1.0-0.conda": record("0"), "a-1.0-2.conda": record("2")},
+        })).unwrap();
+        let records = |preference| {
+            let mut file_names: Vec<&str> = repodata.records(preference).map(|(file_name, _)| file_name).collect();
+            file_names.sort();
+            file_names
+        };
+        assert_eq!(records(FormatPreference::default()), vec!["a-1.0-0.conda", "a-1.0-1.tar.bz2", "a-1.0-2.conda"]);
+        assert_eq!(records(FormatPreference::Prefer(PackageFormat::TarBz2)),
+            vec!["a-1.0-0.tar.bz2", "a-1.0-1.tar.bz2", "a-1.0-2.conda"]);
+        assert_eq!(records(FormatPreference::Only(PackageFormat::Conda)), vec!["a-1.0-0.conda", "a-1.0-2.conda"]);
+        assert_eq!(records(FormatPreference::Only(PackageFormat::TarBz2)), vec!["a-1.0-0.tar.bz2", "a-1.0-1.tar.bz2"]);
+
+        let repodata = load_repodata();
+        let file_names: HashSet<&str> = repodata.records(FormatPreference::default()).map(|(file_name, _)| file_name).collect();
+        assert_eq!(file_names.len(), repodata.packages.len() + repodata.packages_conda.len());
+    }
+
+    #[test]
+    fn test_find() {
+        let repodata = load_repodata();
+        let found: Vec<(&str, &str)> = repodata.find(&"openssl >=1.1".parse().unwrap()).iter()
+            .map(|record| (record.version(), record.build()))
+            .collect();
+        assert_eq!(found, vec![
+            ("1.1.1d", "he774522_0"),
+            ("1.1.1d", "h0c8e037_0"),
+            ("1.1.1c", "he774522_1"),
+            ("1.1.1c", "h0c8e037_1"),
+        ]);
+
+        let found = repodata.find(&"openssl[md5=2DF8486FA2FE89102479ABE4B161D381]".parse().unwrap());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].build(), "he774522_0");
+        assert_eq!(repodata.find(&"vc[track_features=vc9]".parse().unwrap()).len(), 2);
+        assert!(repodata.find(&"openssl 3.*".parse().unwrap()).is_empty());
+        assert!(repodata.find(&"openssl[subdir=linux-64]".parse().unwrap()).is_empty());
+        assert_eq!(repodata.find(&"openssl[subdir=win-64]".parse().unwrap()).len(), 8);
+    }
+
+    #[test]
+    fn test_find_invalid_versions() {
+        let repodata: Repodata = serde_json::from_value(serde_json::json!({"packages": {
+            "a-x!1-0.tar.bz2": test_record("a", "x!1", "0", 1),
+            "a-1.0-0.tar.bz2": test_record("a", "1.0", "0", 0),
+            "a-2.0-0.tar.bz2": test_record("a", "2.0", "0", 0),
+        }})).unwrap();
+        let versions = |spec: &str| -> Vec<&str> {
+            repodata.find(&spec.parse().unwrap()).iter().map(|record| record.version()).collect()
+        };
+        assert_eq!(versions("a"), vec!["2.0", "1.0", "x!1"]);
+        assert_eq!(versions("a[build_number=1]"), vec!["x!1"]);
+        assert_eq!(versions("a >=1"), vec!["2.0", "1.0"]);
+    }
+}
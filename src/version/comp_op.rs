@@ -60,8 +60,9 @@ impl CompOp {
     /// assert_eq!(CompOp::from_sign("  >=   "), Ok(CompOp::Ge));
     /// assert!(CompOp::from_sign("*").is_err());
     /// ```
+    #[allow(clippy::result_unit_err)]
     pub fn from_sign(sign: &str) -> Result<CompOp, ()> {
-        match sign.trim().as_ref() {
+        match sign.trim() {
             "==" | "=" => Ok(CompOp::Eq),
             "!=" | "!" | "<>" => Ok(CompOp::Ne),
             "<" => Ok(CompOp::Lt),
@@ -86,6 +87,7 @@ impl CompOp {
     /// assert_eq!(CompOp::from_name("  Ge   "), Ok(CompOp::Ge));
     /// assert!(CompOp::from_name("abc").is_err());
     /// ```
+    #[allow(clippy::result_unit_err)]
     pub fn from_name(sign: &str) -> Result<CompOp, ()> {
         match sign.trim().to_lowercase().as_ref() {
             "eq" => Ok(CompOp::Eq),
@@ -125,13 +127,13 @@ impl CompOp {
     /// assert_eq!(CompOp::Ge.name(), "ge");
     /// ```
     pub fn name(&self) -> &str {
-        match self {
-            &CompOp::Eq => "eq",
-            &CompOp::Ne => "ne",
-            &CompOp::Lt => "lt",
-            &CompOp::Le => "le",
-            &CompOp::Ge => "ge",
-            &CompOp::Gt => "gt",
+        match *self {
+            CompOp::Eq => "eq",
+            CompOp::Ne => "ne",
+            CompOp::Lt => "lt",
+            CompOp::Le => "le",
+            CompOp::Ge => "ge",
+            CompOp::Gt => "gt",
         }
     }
 
@@ -174,13 +176,13 @@ impl CompOp {
     /// assert_eq!(CompOp::Gt.invert(), CompOp::Le);
     /// ```
     pub fn invert(&self) -> Self {
-        match self {
-            &CompOp::Eq => CompOp::Ne,
-            &CompOp::Ne => CompOp::Eq,
-            &CompOp::Lt => CompOp::Ge,
-            &CompOp::Le => CompOp::Gt,
-            &CompOp::Ge => CompOp::Lt,
-            &CompOp::Gt => CompOp::Le,
+        match *self {
+            CompOp::Eq => CompOp::Ne,
+            CompOp::Ne => CompOp::Eq,
+            CompOp::Lt => CompOp::Ge,
+            CompOp::Le => CompOp::Gt,
+            CompOp::Ge => CompOp::Lt,
+            CompOp::Gt => CompOp::Le,
         }
     }
 
@@ -223,13 +225,13 @@ impl CompOp {
     /// assert_eq!(CompOp::Ge.opposite(), CompOp::Le);
     /// ```
     pub fn opposite(&self) -> Self {
-        match self {
-            &CompOp::Eq => CompOp::Ne,
-            &CompOp::Ne => CompOp::Eq,
-            &CompOp::Lt => CompOp::Gt,
-            &CompOp::Le => CompOp::Ge,
-            &CompOp::Ge => CompOp::Le,
-            &CompOp::Gt => CompOp::Lt,
+        match *self {
+            CompOp::Eq => CompOp::Ne,
+            CompOp::Ne => CompOp::Eq,
+            CompOp::Lt => CompOp::Gt,
+            CompOp::Le => CompOp::Ge,
+            CompOp::Ge => CompOp::Le,
+            CompOp::Gt => CompOp::Lt,
         }
     }
 
@@ -272,11 +274,11 @@ impl CompOp {
     /// assert_eq!(CompOp::Ge.flip(), CompOp::Le);
     /// ```
     pub fn flip(&self) -> Self {
-        match self {
-            &CompOp::Lt => CompOp::Gt,
-            &CompOp::Le => CompOp::Ge,
-            &CompOp::Ge => CompOp::Le,
-            &CompOp::Gt => CompOp::Lt,
+        match *self {
+            CompOp::Lt => CompOp::Gt,
+            CompOp::Le => CompOp::Ge,
+            CompOp::Ge => CompOp::Le,
+            CompOp::Gt => CompOp::Lt,
             _ => self.clone(),
         }
     }
@@ -306,13 +308,13 @@ impl CompOp {
     /// assert_eq!(CompOp::Ge.flip().sign(), "<=");
     /// ```
     pub fn sign(&self) -> &'static str {
-        match self {
-            &CompOp::Eq => "==",
-            &CompOp::Ne => "!=",
-            &CompOp::Lt => "<",
-            &CompOp::Le => "<=",
-            &CompOp::Ge => ">=",
-            &CompOp::Gt => ">",
+        match *self {
+            CompOp::Eq => "==",
+            CompOp::Ne => "!=",
+            CompOp::Lt => "<",
+            CompOp::Le => "<=",
+            CompOp::Ge => ">=",
+            CompOp::Gt => ">",
         }
     }
 
@@ -337,10 +339,10 @@ impl CompOp {
     /// assert_eq!(10 * ver_b.compare(&ver_a).factor(), 10);
     /// ```
     pub fn factor(&self) -> i8 {
-        match self {
-            &CompOp::Eq | &CompOp::Ne => 0,
-            &CompOp::Lt | &CompOp::Le => -1,
-            &CompOp::Gt | &CompOp::Ge => 1,
+        match *self {
+            CompOp::Eq | CompOp::Ne => 0,
+            CompOp::Lt | CompOp::Le => -1,
+            CompOp::Gt | CompOp::Ge => 1,
         }
     }
 
@@ -366,10 +368,10 @@ impl CompOp {
     /// assert_eq!(ver_a.compare(&ver_b).ord().unwrap(), Ordering::Less);
     /// ```
     pub fn ord(&self) -> Option<Ordering> {
-        match self {
-            &CompOp::Eq => Some(Ordering::Equal),
-            &CompOp::Lt => Some(Ordering::Less),
-            &CompOp::Gt => Some(Ordering::Greater),
+        match *self {
+            CompOp::Eq => Some(Ordering::Equal),
+            CompOp::Lt => Some(Ordering::Less),
+            CompOp::Gt => Some(Ordering::Greater),
             _ => None,
         }
     }
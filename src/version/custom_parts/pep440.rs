@@ -1,89 +1,213 @@
+//! A conda version component, such as the `1a1` in `1.1a1` or the `post1` in `1.0.post1`.
+//!
+//! Each component is split into runs of digits, runs of `*` and runs of other characters, which
+//! are compared the way conda's `VersionOrder` compares them:
+//!
+//! * numbers compare numerically, and missing runs are filled with `0`,
+//! * strings compare lexicographically and case-insensitively, and always sort before numbers,
+//! * `dev` sorts before all other strings, so `*` < `dev` < `_` < `a` < `""` < number,
+//! * `post` sorts after all numbers.
+
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt;
-use regex::Regex;
+use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Copy, Clone)]
-pub struct PEP440String<'a> {
-    pre: i16,
-    alpha: &'a str,
-    post: i16,
+use crate::version::VersionParseError;
+
+/// A single run within a version component.
+#[derive(Debug, Clone)]
+pub enum Atom<'a> {
+    /// A run of digits.
+    Number(u64),
+    /// A run of letters, `_`, or `*`, stored in lower case.
+    Text(Cow<'a, str>),
+    /// The special string `post`, which sorts after all numbers.
+    Post,
 }
 
-impl<'a> PEP440String<'a> {
-    pub fn from(input: &'a str) -> PEP440String {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^(\d*)([a-zA-Z]*)(\d*)").unwrap();
+impl<'a> Atom<'a> {
+    /// Convert into an `Atom` that owns its text.
+    pub fn into_owned(self) -> Atom<'static> {
+        match self {
+            Atom::Number(n) => Atom::Number(n),
+            Atom::Text(s) => Atom::Text(Cow::Owned(s.into_owned())),
+            Atom::Post => Atom::Post,
         }
+    }
 
-        let caps = RE.captures(input).unwrap();
-        let pre: i16 = caps.get(1).map_or(0, |m| match m.as_str().is_empty() {
-            true => 0,
-            false => m.as_str().parse().unwrap()
-        });
-        let alpha = caps.get(2).map_or("", |m| m.as_str());
-        let post: i16 = caps.get(3).map_or(0, |m| match m.as_str().is_empty() {
-            true => 0,
-            false => m.as_str().parse().unwrap()
-        });
+    /// Build the atom for a run of non-digit characters.
+    fn text(run: &'a str) -> Atom<'a> {
+        let text: Cow<'a, str> = if run.bytes().any(|b| b.is_ascii_uppercase() || b == b'-') {
+            // dashes are accepted in place of underscores
+            Cow::Owned(run.to_ascii_lowercase().replace('-', "_"))
+        } else {
+            Cow::Borrowed(run)
+        };
+        match text.as_ref() {
+            "post" => Atom::Post,
+            _ => Atom::Text(text),
+        }
+    }
 
-        PEP440String{ pre, alpha, post }
+    /// Whether this atom starts with `prefix`.  Text atoms match by string prefix, other atoms
+    /// must be equal.
+    pub(crate) fn starts_with(&self, prefix: &Atom) -> bool {
+        match (self, prefix) {
+            (Atom::Text(a), Atom::Text(b)) => Atom::sort_key(a).starts_with(Atom::sort_key(b)),
+            _ => self == prefix,
+        }
     }
 
-    pub fn empty() -> PEP440String<'a> {
-        PEP440String {pre: 0, alpha: "", post: 0}
+    /// The string used to order text atoms.  `dev` is upper-cased so that it sorts before `_`
+    /// and all lower case strings.
+    fn sort_key(text: &str) -> &str {
+        match text {
+            "dev" => "DEV",
+            _ => text,
+        }
+    }
+}
+
+impl<'a> PartialOrd for Atom<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(match (self, other) {
+            (Atom::Number(a), Atom::Number(b)) => a.cmp(b),
+            (Atom::Text(a), Atom::Text(b)) => Atom::sort_key(a).cmp(Atom::sort_key(b)),
+            (Atom::Post, Atom::Post) => Ordering::Equal,
+            // strings sort before numbers and post
+            (Atom::Text(_), _) => Ordering::Less,
+            (_, Atom::Text(_)) => Ordering::Greater,
+            // post sorts after all numbers
+            (Atom::Post, Atom::Number(_)) => Ordering::Greater,
+            (Atom::Number(_), Atom::Post) => Ordering::Less,
+        })
+    }
+}
+
+impl<'a> PartialEq for Atom<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
     }
 }
 
-fn compare_pep440_str<'a>(left: &'a str, right: &'a str) -> Option<Ordering> {
-    lazy_static! { static ref DEV_RE: Regex = Regex::new("dev").unwrap(); }
-    lazy_static! { static ref POST_RE: Regex = Regex::new("post").unwrap(); }
-
-    let is_dev = (DEV_RE.is_match(left), DEV_RE.is_match(right));
-    let is_post = (POST_RE.is_match(left), POST_RE.is_match(right));
-
-    let str_match = left.partial_cmp(right);
-    match str_match {
-        Some(Ordering::Equal) => Some(Ordering::Equal),
-        _ => match is_dev {
-            (false, true) => Some(Ordering::Greater),
-            (true, false) => Some(Ordering::Less),
-            _ => match is_post {
-                (true, true) => Some(Ordering::Equal),
-                (false, true) => Some(Ordering::Less),
-                (true, false) => Some(Ordering::Greater),
-                // this is the final fallback to lexicographic sorting, if neither
-                //   dev nor post are in effect
-                (false, false) => left.partial_cmp(right),
-            }
+impl<'a> Eq for Atom<'a> {}
+
+impl<'a> Hash for Atom<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Atom::Number(n) => (0u8, n).hash(state),
+            Atom::Text(s) => (1u8, s).hash(state),
+            Atom::Post => 2u8.hash(state),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Atom<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Atom::Number(n) => write!(f, "{}", n),
+            Atom::Text(s) => write!(f, "{}", s),
+            Atom::Post => write!(f, "post"),
+        }
+    }
+}
+
+/// Compare two components atom by atom, filling the shorter one with `0`.
+pub(crate) fn compare_atoms(a: &[Atom], b: &[Atom]) -> Ordering {
+    let fill = Atom::Number(0);
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&fill).partial_cmp(b.get(i).unwrap_or(&fill));
+        match ordering {
+            Some(Ordering::Equal) => continue,
+            Some(ordering) => return ordering,
+            None => unreachable!(),
+        }
+    }
+    Ordering::Equal
+}
+
+/// Hash a component consistently with `compare_atoms`, by leaving out the trailing zeros that
+/// compare equal to the fill value.
+pub(crate) fn hash_atoms<H: Hasher>(atoms: &[Atom], state: &mut H) {
+    let len = atoms.iter().rposition(|atom| *atom != Atom::Number(0)).map_or(0, |i| i + 1);
+    atoms[..len].hash(state);
+}
+
+#[derive(Debug, Clone)]
+pub struct PEP440String<'a> {
+    atoms: Vec<Atom<'a>>,
+}
+
+impl<'a> PEP440String<'a> {
+    /// Split a version component into its runs.  Components that don't start with a number get
+    /// an implicit leading `0`, so that numbers and strings stay in phase: `dev1` is `0dev1`.
+    pub fn from(input: &'a str) -> Result<PEP440String<'a>, VersionParseError> {
+        let bytes = input.as_bytes();
+        let class = |b: u8| match b {
+            b'0'..=b'9' => 0,
+            b'*' => 1,
+            _ => 2,
+        };
+
+        let mut atoms = Vec::with_capacity(3);
+        if !bytes.first().is_some_and(u8::is_ascii_digit) {
+            atoms.push(Atom::Number(0));
         }
+
+        let mut start = 0;
+        while start < bytes.len() {
+            let run_class = class(bytes[start]);
+            let end = bytes[start..].iter()
+                .position(|&b| class(b) != run_class)
+                .map_or(bytes.len(), |len| start + len);
+            let run = &input[start..end];
+            atoms.push(match run_class {
+                0 => Atom::Number(run.parse()
+                    .map_err(|_| VersionParseError::NumericOverflow { offset: start })?),
+                1 => Atom::Text(Cow::Borrowed(run)),
+                _ => Atom::text(run),
+            });
+            start = end;
+        }
+
+        Ok(PEP440String{ atoms })
+    }
+
+    /// Build a component from its runs.
+    pub(crate) fn from_atoms(atoms: Vec<Atom<'a>>) -> PEP440String<'a> {
+        PEP440String { atoms }
+    }
+
+    /// Get the runs of this component.
+    pub fn atoms(&self) -> &[Atom<'a>] {
+        &self.atoms
+    }
+
+    /// Convert into a `PEP440String` that owns its text runs.
+    pub fn into_owned(self) -> PEP440String<'static> {
+        PEP440String { atoms: self.atoms.into_iter().map(Atom::into_owned).collect() }
     }
 }
 
 impl<'a> PartialOrd for PEP440String<'a> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match self.pre.partial_cmp(&other.pre) {
-            Some(Ordering::Greater) => Some(Ordering::Greater),
-            Some(Ordering::Less) => Some(Ordering::Less),
-            Some(Ordering::Equal) => match compare_pep440_str(self.alpha, &other.alpha) {
-                Some(Ordering::Equal) => self.post.partial_cmp(&other.post),
-                Some(Ordering::Greater) => Some(Ordering::Greater),
-                Some(Ordering::Less) => Some(Ordering::Less),
-                _ => panic!()
-            }
-            _ => panic!()
-        }
+        Some(compare_atoms(&self.atoms, &other.atoms))
     }
 }
 
 impl<'a> PartialEq for PEP440String<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.partial_cmp(&other).unwrap() == Ordering::Equal
+        self.partial_cmp(other) == Some(Ordering::Equal)
     }
 }
 
 impl<'a> fmt::Display for PEP440String<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}{}{}", self.pre, self.alpha, self.post)
+        for atom in &self.atoms {
+            write!(f, "{}", atom)?;
+        }
+        Ok(())
     }
 }
 
@@ -91,12 +215,54 @@ impl<'a> fmt::Display for PEP440String<'a> {
 #[cfg(test)]
 mod tests {
     use super::PEP440String;
+    use crate::version::VersionParseError;
 
     #[test]
     fn compare_implict_leading_zero() {
-        assert_eq!(PEP440String::from("0dev"), PEP440String::from("dev"));
+        assert_eq!(PEP440String::from("0dev").unwrap(), PEP440String::from("dev").unwrap());
         // epoch of any value trumps integer (priority)
         // assert!(VersionPart::Epoch(value: 0) > VersionPart::Integer(value: 1);
         // assert!(Version::Epoch{0} > Version::String{"abc"});
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn special_strings() {
+        let ordered = ["1*", "1dev", "1_", "1a", "1rc", "1", "1post"];
+        for pair in ordered.windows(2) {
+            let a = PEP440String::from(pair[0]).unwrap();
+            let b = PEP440String::from(pair[1]).unwrap();
+            assert!(a < b, "{} < {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn case_insensitive() {
+        assert_eq!(PEP440String::from("1RC1").unwrap(), PEP440String::from("1rc1").unwrap());
+        assert_eq!(PEP440String::from("DEV").unwrap(), PEP440String::from("dev").unwrap());
+    }
+
+    #[test]
+    fn more_than_three_runs() {
+        // the trailing string sorts below the implicit zero fill
+        assert!(PEP440String::from("abc123def").unwrap() < PEP440String::from("abc123").unwrap());
+        assert!(PEP440String::from("abc123def").unwrap() < PEP440String::from("abc124").unwrap());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(format!("{}", PEP440String::from("1RC1").unwrap()), "1rc1");
+        assert_eq!(format!("{}", PEP440String::from("post1").unwrap()), "0post1");
+    }
+
+    #[test]
+    fn numeric_overflow() {
+        assert_eq!(
+            PEP440String::from("99999999999999999999a").unwrap_err(),
+            VersionParseError::NumericOverflow { offset: 0 },
+        );
+        assert_eq!(
+            PEP440String::from("rc99999999999999999999").unwrap_err(),
+            VersionParseError::NumericOverflow { offset: 2 },
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Version editing module, which adds methods to derive new versions from a `Version`.
+//!
+//! New versions are built from the original version string rather than from the parsed parts, so
+//! that separators, letter case and text segments that aren't edited keep their formatting.
+//! Edits return a `VersionParseError` when the new version isn't valid, such as when the number
+//! to increment overflows.
+
+use std::cmp;
+
+use super::parse_error::VersionParseError;
+use super::version::{Version, component_spans};
+use super::version_buf::VersionBuf;
+
+/// Assemble a version from its epoch, public version and local version.
+fn assemble(epoch: Option<&str>, public: &str, local: Option<&str>) -> Result<VersionBuf, VersionParseError> {
+    let mut version = String::with_capacity(public.len() + 8);
+    if let Some(epoch) = epoch {
+        version.push_str(epoch);
+        version.push('!');
+    }
+    version.push_str(public);
+    if let Some(local) = local {
+        version.push('+');
+        version.push_str(local);
+    }
+    version.parse()
+}
+
+/// Find where a trailing `dev` or `post` segment, optionally followed by a number, starts in a
+/// component.
+fn dev_post_start(component: &str) -> Option<usize> {
+    let lower = component.to_ascii_lowercase();
+    ["dev", "post"].iter()
+        .filter_map(|tag| lower.rfind(tag).filter(|&i| lower[i + tag.len()..].bytes().all(|b| b.is_ascii_digit())))
+        .max()
+}
+
+/// Increment the leading number of a component found at `offset`, dropping anything that
+/// follows it.
+fn increment(component: &str, offset: usize) -> Result<String, VersionParseError> {
+    let digits = component.bytes().take_while(u8::is_ascii_digit).count();
+    let number: u64 = component[..digits].parse().unwrap_or(0);
+    number.checked_add(1)
+        .map(|number| number.to_string())
+        .ok_or(VersionParseError::NumericOverflow { offset })
+}
+
+impl<'a> Version<'a> {
+    /// Get the next major version, see `bump`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// assert_eq!(Version::from("1.2.3").unwrap().bump_major().unwrap().as_str(), "2.0.0");
+    /// ```
+    pub fn bump_major(&self) -> Result<VersionBuf, VersionParseError> {
+        self.bump(0)
+    }
+
+    /// Get the next minor version, see `bump`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// assert_eq!(Version::from("1.2.3").unwrap().bump_minor().unwrap().as_str(), "1.3.0");
+    /// ```
+    pub fn bump_minor(&self) -> Result<VersionBuf, VersionParseError> {
+        self.bump(1)
+    }
+
+    /// Get the next patch version, see `bump`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// assert_eq!(Version::from("1_2").unwrap().bump_patch().unwrap().as_str(), "1_2_1");
+    /// ```
+    pub fn bump_patch(&self) -> Result<VersionBuf, VersionParseError> {
+        self.bump(2)
+    }
+
+    /// Get the next version, by incrementing the leading number of the public component at
+    /// `index`.
+    ///
+    /// Text following that number, the `dev` and `post` segments and the local version are
+    /// dropped, and the components after `index` are reset to `0`.  Missing components are added
+    /// as `0`, using the last separator of the version.  The epoch is kept.
+    ///
+    /// This fails with `NumericOverflow` when the number to increment is already `u64::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// let ver = Version::from("1!1.2rc1.dev0+local").unwrap();
+    ///
+    /// assert_eq!(ver.bump(1).unwrap().as_str(), "1!1.3");
+    /// assert_eq!(ver.bump(3).unwrap().as_str(), "1!1.2rc1.0.1");
+    /// ```
+    pub fn bump(&self, index: usize) -> Result<VersionBuf, VersionParseError> {
+        let release = self.without_dev_post()?;
+        let (epoch, public, _) = release.split_str();
+        let spans = component_spans(public);
+        let separator = public.rfind(['.', '_', '-']).map_or(".", |i| &public[i..=i]);
+
+        let mut bumped = String::with_capacity(public.len() + 4);
+        for i in 0..=cmp::max(index, spans.len().saturating_sub(1)) {
+            if i > 0 {
+                bumped.push_str(spans.get(i).map_or(separator, |&(start, _)| &public[start - 1..start]));
+            }
+            let component = spans.get(i).map_or("0", |&(start, end)| &public[start..end]);
+            match i.cmp(&index) {
+                cmp::Ordering::Less => bumped.push_str(component),
+                cmp::Ordering::Equal => {
+                    let offset = epoch.map_or(0, |epoch| epoch.len() + 1) + spans.get(i).map_or(0, |&(start, _)| start);
+                    bumped.push_str(&increment(component, offset)?)
+                }
+                cmp::Ordering::Greater => bumped.push('0'),
+            }
+        }
+        assemble(epoch, &bumped, None)
+    }
+
+    /// Truncate the public version to its first `count` components, and drop the local version.
+    /// At least one component is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// assert_eq!(Version::from("1.2.3.4").unwrap().truncate(2).unwrap().as_str(), "1.2");
+    /// assert_eq!(Version::from("2!1_2rc1_3+abc").unwrap().truncate(2).unwrap().as_str(), "2!1_2rc1");
+    /// assert_eq!(Version::from("1.2").unwrap().truncate(3).unwrap().as_str(), "1.2");
+    /// ```
+    pub fn truncate(&self, count: usize) -> Result<VersionBuf, VersionParseError> {
+        let (epoch, public, _) = self.split_str();
+        let spans = component_spans(public);
+        let end = spans.get(cmp::max(count, 1) - 1).map_or(public.len(), |&(_, end)| end);
+        assemble(epoch, &public[..end], None)
+    }
+
+    /// Get this version without its local version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// assert_eq!(Version::from("1.2.3+cuda.11").unwrap().without_local().unwrap().as_str(), "1.2.3");
+    /// ```
+    pub fn without_local(&self) -> Result<VersionBuf, VersionParseError> {
+        let (epoch, public, _) = self.split_str();
+        assemble(epoch, public, None)
+    }
+
+    /// Get this version without its trailing `dev` and `post` segments, such as the `.post1` and
+    /// `.dev0` in `1.0.post1.dev0`.  The local version is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// assert_eq!(Version::from("1.0.post1.dev0").unwrap().without_dev_post().unwrap().as_str(), "1.0");
+    /// assert_eq!(Version::from("1.0rc1dev+abc").unwrap().without_dev_post().unwrap().as_str(), "1.0rc1+abc");
+    /// ```
+    pub fn without_dev_post(&self) -> Result<VersionBuf, VersionParseError> {
+        let (epoch, mut public, local) = self.split_str();
+        while let Some(&(start, end)) = component_spans(public).last() {
+            match dev_post_start(&public[start..end]) {
+                // A version made of a single `dev` component is left alone
+                Some(0) if start == 0 => break,
+                Some(0) => public = &public[..start - 1],
+                Some(i) => public = &public[..start + i],
+                None => break,
+            }
+        }
+        assemble(epoch, public, local)
+    }
+
+    /// Get this version with the given `epoch`, replacing any epoch it has.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// assert_eq!(Version::from("1.2").unwrap().with_epoch(1).unwrap().as_str(), "1!1.2");
+    /// assert_eq!(Version::from("1!1.2").unwrap().with_epoch(2).unwrap().as_str(), "2!1.2");
+    /// ```
+    pub fn with_epoch(&self, epoch: u64) -> Result<VersionBuf, VersionParseError> {
+        let (_, public, local) = self.split_str();
+        assemble(Some(&epoch.to_string()), public, local)
+    }
+
+    /// Get this version without its epoch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// assert_eq!(Version::from("1!1.2+abc").unwrap().without_epoch().unwrap().as_str(), "1.2+abc");
+    /// ```
+    pub fn without_epoch(&self) -> Result<VersionBuf, VersionParseError> {
+        let (_, public, local) = self.split_str();
+        assemble(None, public, local)
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use crate::version::{Version, VersionParseError};
+
+    #[rstest_parametrize(version, index, expected,
+    case("1.2.3", 0, "2.0.0"),
+    case("1.2.3", 1, "1.3.0"),
+    case("1.2.3", 2, "1.2.4"),
+    case("1", 2, "1.0.1"),
+    case("1_2", 2, "1_2_1"),
+    case("1.2_3", 1, "1.3_0"),
+    case("1.2.3rc1", 2, "1.2.4"),
+    case("1.2.3rc1", 0, "2.0.0"),
+    case("1.2.3.post1", 2, "1.2.4"),
+    case("1.2.3.dev0", 0, "2.0.0"),
+    case("2!1.2+local", 0, "2!2.0"),
+    case("1.1.1k", 2, "1.1.2"),
+    case("1.a.3", 1, "1.1.0"),
+    )]
+    fn bump(version: &str, index: usize, expected: &str) {
+        let version = Version::from(version).unwrap();
+        let bumped = version.bump(index).unwrap();
+        assert_eq!(bumped.as_str(), expected);
+        assert!(*bumped > version);
+    }
+
+    #[rstest_parametrize(version, count, expected,
+    case("1.2.3.4", 2, "1.2"),
+    case("1.2.3.4", 0, "1"),
+    case("1.2", 5, "1.2"),
+    case("1-2-3", 2, "1-2"),
+    case("1!1.2.3+local.1", 1, "1!1"),
+    case("1.2rc1.3", 2, "1.2rc1"),
+    )]
+    fn truncate(version: &str, count: usize, expected: &str) {
+        assert_eq!(Version::from(version).unwrap().truncate(count).unwrap().as_str(), expected);
+    }
+
+    #[rstest_parametrize(version, expected,
+    case("1.0", "1.0"),
+    case("1.0.dev0", "1.0"),
+    case("1.0.post1", "1.0"),
+    case("1.0.POST1", "1.0"),
+    case("1.0-post1", "1.0"),
+    case("1.0dev", "1.0"),
+    case("1.0.post1.dev0", "1.0"),
+    case("1.0rc1.dev2", "1.0rc1"),
+    case("1.0develop", "1.0develop"),
+    case("1.0.dev0+local", "1.0+local"),
+    case("dev", "dev"),
+    )]
+    fn without_dev_post(version: &str, expected: &str) {
+        assert_eq!(Version::from(version).unwrap().without_dev_post().unwrap().as_str(), expected);
+    }
+
+    #[test]
+    fn epoch_and_local() {
+        let version = Version::from("1!1.2+cuda").unwrap();
+        assert_eq!(version.without_epoch().unwrap().as_str(), "1.2+cuda");
+        assert_eq!(version.with_epoch(3).unwrap().as_str(), "3!1.2+cuda");
+        assert_eq!(version.without_local().unwrap().as_str(), "1!1.2");
+        assert_eq!(version.without_epoch().unwrap().without_local().unwrap().as_str(), "1.2");
+    }
+
+    #[test]
+    fn overflow() {
+        let max = Version::from("1.18446744073709551615").unwrap();
+        assert_eq!(max.bump_minor().unwrap_err(), VersionParseError::NumericOverflow { offset: 2 });
+        assert_eq!(max.bump_major().unwrap().as_str(), "2.0");
+        let max = Version::from("3!18446744073709551615rc1").unwrap();
+        assert_eq!(max.bump_major().unwrap_err(), VersionParseError::NumericOverflow { offset: 2 });
+    }
+}
@@ -5,13 +5,37 @@
 
 pub mod parsers;
 pub mod custom_parts;
+mod edit;
 pub mod comp_op;
+pub mod parse_error;
+pub mod pep440_spec;
+pub mod pep440_version;
+pub mod pinning;
+pub mod scheme;
+pub mod spec_parse_error;
+mod spec_ranges;
+#[allow(clippy::module_inception)]
 pub mod version;
+pub mod version_buf;
 pub mod version_compare;
 pub mod version_part;
+pub mod version_spec;
 
 pub use self::comp_op::CompOp;
+pub use self::parse_error::VersionParseError;
+pub use self::pep440_spec::{Pep440Specifier, Pep440SpecifierSet};
+pub use self::pep440_version::{LocalSegment, Pep440Version, PreRelease};
+pub use self::pinning::Pin;
+pub use self::scheme::{CondaScheme, DebianScheme, DefaultScheme, Pep440Scheme, RpmScheme, SemVerScheme, VersionScheme};
 pub use self::version::Version;
+pub use self::version_buf::VersionBuf;
 pub use self::version_compare::VersionCompare;
 pub use self::version_part::VersionPart;
-pub use self::parsers::conda::conda_parser;
\ No newline at end of file
+pub use self::version_spec::VersionSpec;
+pub use self::spec_parse_error::VersionSpecParseError;
+pub use self::parsers::conda::conda_parser;
+pub use self::parsers::debian::debian_parser;
+pub use self::parsers::default::default_parser;
+pub use self::parsers::pep440::pep440_parser;
+pub use self::parsers::rpm::rpm_parser;
+pub use self::parsers::semver::semver_parser;
\ No newline at end of file
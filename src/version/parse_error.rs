@@ -0,0 +1,133 @@
+//! Version parse error module.
+//!
+//! This module provides the `VersionParseError` enum, which is returned by the version parsers
+//! when a version string can't be parsed.  Every variant carries the byte offset into the version
+//! string at which the problem was found.
+
+use std::error::Error;
+use std::fmt;
+
+/// Error returned when a version string can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionParseError {
+    /// The epoch before the `!` separator is not a number.
+    BadEpoch {
+        offset: usize,
+    },
+
+    /// A separator that may only appear once, such as the epoch separator `!` or the local
+    /// version separator `+`, appears a second time.
+    DuplicateSeparator {
+        separator: char,
+        offset: usize,
+    },
+
+    /// A numeric component is too large to be represented.
+    NumericOverflow {
+        offset: usize,
+    },
+
+    /// The version string contains a character that is not allowed in a version.
+    IllegalCharacter {
+        character: char,
+        offset: usize,
+    },
+
+    /// A version component is empty, such as the one between the dots in `1..2`.
+    EmptyComponent {
+        offset: usize,
+    },
+}
+
+impl VersionParseError {
+    /// Get the byte offset into the version string at which the error was found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// let err = Version::from("1!2!3").unwrap_err();
+    ///
+    /// assert_eq!(err.offset(), 3);
+    /// ```
+    pub fn offset(&self) -> usize {
+        match *self {
+            VersionParseError::BadEpoch { offset } => offset,
+            VersionParseError::DuplicateSeparator { offset, .. } => offset,
+            VersionParseError::NumericOverflow { offset } => offset,
+            VersionParseError::IllegalCharacter { offset, .. } => offset,
+            VersionParseError::EmptyComponent { offset } => offset,
+        }
+    }
+
+    /// Shift the offset of this error by `base` bytes, for errors found in a substring that
+    /// starts `base` bytes into the version string.
+    pub(crate) fn shifted(self, base: usize) -> Self {
+        match self {
+            VersionParseError::BadEpoch { offset } => VersionParseError::BadEpoch { offset: offset + base },
+            VersionParseError::DuplicateSeparator { separator, offset } =>
+                VersionParseError::DuplicateSeparator { separator, offset: offset + base },
+            VersionParseError::NumericOverflow { offset } =>
+                VersionParseError::NumericOverflow { offset: offset + base },
+            VersionParseError::IllegalCharacter { character, offset } =>
+                VersionParseError::IllegalCharacter { character, offset: offset + base },
+            VersionParseError::EmptyComponent { offset } =>
+                VersionParseError::EmptyComponent { offset: offset + base },
+        }
+    }
+}
+
+impl fmt::Display for VersionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VersionParseError::BadEpoch { offset } =>
+                write!(f, "epoch must be an integer (at offset {})", offset),
+            VersionParseError::DuplicateSeparator { separator, offset } =>
+                write!(f, "duplicated separator '{}' (at offset {})", separator, offset),
+            VersionParseError::NumericOverflow { offset } =>
+                write!(f, "numeric component is too large (at offset {})", offset),
+            VersionParseError::IllegalCharacter { character, offset } =>
+                write!(f, "invalid character '{}' (at offset {})", character, offset),
+            VersionParseError::EmptyComponent { offset } =>
+                write!(f, "empty version component (at offset {})", offset),
+        }
+    }
+}
+
+impl Error for VersionParseError {}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use super::VersionParseError;
+
+    #[test]
+    fn offset() {
+        assert_eq!(VersionParseError::BadEpoch { offset: 0 }.offset(), 0);
+        assert_eq!(VersionParseError::DuplicateSeparator { separator: '+', offset: 5 }.offset(), 5);
+        assert_eq!(VersionParseError::NumericOverflow { offset: 2 }.offset(), 2);
+        assert_eq!(VersionParseError::IllegalCharacter { character: '$', offset: 3 }.offset(), 3);
+        assert_eq!(VersionParseError::EmptyComponent { offset: 2 }.offset(), 2);
+    }
+
+    #[test]
+    fn shifted() {
+        assert_eq!(
+            VersionParseError::NumericOverflow { offset: 1 }.shifted(4),
+            VersionParseError::NumericOverflow { offset: 5 },
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            format!("{}", VersionParseError::DuplicateSeparator { separator: '!', offset: 3 }),
+            "duplicated separator '!' (at offset 3)",
+        );
+        assert_eq!(
+            format!("{}", VersionParseError::IllegalCharacter { character: ' ', offset: 5 }),
+            "invalid character ' ' (at offset 5)",
+        );
+    }
+}
@@ -1,77 +1,212 @@
 use crate::version::VersionPart;
+use crate::version::VersionParseError;
 use crate::version::custom_parts::pep440::PEP440String;
 
+/// Whether `c` may appear in a conda version string.
+fn is_version_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "*.+!_-".contains(c)
+}
+
+/// Split `version` at `_`, `-` and `.`, yielding each component along with its byte offset into
+/// the full version string.  `base` is the offset of `version` itself.
+///
+/// A trailing `_` is kept on the last component rather than split off, so that openssl-style
+/// versions like `1.0.1_` sort before `1.0.1a`.
+fn split_components(version: &str, base: usize) -> impl Iterator<Item = (usize, &str)> {
+    let body = version.strip_suffix(['_', '-']).unwrap_or(version);
+    body.split(['_', '-', '.']).scan(0, move |start, part| {
+        let offset = *start;
+        *start += part.len() + 1;
+        let end = if *start > body.len() { version.len() } else { offset + part.len() };
+        Some((base + offset, &version[offset..end]))
+    })
+}
+
+/// Parse the components of a public or local version.
+fn parse_components<'a>(
+    version: &'a str,
+    base: usize,
+) -> Result<Vec<VersionPart<'a>>, VersionParseError> {
+    let mut parts = Vec::with_capacity(version.len() / 2 + 1);
+
+    for (offset, part) in split_components(version, base) {
+        if part.is_empty() {
+            return Err(VersionParseError::EmptyComponent { offset });
+        }
+
+        if part.bytes().all(|b| b.is_ascii_digit()) {
+            let number = part.parse().map_err(|_| VersionParseError::NumericOverflow { offset })?;
+            parts.push(VersionPart::Integer(number));
+        } else {
+            let text = PEP440String::from(part).map_err(|e| e.shifted(offset))?;
+            parts.push(VersionPart::PEP440String(text));
+        }
+    }
+
+    Ok(parts)
+}
+
 /// Split the given version string, in it's version parts.
+///
+/// This follows conda's `VersionOrder`: the version is made of an optional integer epoch followed
+/// by `!`, the public version, and an optional local version following `+`.  The public and local
+/// versions are split into components at `.` and `_`, or at `-` when the version contains no `_`.
+/// The local version is returned as a single `VersionPart::Local` at the end of the parts.
+/// Like conda, a blank version is rejected with an `EmptyComponent` error.
 pub fn conda_parser(
     version: &str,
-) -> Option<Vec<VersionPart>> {
-    // version len may be a bit wasteful of memory.  Let's start there and tune as necessary.
-    let mut parts = Vec::with_capacity(version.len()/2);
+) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+    // Surrounding whitespace is ignored
+    let base = version.len() - version.trim_start().len();
+    let version = version.trim();
+    if version.is_empty() {
+        return Err(VersionParseError::EmptyComponent { offset: base });
+    }
+
+    // Reject anything outside of the characters conda allows in a version
+    if let Some((offset, character)) = version.char_indices().find(|&(_, c)| !is_version_char(c)) {
+        return Err(VersionParseError::IllegalCharacter { character, offset: base + offset });
+    }
+
+    // Dashes may only stand in for underscores if there are no underscores
+    if version.contains('_') {
+        if let Some(offset) = version.find('-') {
+            return Err(VersionParseError::IllegalCharacter { character: '-', offset: base + offset });
+        }
+    }
 
     // Split at epoch
-    let epoch_split: Vec<&str> = version.split("!").collect();
-    let post_epoch_split: &str = match epoch_split.len() {
-        2 => {
-            parts.push(VersionPart::Epoch(epoch_split[0].parse().unwrap()));
-            epoch_split[1]
-        },
-        1 => {
-            epoch_split[0]
+    let mut parts = Vec::new();
+    let mut epoch_split = version.match_indices('!').map(|(i, _)| i);
+    let post_epoch_start: usize = match (epoch_split.next(), epoch_split.next()) {
+        (None, _) => 0,
+        (Some(end), None) => {
+            let epoch = &version[..end];
+            if epoch.is_empty() || !epoch.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(VersionParseError::BadEpoch { offset: base });
+            }
+            let epoch = epoch.parse().map_err(|_| VersionParseError::NumericOverflow { offset: base })?;
+            parts.push(VersionPart::Epoch(epoch));
+            end + 1
         },
-        _ => panic!("Duplicated epoch separator (!)")
+        (Some(_), Some(offset)) =>
+            return Err(VersionParseError::DuplicateSeparator { separator: '!', offset: base + offset }),
     };
+    let post_epoch = &version[post_epoch_start..];
 
     // Get any local version string
-    let local_version_split: Vec<&str> = post_epoch_split.split("+").collect();
-    let local: &str = match local_version_split.len() {
-        1 => "",
-        2 => local_version_split[1],
-        _ => panic!("duplicated local version separator (+)")
+    let mut local_split = post_epoch.match_indices('+').map(|(i, _)| i);
+    let (public, local): (&str, Option<&str>) = match (local_split.next(), local_split.next()) {
+        (None, _) => (post_epoch, None),
+        (Some(end), None) => (&post_epoch[..end], Some(&post_epoch[end + 1..])),
+        (Some(_), Some(offset)) => return Err(VersionParseError::DuplicateSeparator {
+            separator: '+',
+            offset: base + post_epoch_start + offset,
+        }),
     };
 
-    // Split at periods
-    let mut version_split: Vec<&str> = local_version_split[0].split(|c| c == '_' || c == '.')
-        .collect();
-    let local_split: Vec<&str> = local.split(|c| c == '_' || c == '.').collect();
-    version_split.extend(local_split);
-
-    // Loop over the parts, and parse them
-    for part in version_split {
-        println!("{}", part);
-        // Skip empty parts
-        if part.is_empty() {
-            continue;
-        }
-
-        // Try to parse the value as an number
-        match part.parse::<i32>() {
-            Ok(number) => {
-                // Push the number part to the vector, and set the has number flag
-                parts.push(VersionPart::Integer(number));
-            }
-            Err(_) => {
-                // Push the text part to the vector
-                parts.push(VersionPart::PEP440String(PEP440String::from(part)));
-            }
-        }
-    }
-
-    if parts.is_empty() && version.is_empty() {
-        parts.push(VersionPart::Empty);
+    parts.extend(parse_components(public, base + post_epoch_start)?);
+    if let Some(local) = local {
+        let local_start = base + post_epoch_start + public.len() + 1;
+        parts.push(VersionPart::Local(parse_components(local, local_start)?));
     }
 
     // Return the list of parts
-    Some(parts)
+    Ok(parts)
 }
 
 #[cfg(test)]
 mod tests {
     use super::conda_parser;
 
+    use crate::version::VersionParseError;
+
     # [test]
     fn test_less_specific_less_than_more_specific() {
         // 0.4 < 0.4.0
         let parts = conda_parser("0.4").unwrap();
         assert_eq!(parts.len(), 2);
     }
+
+    #[test]
+    fn bad_epoch() {
+        assert_eq!(conda_parser("x!1.0").unwrap_err(), VersionParseError::BadEpoch { offset: 0 });
+        assert_eq!(conda_parser("!1.0").unwrap_err(), VersionParseError::BadEpoch { offset: 0 });
+        assert_eq!(
+            conda_parser("99999999999999999999!1.0").unwrap_err(),
+            VersionParseError::NumericOverflow { offset: 0 },
+        );
+    }
+
+    #[test]
+    fn duplicate_separator() {
+        assert_eq!(
+            conda_parser("1!2!3").unwrap_err(),
+            VersionParseError::DuplicateSeparator { separator: '!', offset: 3 },
+        );
+        assert_eq!(
+            conda_parser("1!1.0+a+b").unwrap_err(),
+            VersionParseError::DuplicateSeparator { separator: '+', offset: 7 },
+        );
+    }
+
+    #[test]
+    fn numeric_overflow() {
+        let big = "99999999999999999999";
+        assert_eq!(conda_parser(&format!("1.{}a", big)).unwrap_err(), VersionParseError::NumericOverflow { offset: 2 });
+        assert_eq!(conda_parser(&format!("1.a{}", big)).unwrap_err(), VersionParseError::NumericOverflow { offset: 3 });
+        assert_eq!(conda_parser(&format!("1.{}", big)).unwrap_err(), VersionParseError::NumericOverflow { offset: 2 });
+        assert_eq!(conda_parser(&format!("1.0+{}", big)).unwrap_err(), VersionParseError::NumericOverflow { offset: 4 });
+        // conda's integers are unbounded, so anything that fits in 64 bits must parse
+        assert!(conda_parser("1.99999a").is_ok());
+        assert!(conda_parser("20190101123456.1").is_ok());
+    }
+
+    #[test]
+    fn illegal_character() {
+        assert_eq!(
+            conda_parser("1.0 beta").unwrap_err(),
+            VersionParseError::IllegalCharacter { character: ' ', offset: 3 },
+        );
+        assert_eq!(
+            conda_parser("3.5&1").unwrap_err(),
+            VersionParseError::IllegalCharacter { character: '&', offset: 3 },
+        );
+        assert_eq!(
+            conda_parser("1.0_1-2").unwrap_err(),
+            VersionParseError::IllegalCharacter { character: '-', offset: 5 },
+        );
+    }
+
+    #[test]
+    fn empty_component() {
+        assert_eq!(conda_parser(".").unwrap_err(), VersionParseError::EmptyComponent { offset: 0 });
+        assert_eq!(conda_parser("5.5..mw").unwrap_err(), VersionParseError::EmptyComponent { offset: 4 });
+        assert_eq!(conda_parser("1.0+").unwrap_err(), VersionParseError::EmptyComponent { offset: 4 });
+        assert_eq!(conda_parser("1!").unwrap_err(), VersionParseError::EmptyComponent { offset: 2 });
+    }
+
+    #[test]
+    fn whitespace_is_trimmed() {
+        assert_eq!(conda_parser(" 1.0 ").unwrap(), conda_parser("1.0").unwrap());
+        assert_eq!(
+            conda_parser(" 1.0+").unwrap_err(),
+            VersionParseError::EmptyComponent { offset: 5 },
+        );
+    }
+
+    #[test]
+    fn trailing_underscore() {
+        // 1.0.1_ keeps its underscore on the last component: [1], [0], [1, '_']
+        let parts = conda_parser("1.0.1_").unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts, conda_parser("1.0.1-").unwrap());
+    }
+
+    #[test]
+    fn local_version() {
+        let parts = conda_parser("1!1.0+abc.7").unwrap();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(format!("{:?}", parts[3]), "Local([PEP440String(0abc), Integer(7)])");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,191 @@
+use std::borrow::Cow;
+
+use crate::version::VersionPart;
+use crate::version::VersionParseError;
+use crate::version::custom_parts::pep440::{Atom, PEP440String};
+
+/// Encode a dpkg upstream version or revision as a single component, whose atoms compare the
+/// way dpkg's `verrevcmp` compares the strings.
+///
+/// The string is split into alternating runs of non-digits and digits.  Every character of a
+/// non-digit run becomes a number atom weighing letters below other characters, followed by a
+/// `0` marking the end of the run, and then by the number of the digit run.  The fill value `0`
+/// thus stands for the end of the string, and `~`, which sorts before even the end of the string,
+/// becomes a text atom.
+fn encode(text: &str) -> VersionPart<'static> {
+    let bytes = text.as_bytes();
+    let mut atoms = Vec::with_capacity(bytes.len() + 2);
+    let mut pos = 0;
+    while pos < bytes.len() {
+        while pos < bytes.len() && !bytes[pos].is_ascii_digit() {
+            atoms.push(match bytes[pos] {
+                b'~' => Atom::Text(Cow::Borrowed("~")),
+                b if b.is_ascii_alphabetic() => Atom::Number(u64::from(b)),
+                b => Atom::Number(u64::from(b) + 256),
+            });
+            pos += 1;
+        }
+        atoms.push(Atom::Number(0));
+
+        let start = pos;
+        while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+            pos += 1;
+        }
+        // Leading zeros don't matter, and the digits were checked by the caller
+        let digits = text[start..pos].trim_start_matches('0');
+        atoms.push(Atom::Number(digits.parse().unwrap_or(0)));
+    }
+    VersionPart::PEP440String(PEP440String::from_atoms(atoms))
+}
+
+/// Check that `text` only holds characters allowed by `allowed`, and that its numbers fit.
+fn check(text: &str, base: usize, allowed: fn(char) -> bool) -> Result<(), VersionParseError> {
+    if let Some((offset, character)) = text.char_indices().find(|&(_, c)| !allowed(c)) {
+        return Err(VersionParseError::IllegalCharacter { character, offset: base + offset });
+    }
+    let mut start = 0;
+    for run in text.split(|c: char| !c.is_ascii_digit()) {
+        let digits = run.trim_start_matches('0');
+        if !digits.is_empty() && digits.parse::<u64>().is_err() {
+            return Err(VersionParseError::NumericOverflow { offset: base + start });
+        }
+        start += run.len() + 1;
+    }
+    Ok(())
+}
+
+/// Split the given Debian version string, in it's version parts.
+///
+/// Debian versions have the form `[epoch:]upstream_version[-debian_revision]`, and are compared
+/// the way `dpkg --compare-versions` does: the epoch numerically, then the upstream version, then
+/// the revision.  Within those, runs of digits compare numerically, and other characters compare
+/// with `~` sorting before anything, even the end of the string, and letters sorting before
+/// other characters, so that `1.0~rc1 < 1.0 < 1.0a < 1.0+b1`.
+///
+/// The revision is returned as a `VersionPart::Local`.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{DebianScheme, Version};
+///
+/// let rc = Version::<DebianScheme>::new("1.0~rc1-1").unwrap();
+/// let release = Version::<DebianScheme>::new("1.0-1").unwrap();
+///
+/// assert!(rc < release);
+/// assert!(release < Version::<DebianScheme>::new("1:0.9").unwrap());
+/// ```
+pub fn debian_parser(
+    version: &str,
+) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+    // Surrounding whitespace is ignored
+    let base = version.len() - version.trim_start().len();
+    let version = version.trim();
+
+    let mut parts = Vec::with_capacity(3);
+    let (upstream_start, rest) = match version.split_once(':') {
+        Some((epoch, rest)) => {
+            if epoch.is_empty() || !epoch.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(VersionParseError::BadEpoch { offset: base });
+            }
+            let epoch = epoch.parse().map_err(|_| VersionParseError::NumericOverflow { offset: base })?;
+            parts.push(VersionPart::Epoch(epoch));
+            (base + version.len() - rest.len(), rest)
+        },
+        None => (base, version),
+    };
+
+    // The revision follows the last hyphen
+    let (upstream, revision) = match rest.rfind('-') {
+        Some(end) => (&rest[..end], Some(&rest[end + 1..])),
+        None => (rest, None),
+    };
+    if upstream.is_empty() {
+        return Err(VersionParseError::EmptyComponent { offset: upstream_start });
+    }
+    if !upstream.starts_with(|c: char| c.is_ascii_digit()) {
+        let character = upstream.chars().next().unwrap_or_default();
+        return Err(VersionParseError::IllegalCharacter { character, offset: upstream_start });
+    }
+    check(upstream, upstream_start, |c| c.is_ascii_alphanumeric() || ".+~-".contains(c))?;
+    parts.push(encode(upstream));
+
+    if let Some(revision) = revision {
+        let revision_start = upstream_start + upstream.len() + 1;
+        if revision.is_empty() {
+            return Err(VersionParseError::EmptyComponent { offset: revision_start });
+        }
+        check(revision, revision_start, |c| c.is_ascii_alphanumeric() || ".+~".contains(c))?;
+        parts.push(VersionPart::Local(vec![encode(revision)]));
+    }
+
+    Ok(parts)
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::debian_parser;
+    use crate::version::{DebianScheme, Version, VersionParseError};
+
+    /// Comparisons from dpkg's `t-version.c` and the Debian Policy Manual.
+    #[rstest_parametrize(a, b, expected,
+    case("0", "0", 0),
+    case("0", "00", 0),
+    case("1", "2", -1),
+    case("0:1.0-1", "0:1.0-1", 0),
+    case("0:1.0-1", "1.0-1", 0),
+    case("1:1.0-0", "1:1.0", 0),
+    case("1.0", "1.0-0", 0),
+    case("0:1.0-1", "0:2.0-1", -1),
+    case("0:1.0-1", "1:1.0-1", -1),
+    case("1:0.4", "10.3", 1),
+    case("1.2.3-4", "1.2.3-5", -1),
+    case("0:1.18.36", "1.18.35", 1),
+    case("1.18.36", "1.18.35", 1),
+    case("0.5.1-22", "0.5.1-22", 0),
+    case("1.0~rc1", "1.0", -1),
+    case("1.0~rc1", "1.0~rc2", -1),
+    case("1.0~~", "1.0~~a", -1),
+    case("1.0~~a", "1.0~", -1),
+    case("1.0~", "1.0", -1),
+    case("1.0", "1.0a", -1),
+    case("1.0a", "1.0+", -1),
+    case("1.0", "1.0+b1", -1),
+    case("1.0", "1.0.0", -1),
+    case("1.0a", "1.0.1", -1),
+    case("2.7.4+reformed", "2.7.4", 1),
+    case("1.0-1", "1.0-1~bpo1", 1),
+    case("1.2~b1", "1.2~a3", 1),
+    case("1.0-z", "1.0a-1", -1),
+    case("1.0-1-1", "1.0-1", 1),
+    case("1.0-1.1", "1.0-1", 1),
+    case("1.0-1ubuntu1", "1.0-1", 1),
+    case("1.0-1ubuntu1", "1.0-2", -1),
+    )]
+    fn compare(a: &str, b: &str, expected: i8) {
+        let a = Version::<DebianScheme>::new(a).unwrap();
+        let b = Version::<DebianScheme>::new(b).unwrap();
+        assert_eq!(a.cmp(&b), expected.cmp(&0));
+        assert_eq!(b.cmp(&a), expected.cmp(&0).reverse());
+        assert_eq!(a == b, expected.cmp(&0) == Ordering::Equal);
+    }
+
+    #[rstest_parametrize(version, error,
+    case("", VersionParseError::EmptyComponent { offset: 0 }),
+    case("a:1.0", VersionParseError::BadEpoch { offset: 0 }),
+    case(":1.0", VersionParseError::BadEpoch { offset: 0 }),
+    case("1:", VersionParseError::EmptyComponent { offset: 2 }),
+    case("1.0-", VersionParseError::EmptyComponent { offset: 4 }),
+    case("a1.0", VersionParseError::IllegalCharacter { character: 'a', offset: 0 }),
+    case("1.0_1", VersionParseError::IllegalCharacter { character: '_', offset: 3 }),
+    case("1:1.0:1", VersionParseError::IllegalCharacter { character: ':', offset: 5 }),
+    case("1.0-1+b1~x_y", VersionParseError::IllegalCharacter { character: '_', offset: 10 }),
+    case("1.99999999999999999999", VersionParseError::NumericOverflow { offset: 2 }),
+    )]
+    fn invalid(version: &str, error: VersionParseError) {
+        assert_eq!(debian_parser(version).unwrap_err(), error);
+    }
+}
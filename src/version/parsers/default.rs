@@ -1,10 +1,11 @@
 use crate::version::version_part::VersionPart;
+use crate::version::VersionParseError;
 
 /// Split the given version string, in it's version parts.
 /// TODO: Move this method to some sort of helper class, maybe as part of `VersionPart`.
 pub fn default_parser(
     version: &str,
-) -> Option<Vec<VersionPart>> {
+) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
     // Split the version string, and create a vector to put the parts in
     // TODO: split at specific separators instead
     let split = version.split(|c| !char::is_alphanumeric(c));
@@ -18,14 +19,14 @@ pub fn default_parser(
         }
 
         // Try to parse the value as an number
-        match part.parse::<i32>() {
+        match part.parse::<u64>() {
             Ok(number) => {
                 // Push the number part to the vector, and set the has number flag
                 parts.push(VersionPart::Integer(number));
             }
             Err(_) => {
                 // Push the text part to the vector
-                parts.push(VersionPart::LexicographicString(part));
+                parts.push(VersionPart::LexicographicString(part.into()));
             }
         }
     }
@@ -35,5 +36,5 @@ pub fn default_parser(
     }
 
     // Return the list of parts
-    Some(parts)
+    Ok(parts)
 }
\ No newline at end of file
@@ -1,2 +1,6 @@
 pub mod conda;
-pub mod default;
\ No newline at end of file
+pub mod debian;
+pub mod default;
+pub mod pep440;
+pub mod rpm;
+pub mod semver;
@@ -0,0 +1,34 @@
+use crate::version::version_part::VersionPart;
+use crate::version::VersionParseError;
+use crate::version::pep440_version::Pep440Version;
+
+/// Split the given PEP 440 version string, in it's version parts.
+///
+/// The version is parsed and normalized as a `Pep440Version`.  The parts are the epoch when it
+/// isn't `0`, the release numbers, then the label and number of each pre-release, post-release
+/// and development release segment, and last the local version.  `Pep440Scheme` orders the
+/// parts the way PEP 440 orders versions.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{Pep440Scheme, Version, VersionPart, pep440_parser};
+///
+/// assert_eq!(pep440_parser("1.0-RC1").unwrap(), vec![
+///     VersionPart::Integer(1),
+///     VersionPart::Integer(0),
+///     VersionPart::LexicographicString("rc".into()),
+///     VersionPart::Integer(1),
+/// ]);
+///
+/// let rc = Version::<Pep440Scheme>::new("1.0-RC1").unwrap();
+/// let dev = Version::<Pep440Scheme>::new("1.0.dev1").unwrap();
+///
+/// assert!(dev < rc);
+/// assert!(rc < Version::new("1.0").unwrap());
+/// ```
+pub fn pep440_parser(
+    version: &str,
+) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+    Ok(version.parse::<Pep440Version>()?.to_parts())
+}
@@ -0,0 +1,223 @@
+use std::borrow::Cow;
+
+use crate::version::VersionPart;
+use crate::version::VersionParseError;
+use crate::version::custom_parts::pep440::{Atom, PEP440String};
+
+/// Whether `c` may appear in an RPM version or release.
+fn is_rpm_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "._+~^".contains(c)
+}
+
+/// Encode an RPM version or release as a single component, whose atoms compare the way
+/// `rpmvercmp` compares the strings.
+///
+/// The string is split into runs of digits and runs of letters, while other characters only
+/// separate runs, except for `~` and `^`.  Comparing two strings run by run, `rpmvercmp` orders
+/// `~` before the end of the string, the end before `^`, `^` before letters, and letters before
+/// numbers.  With the fill value `0` standing for the end of the string, `~` becomes a text atom,
+/// `^` the atom `1`, letters the atom `2` followed by the run and numbers the atom `3` followed
+/// by the number.
+fn encode(text: &str, base: usize) -> Result<VersionPart<'static>, VersionParseError> {
+    let bytes = text.as_bytes();
+    let mut atoms = Vec::with_capacity(bytes.len());
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        match bytes[pos] {
+            b'~' => {
+                atoms.push(Atom::Text(Cow::Borrowed("~")));
+                pos += 1;
+            },
+            b'^' => {
+                atoms.push(Atom::Number(1));
+                pos += 1;
+            },
+            b if b.is_ascii_digit() => {
+                while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                let digits = text[start..pos].trim_start_matches('0');
+                let number = if digits.is_empty() {
+                    0
+                } else {
+                    digits.parse().map_err(|_| VersionParseError::NumericOverflow { offset: base + start })?
+                };
+                atoms.push(Atom::Number(3));
+                atoms.push(Atom::Number(number));
+            },
+            b if b.is_ascii_alphabetic() => {
+                while pos < bytes.len() && bytes[pos].is_ascii_alphabetic() {
+                    pos += 1;
+                }
+                // Prefixed, so that a `dev` run doesn't get the special ordering of conda
+                atoms.push(Atom::Number(2));
+                atoms.push(Atom::Text(Cow::Owned(format!("r{}", &text[start..pos]))));
+            },
+            _ => pos += 1,
+        }
+    }
+    Ok(VersionPart::PEP440String(PEP440String::from_atoms(atoms)))
+}
+
+/// Split the given RPM version string, in it's version parts.
+///
+/// RPM versions have the form `[epoch:]version[-release]`, and are compared the way
+/// `rpmvercmp` does: the epoch numerically, then the version, then the release.  Within those,
+/// runs of digits compare numerically and sort after runs of letters, which compare
+/// case-sensitively, while other separators are ignored.  A `~` sorts before anything, even the
+/// end of the string, and a `^` sorts after the end of the string but before anything else, so
+/// that `1.0~rc1 < 1.0 < 1.0^git1 < 1.0.1`.
+///
+/// The release is returned as a `VersionPart::Local`.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{RpmScheme, Version};
+///
+/// let snapshot = Version::<RpmScheme>::new("1.0^git1-1.fc33").unwrap();
+/// let release = Version::<RpmScheme>::new("1.0-1.fc33").unwrap();
+///
+/// assert!(release < snapshot);
+/// assert!(snapshot < Version::<RpmScheme>::new("1.0.1-1.fc33").unwrap());
+/// ```
+pub fn rpm_parser(
+    version: &str,
+) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+    // Surrounding whitespace is ignored
+    let base = version.len() - version.trim_start().len();
+    let version = version.trim();
+
+    let mut parts = Vec::with_capacity(3);
+    let (version_start, rest) = match version.split_once(':') {
+        Some((epoch, rest)) => {
+            if epoch.is_empty() || !epoch.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(VersionParseError::BadEpoch { offset: base });
+            }
+            let epoch = epoch.parse().map_err(|_| VersionParseError::NumericOverflow { offset: base })?;
+            parts.push(VersionPart::Epoch(epoch));
+            (base + version.len() - rest.len(), rest)
+        },
+        None => (base, version),
+    };
+
+    let mut release_split = rest.match_indices('-').map(|(i, _)| i);
+    let (public, release) = match (release_split.next(), release_split.next()) {
+        (None, _) => (rest, None),
+        (Some(end), None) => (&rest[..end], Some(&rest[end + 1..])),
+        (Some(_), Some(offset)) =>
+            return Err(VersionParseError::DuplicateSeparator { separator: '-', offset: version_start + offset }),
+    };
+
+    if let Some((offset, character)) = rest.char_indices().find(|&(_, c)| !is_rpm_char(c) && c != '-') {
+        return Err(VersionParseError::IllegalCharacter { character, offset: version_start + offset });
+    }
+    if public.is_empty() {
+        return Err(VersionParseError::EmptyComponent { offset: version_start });
+    }
+    parts.push(encode(public, version_start)?);
+
+    if let Some(release) = release {
+        let release_start = version_start + public.len() + 1;
+        if release.is_empty() {
+            return Err(VersionParseError::EmptyComponent { offset: release_start });
+        }
+        parts.push(VersionPart::Local(vec![encode(release, release_start)?]));
+    }
+
+    Ok(parts)
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use super::rpm_parser;
+    use crate::version::{RpmScheme, Version, VersionParseError};
+
+    /// Comparisons from the `rpmvercmp` test suite of rpm.
+    #[rstest_parametrize(a, b, expected,
+    case("1.0", "1.0", 0),
+    case("1.0", "2.0", -1),
+    case("2.0.1", "2.0.1", 0),
+    case("2.0", "2.0.1", -1),
+    case("2.0.1a", "2.0.1a", 0),
+    case("2.0.1a", "2.0.1", 1),
+    case("5.5p1", "5.5p1", 0),
+    case("5.5p1", "5.5p2", -1),
+    case("5.5p10", "5.5p10", 0),
+    case("5.5p1", "5.5p10", -1),
+    case("10xyz", "10.1xyz", -1),
+    case("xyz10", "xyz10", 0),
+    case("xyz10", "xyz10.1", -1),
+    case("xyz.4", "xyz.4", 0),
+    case("xyz.4", "8", -1),
+    case("xyz.4", "2", -1),
+    case("5.5p2", "5.6p1", -1),
+    case("5.6p1", "6.5p1", -1),
+    case("6.0.rc1", "6.0", 1),
+    case("10b2", "10a1", 1),
+    case("10a2", "10b2", -1),
+    case("1.0aa", "1.0aa", 0),
+    case("1.0a", "1.0aa", -1),
+    case("10.0001", "10.0001", 0),
+    case("10.0001", "10.1", 0),
+    case("10.0001", "10.0039", -1),
+    case("4.999.9", "5.0", -1),
+    case("20101121", "20101121", 0),
+    case("20101121", "20101122", -1),
+    case("2_0", "2_0", 0),
+    case("2.0", "2_0", 0),
+    case("a", "a", 0),
+    case("a+", "a+", 0),
+    case("a+", "a_", 0),
+    case("+a", "+a", 0),
+    case("+a", "_a", 0),
+    case("+_", "+_", 0),
+    case("_+", "+_", 0),
+    case("+", "_", 0),
+    case("1.0~rc1", "1.0~rc1", 0),
+    case("1.0~rc1", "1.0", -1),
+    case("1.0~rc1", "1.0~rc2", -1),
+    case("1.0~rc1~git123", "1.0~rc1~git123", 0),
+    case("1.0~rc1~git123", "1.0~rc1", -1),
+    case("1.0^", "1.0^", 0),
+    case("1.0^", "1.0", 1),
+    case("1.0^git1", "1.0^git1", 0),
+    case("1.0^git1", "1.0", 1),
+    case("1.0^git1", "1.0^git2", -1),
+    case("1.0^git1", "1.01", -1),
+    case("1.0^20160101", "1.0^20160101", 0),
+    case("1.0^20160101", "1.0.1", -1),
+    case("1.0^20160101^git1", "1.0^20160101^git1", 0),
+    case("1.0^20160102", "1.0^20160101^git1", 1),
+    case("1.0~rc1^git1", "1.0~rc1^git1", 0),
+    case("1.0~rc1^git1", "1.0~rc1", 1),
+    case("1.0^git1~pre", "1.0^git1~pre", 0),
+    case("1.0^git1", "1.0^git1~pre", 1),
+    case("dev", "abc", 1),
+    case("1:1.0-1", "2.0-1", 1),
+    case("1.0-1", "1.0-2", -1),
+    case("1.0-1.el8", "1.0-1", 1),
+    case("1.0-1~rc", "1.0-1", -1),
+    )]
+    fn compare(a: &str, b: &str, expected: i8) {
+        let a = Version::<RpmScheme>::new(a).unwrap();
+        let b = Version::<RpmScheme>::new(b).unwrap();
+        assert_eq!(a.cmp(&b), expected.cmp(&0));
+        assert_eq!(b.cmp(&a), expected.cmp(&0).reverse());
+    }
+
+    #[rstest_parametrize(version, error,
+    case("", VersionParseError::EmptyComponent { offset: 0 }),
+    case("x:1.0", VersionParseError::BadEpoch { offset: 0 }),
+    case("1.0-", VersionParseError::EmptyComponent { offset: 4 }),
+    case("-1", VersionParseError::EmptyComponent { offset: 0 }),
+    case("1.0-1-2", VersionParseError::DuplicateSeparator { separator: '-', offset: 5 }),
+    case("1.0/1", VersionParseError::IllegalCharacter { character: '/', offset: 3 }),
+    case("1.99999999999999999999", VersionParseError::NumericOverflow { offset: 2 }),
+    )]
+    fn invalid(version: &str, error: VersionParseError) {
+        assert_eq!(rpm_parser(version).unwrap_err(), error);
+    }
+}
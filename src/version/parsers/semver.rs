@@ -0,0 +1,234 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use crate::version::VersionPart;
+use crate::version::VersionParseError;
+
+/// Check the dot separated identifiers of a pre-release or build metadata, which must be
+/// non-empty and alphanumeric or `-`.  Numeric pre-release identifiers may not have leading
+/// zeros.
+fn check_identifiers(text: &str, base: usize, numeric_leading_zero: bool) -> Result<(), VersionParseError> {
+    let mut start = 0;
+    for identifier in text.split('.') {
+        let offset = base + start;
+        if identifier.is_empty() {
+            return Err(VersionParseError::EmptyComponent { offset });
+        }
+        if let Some((i, character)) = identifier.char_indices().find(|&(_, c)| !c.is_ascii_alphanumeric() && c != '-') {
+            return Err(VersionParseError::IllegalCharacter { character, offset: offset + i });
+        }
+        if !numeric_leading_zero && identifier.len() > 1 && identifier.starts_with('0')
+            && identifier.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(VersionParseError::IllegalCharacter { character: '0', offset });
+        }
+        start += identifier.len() + 1;
+    }
+    Ok(())
+}
+
+/// Compare two pre-releases identifier by identifier: numerically when both identifiers are
+/// numeric, with numeric identifiers sorting before alphanumeric ones, and with a shorter list
+/// of identifiers sorting first when all of its identifiers are equal.
+fn compare_pre_releases(a: &str, b: &str) -> Ordering {
+    let numeric = |identifier: &str| identifier.bytes().all(|b| b.is_ascii_digit());
+    let (mut a, mut b) = (a.split('.'), b.split('.'));
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            // Numeric identifiers have no leading zeros, so the longer one is the larger
+            (Some(a), Some(b)) => match (numeric(a), numeric(b)) {
+                (true, true) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// View the parts built by `semver_parser` as the major, minor and patch numbers, and the
+/// pre-release.
+fn precedence_key<'p>(parts: &'p [VersionPart]) -> (Vec<u64>, Option<&'p str>) {
+    let numbers = parts.iter().filter_map(|part| match part {
+        VersionPart::Integer(number) => Some(*number),
+        _ => None,
+    }).collect();
+    let pre_release = parts.iter().find_map(|part| match part {
+        VersionPart::LexicographicString(pre_release) => Some(&**pre_release),
+        _ => None,
+    });
+    (numbers, pre_release)
+}
+
+/// Order the parts of two SemVer versions by the precedence rules of the SemVer specification.
+pub(crate) fn compare_semver(a: &[VersionPart], b: &[VersionPart]) -> Ordering {
+    let (numbers, pre_release) = precedence_key(a);
+    let (other_numbers, other_pre_release) = precedence_key(b);
+    numbers.cmp(&other_numbers).then_with(|| match (pre_release, other_pre_release) {
+        (None, None) => Ordering::Equal,
+        // A pre-release sorts before its release
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(pre_release), Some(other_pre_release)) => compare_pre_releases(pre_release, other_pre_release),
+    })
+}
+
+/// Hash the parts of a SemVer version consistently with `compare_semver`.
+pub(crate) fn hash_semver<H: Hasher>(parts: &[VersionPart], state: &mut H) {
+    precedence_key(parts).hash(state);
+}
+
+/// Split the given SemVer 2.0 version string, in it's version parts.
+///
+/// The version must have the form `major.minor.patch[-pre.release][+build.metadata]`, without
+/// leading zeros in numbers.  The parts are the three numbers, followed by the pre-release as a
+/// single string.  Build metadata is validated, but left out of the parts.
+///
+/// `SemVerScheme` orders the parts by the precedence rules of the SemVer specification: a
+/// pre-release sorts before its release, pre-release identifiers are compared one by one,
+/// numerically when both are numeric, and with numeric identifiers sorting before alphanumeric
+/// ones.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{SemVerScheme, Version, VersionPart, semver_parser};
+///
+/// assert_eq!(semver_parser("1.0.0-rc.1+sha.abc").unwrap(), vec![
+///     VersionPart::Integer(1),
+///     VersionPart::Integer(0),
+///     VersionPart::Integer(0),
+///     VersionPart::LexicographicString("rc.1".into()),
+/// ]);
+///
+/// let alpha = Version::<SemVerScheme>::new("1.0.0-alpha.1").unwrap();
+/// let beta = Version::<SemVerScheme>::new("1.0.0-alpha.beta").unwrap();
+///
+/// assert!(alpha < beta);
+/// assert_eq!(
+///     Version::<SemVerScheme>::new("1.0.0+sha.abc").unwrap(),
+///     Version::<SemVerScheme>::new("1.0.0").unwrap(),
+/// );
+/// ```
+pub fn semver_parser(
+    version: &str,
+) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+    // Surrounding whitespace is ignored
+    let base = version.len() - version.trim_start().len();
+    let version = version.trim();
+
+    let (rest, build) = match version.split_once('+') {
+        Some((rest, build)) => (rest, Some(build)),
+        None => (version, None),
+    };
+    let (core, pre_release) = match rest.split_once('-') {
+        Some((core, pre_release)) => (core, Some(pre_release)),
+        None => (rest, None),
+    };
+
+    // Exactly three numbers, without leading zeros
+    let mut parts = Vec::with_capacity(4);
+    let mut start = 0;
+    for number in core.splitn(3, '.') {
+        let offset = base + start;
+        if number.is_empty() {
+            return Err(VersionParseError::EmptyComponent { offset });
+        }
+        if let Some((i, character)) = number.char_indices().find(|&(_, c)| !c.is_ascii_digit()) {
+            return Err(VersionParseError::IllegalCharacter { character, offset: offset + i });
+        }
+        if number.len() > 1 && number.starts_with('0') {
+            return Err(VersionParseError::IllegalCharacter { character: '0', offset });
+        }
+        parts.push(VersionPart::Integer(number.parse().map_err(|_| VersionParseError::NumericOverflow { offset })?));
+        start += number.len() + 1;
+    }
+    if parts.len() < 3 {
+        return Err(VersionParseError::EmptyComponent { offset: base + core.len() });
+    }
+
+    if let Some(pre_release) = pre_release {
+        let pre_start = base + core.len() + 1;
+        check_identifiers(pre_release, pre_start, false)?;
+        parts.push(VersionPart::LexicographicString(pre_release.into()));
+    }
+    if let Some(build) = build {
+        check_identifiers(build, base + rest.len() + 1, true)?;
+    }
+
+    Ok(parts)
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use super::semver_parser;
+    use crate::version::{SemVerScheme, Version, VersionParseError};
+
+    #[test]
+    fn precedence() {
+        // The examples of the SemVer specification
+        let ordered = [
+            "1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta", "1.0.0-beta.2",
+            "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0", "2.0.0", "2.1.0", "2.1.1", "2.1.11",
+        ];
+        let versions: Vec<Version<SemVerScheme>> = ordered.iter().map(|v| Version::new(v).unwrap()).collect();
+        for (i, a) in versions.iter().enumerate() {
+            for (j, b) in versions.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{} vs {}", a, b);
+            }
+        }
+    }
+
+    #[rstest_parametrize(a, b,
+    case("1.0.0-abc", "1.0.0-dev"),
+    case("1.0.0-Z", "1.0.0-a"),
+    case("1.0.0-1", "1.0.0-0a"),
+    case("1.0.0-a.b", "1.0.0-a-b"),
+    case("1.0.0-0.0", "1.0.0-0.0.0"),
+    case("1.0.0-2", "1.0.0-10"),
+    case("1.0.0-10", "1.0.0-99999999999999999999"),
+    case("1.0.0-rc.1+build.5", "1.0.0"),
+    )]
+    fn less_than(a: &str, b: &str) {
+        assert!(Version::<SemVerScheme>::new(a).unwrap() < Version::new(b).unwrap());
+    }
+
+    #[test]
+    fn build_metadata() {
+        let a = Version::<SemVerScheme>::new("1.0.0-rc.1+sha.abc").unwrap();
+        let b = Version::<SemVerScheme>::new("1.0.0-rc.1+sha.def").unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.to_string(), "1.0.0-rc.1+sha.abc");
+        assert_eq!(b.to_string(), "1.0.0-rc.1+sha.def");
+    }
+
+    #[rstest_parametrize(version, error,
+    case("", VersionParseError::EmptyComponent { offset: 0 }),
+    case("1.0", VersionParseError::EmptyComponent { offset: 3 }),
+    case("1..0", VersionParseError::EmptyComponent { offset: 2 }),
+    case("1.0.0.0", VersionParseError::IllegalCharacter { character: '.', offset: 5 }),
+    case("01.0.0", VersionParseError::IllegalCharacter { character: '0', offset: 0 }),
+    case("1.0.0-01", VersionParseError::IllegalCharacter { character: '0', offset: 6 }),
+    case("1.0.0-", VersionParseError::EmptyComponent { offset: 6 }),
+    case("1.0.0-a..b", VersionParseError::EmptyComponent { offset: 8 }),
+    case("1.0.0-a_b", VersionParseError::IllegalCharacter { character: '_', offset: 7 }),
+    case("1.0.0+", VersionParseError::EmptyComponent { offset: 6 }),
+    case("1.0.0+a+b", VersionParseError::IllegalCharacter { character: '+', offset: 7 }),
+    case("v1.0.0", VersionParseError::IllegalCharacter { character: 'v', offset: 0 }),
+    case("1.0.99999999999999999999", VersionParseError::NumericOverflow { offset: 4 }),
+    )]
+    fn invalid(version: &str, error: VersionParseError) {
+        assert_eq!(semver_parser(version).unwrap_err(), error);
+    }
+
+    #[test]
+    fn leading_zeros_in_build_metadata() {
+        assert!(semver_parser("1.0.0+001").is_ok());
+    }
+}
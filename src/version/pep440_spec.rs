@@ -0,0 +1,397 @@
+//! PEP 440 specifier module, which provides the `Pep440Specifier` enum and the
+//! `Pep440SpecifierSet` struct.
+//!
+//! Specifiers constrain PEP 440 versions the way the `Requires-Dist` metadata of Python packages
+//! does, such as `~=1.4.2` or `>=1.0,!=1.3.*`.  Matching follows PEP 440, including its special
+//! cases for pre-releases, post-releases and local versions.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::parse_error::VersionParseError;
+use super::pep440_version::Pep440Version;
+use super::spec_parse_error::VersionSpecParseError;
+
+/// A single PEP 440 version specifier.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pep440Specifier {
+    /// Matches compatible releases (`~=1.4.2`, meaning `>=1.4.2,==1.4.*`).
+    Compatible(Pep440Version),
+
+    /// Matches an equal version (`==1.4`).  When the specifier has no local version, the local
+    /// version of the candidate is ignored.
+    Equal(Pep440Version),
+
+    /// Matches versions whose release starts with the given release (`==1.4.*`).
+    EqualPrefix(Pep440Version),
+
+    /// Matches versions that are not equal (`!=1.4`).
+    NotEqual(Pep440Version),
+
+    /// Matches versions whose release doesn't start with the given release (`!=1.4.*`).
+    NotEqualPrefix(Pep440Version),
+
+    /// Matches lower or equal versions (`<=1.4`).
+    LessEqual(Pep440Version),
+
+    /// Matches greater or equal versions (`>=1.4`).
+    GreaterEqual(Pep440Version),
+
+    /// Matches lower versions, except pre-releases of the given version (`<1.4`).
+    Less(Pep440Version),
+
+    /// Matches greater versions, except post-releases and local versions of the given version
+    /// (`>1.4`).
+    Greater(Pep440Version),
+
+    /// Matches a version parsed from the given string, ignoring case (`===1.4`).  The version
+    /// isn't normalized, so `===1.4` doesn't match `1.4.0` or `v1.4`.
+    Arbitrary(String),
+}
+
+/// The operators, longest first so that `===` isn't read as `==`.
+const OPERATORS: [&str; 8] = ["===", "~=", "==", "!=", "<=", ">=", "<", ">"];
+
+/// Check whether `version` has the given epoch, and a release starting with `release`, padded
+/// with zeros.
+fn release_starts_with(version: &Pep440Version, epoch: u64, release: &[u64]) -> bool {
+    version.epoch() == epoch
+        && release.iter().enumerate().all(|(i, &n)| version.release().get(i).copied().unwrap_or(0) == n)
+}
+
+impl Pep440Specifier {
+    /// Get the version of this specifier, unless it is an arbitrary equality.
+    fn version(&self) -> Option<&Pep440Version> {
+        match self {
+            Pep440Specifier::Compatible(v) | Pep440Specifier::Equal(v) | Pep440Specifier::EqualPrefix(v)
+            | Pep440Specifier::NotEqual(v) | Pep440Specifier::NotEqualPrefix(v) | Pep440Specifier::LessEqual(v)
+            | Pep440Specifier::GreaterEqual(v) | Pep440Specifier::Less(v) | Pep440Specifier::Greater(v) => Some(v),
+            Pep440Specifier::Arbitrary(_) => None,
+        }
+    }
+
+    /// Check whether this specifier lets pre-releases match, because it mentions one.
+    pub fn allows_prereleases(&self) -> bool {
+        match self {
+            Pep440Specifier::NotEqual(_) | Pep440Specifier::NotEqualPrefix(_) => false,
+            Pep440Specifier::Arbitrary(text) => text.parse::<Pep440Version>().is_ok_and(|v| v.is_prerelease()),
+            _ => self.version().is_some_and(Pep440Version::is_prerelease),
+        }
+    }
+
+    /// Check whether the given `version` satisfies this specifier.
+    ///
+    /// Pre-releases only satisfy a specifier that mentions a pre-release, as `pip` does by
+    /// default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{Pep440Specifier, Pep440Version};
+    ///
+    /// let spec: Pep440Specifier = "~=1.4.2".parse().unwrap();
+    ///
+    /// assert!(spec.contains(&"1.4.5".parse::<Pep440Version>().unwrap()));
+    /// assert!(!spec.contains(&"1.5".parse::<Pep440Version>().unwrap()));
+    /// assert!(!spec.contains(&"1.4.6rc1".parse::<Pep440Version>().unwrap()));
+    /// ```
+    pub fn contains(&self, version: &Pep440Version) -> bool {
+        (!version.is_prerelease() || self.allows_prereleases()) && self.matches(version)
+    }
+
+    /// Check whether the given `version` satisfies this specifier, regardless of pre-releases.
+    pub(crate) fn matches(&self, version: &Pep440Version) -> bool {
+        match self {
+            Pep440Specifier::Compatible(spec) => {
+                let release = &spec.release()[..spec.release().len() - 1];
+                version.public() >= *spec && release_starts_with(version, spec.epoch(), release)
+            },
+            Pep440Specifier::Equal(spec) if spec.local().is_empty() => version.public() == *spec,
+            Pep440Specifier::Equal(spec) => version == spec,
+            Pep440Specifier::EqualPrefix(spec) => release_starts_with(version, spec.epoch(), spec.release()),
+            Pep440Specifier::NotEqual(spec) => !Pep440Specifier::Equal(spec.clone()).matches(version),
+            Pep440Specifier::NotEqualPrefix(spec) => !release_starts_with(version, spec.epoch(), spec.release()),
+            Pep440Specifier::LessEqual(spec) => version.public() <= *spec,
+            Pep440Specifier::GreaterEqual(spec) => version.public() >= *spec,
+            Pep440Specifier::Less(spec) => {
+                // `<1.4` doesn't match `1.4rc1`
+                version < spec && (spec.is_prerelease() || !version.is_prerelease() || version.base() != spec.base())
+            },
+            Pep440Specifier::Greater(spec) => {
+                // `>1.4` doesn't match `1.4.post1` or `1.4+local`
+                let same_base = version.base() == spec.base();
+                version > spec
+                    && (spec.is_postrelease() || !version.is_postrelease() || !same_base)
+                    && (version.local().is_empty() || !same_base)
+            },
+            Pep440Specifier::Arbitrary(text) => match version.original() {
+                Some(original) => original.eq_ignore_ascii_case(text),
+                None => version.to_string().eq_ignore_ascii_case(text),
+            },
+        }
+    }
+}
+
+/// Parse a single PEP 440 specifier, such as `>=1.4`.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{Pep440Specifier, VersionSpecParseError};
+///
+/// assert_eq!("== 1.4.*".parse::<Pep440Specifier>().unwrap().to_string(), "==1.4.*");
+/// assert_eq!(
+///     "~=1".parse::<Pep440Specifier>().unwrap_err(),
+///     VersionSpecParseError::InvalidCompatibleRelease { offset: 2 },
+/// );
+/// ```
+impl FromStr for Pep440Specifier {
+    type Err = VersionSpecParseError;
+
+    fn from_str(spec: &str) -> Result<Pep440Specifier, VersionSpecParseError> {
+        let start = spec.len() - spec.trim_start().len();
+        let trimmed = spec.trim();
+        let operator = OPERATORS.iter().find(|operator| trimmed.starts_with(*operator))
+            .ok_or(VersionSpecParseError::InvalidOperator { offset: start })?;
+        let version_start = start + operator.len() + (trimmed[operator.len()..].len() - trimmed[operator.len()..].trim_start().len());
+        let text = spec[version_start..].trim_end();
+        if text.is_empty() {
+            return Err(VersionSpecParseError::EmptyConstraint { offset: version_start });
+        }
+        if *operator == "===" {
+            return Ok(Pep440Specifier::Arbitrary(text.to_string()));
+        }
+
+        let invalid = |err: VersionParseError| VersionSpecParseError::InvalidVersion(err.shifted(version_start));
+        let (text, prefix) = match text.strip_suffix(".*") {
+            Some(text) if *operator == "==" || *operator == "!=" => (text, true),
+            _ => (text, false),
+        };
+        let version: Pep440Version = text.parse().map_err(invalid)?;
+
+        // Wildcards only follow a release, and ordered comparisons take no local version
+        if prefix && (version.is_prerelease() || version.is_postrelease() || !version.local().is_empty()) {
+            return Err(invalid(VersionParseError::IllegalCharacter { character: '*', offset: text.len() + 1 }));
+        }
+        if !prefix && !version.local().is_empty() && !matches!(*operator, "==" | "!=") {
+            let offset = text.find('+').unwrap_or(0);
+            return Err(invalid(VersionParseError::IllegalCharacter { character: '+', offset }));
+        }
+
+        Ok(match (*operator, prefix) {
+            ("~=", _) if version.release().len() < 2 =>
+                return Err(VersionSpecParseError::InvalidCompatibleRelease { offset: version_start }),
+            ("~=", _) => Pep440Specifier::Compatible(version),
+            ("==", false) => Pep440Specifier::Equal(version),
+            ("==", true) => Pep440Specifier::EqualPrefix(version),
+            ("!=", false) => Pep440Specifier::NotEqual(version),
+            ("!=", true) => Pep440Specifier::NotEqualPrefix(version),
+            ("<=", _) => Pep440Specifier::LessEqual(version),
+            (">=", _) => Pep440Specifier::GreaterEqual(version),
+            ("<", _) => Pep440Specifier::Less(version),
+            _ => Pep440Specifier::Greater(version),
+        })
+    }
+}
+
+impl fmt::Display for Pep440Specifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pep440Specifier::Compatible(v) => write!(f, "~={}", v),
+            Pep440Specifier::Equal(v) => write!(f, "=={}", v),
+            Pep440Specifier::EqualPrefix(v) => write!(f, "=={}.*", v),
+            Pep440Specifier::NotEqual(v) => write!(f, "!={}", v),
+            Pep440Specifier::NotEqualPrefix(v) => write!(f, "!={}.*", v),
+            Pep440Specifier::LessEqual(v) => write!(f, "<={}", v),
+            Pep440Specifier::GreaterEqual(v) => write!(f, ">={}", v),
+            Pep440Specifier::Less(v) => write!(f, "<{}", v),
+            Pep440Specifier::Greater(v) => write!(f, ">{}", v),
+            Pep440Specifier::Arbitrary(text) => write!(f, "==={}", text),
+        }
+    }
+}
+
+/// A comma separated list of PEP 440 specifiers, which must all be satisfied.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{Pep440SpecifierSet, Pep440Version};
+///
+/// let specs: Pep440SpecifierSet = ">=1.0,!=1.3.*,<2".parse().unwrap();
+///
+/// assert!(specs.contains(&"1.4".parse::<Pep440Version>().unwrap()));
+/// assert!(!specs.contains(&"1.3.1".parse::<Pep440Version>().unwrap()));
+/// assert!(!specs.contains(&"1.5a1".parse::<Pep440Version>().unwrap()));
+/// ```
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Pep440SpecifierSet {
+    specifiers: Vec<Pep440Specifier>,
+}
+
+impl Pep440SpecifierSet {
+    /// Get the specifiers of this set.
+    pub fn specifiers(&self) -> &[Pep440Specifier] {
+        &self.specifiers
+    }
+
+    /// Check whether the given `version` satisfies all specifiers of this set.
+    ///
+    /// Pre-releases only satisfy the set when one of its specifiers mentions a pre-release.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{Pep440SpecifierSet, Pep440Version};
+    ///
+    /// let version: Pep440Version = "2.0b1".parse().unwrap();
+    ///
+    /// assert!(!">=1.0".parse::<Pep440SpecifierSet>().unwrap().contains(&version));
+    /// assert!(">=1.0,<3.0a0".parse::<Pep440SpecifierSet>().unwrap().contains(&version));
+    /// ```
+    pub fn contains(&self, version: &Pep440Version) -> bool {
+        (!version.is_prerelease() || self.specifiers.iter().any(Pep440Specifier::allows_prereleases))
+            && self.specifiers.iter().all(|spec| spec.matches(version))
+    }
+}
+
+/// Parse a comma separated list of PEP 440 specifiers.  An empty string matches any final
+/// release.
+impl FromStr for Pep440SpecifierSet {
+    type Err = VersionSpecParseError;
+
+    fn from_str(specs: &str) -> Result<Pep440SpecifierSet, VersionSpecParseError> {
+        if specs.trim().is_empty() {
+            return Ok(Pep440SpecifierSet::default());
+        }
+
+        let mut specifiers = Vec::new();
+        let mut offset = 0;
+        for spec in specs.split(',') {
+            if spec.trim().is_empty() {
+                return Err(VersionSpecParseError::EmptyConstraint { offset });
+            }
+            specifiers.push(spec.parse().map_err(|err: VersionSpecParseError| err.shifted(offset))?);
+            offset += spec.len() + 1;
+        }
+        Ok(Pep440SpecifierSet { specifiers })
+    }
+}
+
+impl fmt::Display for Pep440SpecifierSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, spec) in self.specifiers.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", spec)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use super::{Pep440Specifier, Pep440SpecifierSet};
+    use crate::version::{Pep440Version, VersionParseError, VersionSpecParseError};
+
+    #[rstest_parametrize(version, spec, expected,
+    case("2.0", "==2", true),
+    case("2.0", "==2.0", true),
+    case("2.0", "==2.*", true),
+    case("2.0", "==2.0.*", true),
+    case("2.0.0", "==2.*", true),
+    case("2", "==2.0.*", true),
+    case("2.0.post1", "==2.0.*", true),
+    case("2.1+local.version", "==2.1.*", true),
+    case("1.2+downstream1", "==1.2", true),
+    case("1.2+downstream1", "==1.2+downstream1", true),
+    case("1.2+downstream1", "==1.2+other", false),
+    case("1!2.0", "==1!2.*", true),
+    case("2.0", "==1!2.*", false),
+    case("2.1", "==2", false),
+    case("2.1", "==2.0", false),
+    case("2.1", "==2.0.*", false),
+    case("2.0", "!=2.1", true),
+    case("2.0", "!=2.1.*", true),
+    case("2.0", "!=2", false),
+    case("2.0", "!=2.*", false),
+    case("2.0+local", "!=2.0", false),
+    case("2.0", "<=2", true),
+    case("2.0+local", "<=2", true),
+    case("2.0", ">=2", true),
+    case("2.0.post1", ">=2", true),
+    case("2.0", "<3", true),
+    case("2.0", "<2", false),
+    case("3.0.dev1", "<3", false),
+    case("3.0a1", "<3", false),
+    case("3.0a1", "<3.0a2", true),
+    case("2.0", ">1", true),
+    case("2.0", ">2", false),
+    case("2.0.post1", ">2", false),
+    case("2.0.post2", ">2.0.post1", true),
+    case("2.0+local", ">2", false),
+    case("2.0.1+local", ">2", true),
+    case("2.0", "~=2.0", true),
+    case("2.1", "~=2.0", true),
+    case("3.0", "~=2.0", false),
+    case("2.0.1", "~=2.0.0", true),
+    case("2.1", "~=2.0.0", false),
+    case("2.0", "~=2.0.1", false),
+    case("1!2.1", "~=1!2.0", true),
+    case("2.0", "===2.0", true),
+    case("2.0", "===2.0.0", false),
+    case("v2.0", "===2.0", false),
+    case("v2.0", "===V2.0", true),
+    case("2.0-RC1", "===2.0rc1", false),
+    case("2.0-RC1", "===2.0-rc1", true),
+    case("2.0+Local", "===2.0+Local", true),
+    case("2.0a1", ">=1", false),
+    case("2.0a1", ">=1,<3.0a0", true),
+    case("2.0a1", "~=2.0a0", true),
+    case("2.0", "", true),
+    case("2.0.dev1", "", false),
+    )]
+    fn contains(version: &str, spec: &str, expected: bool) {
+        let version: Pep440Version = version.parse().unwrap();
+        assert_eq!(spec.parse::<Pep440SpecifierSet>().unwrap().contains(&version), expected);
+    }
+
+    #[rstest_parametrize(spec, canonical,
+    case("~=1.4.2", "~=1.4.2"),
+    case(" == 1.4.* ", "==1.4.*"),
+    case("!=1.4-RC1", "!=1.4rc1"),
+    case(">= 1.0,< 2", ">=1.0,<2"),
+    case("===foo-bar", "===foo-bar"),
+    )]
+    fn display(spec: &str, canonical: &str) {
+        assert_eq!(spec.parse::<Pep440SpecifierSet>().unwrap().to_string(), canonical);
+    }
+
+    #[rstest_parametrize(spec, error,
+    case("1.0", VersionSpecParseError::InvalidOperator { offset: 0 }),
+    case(">=1.0,=1.0", VersionSpecParseError::InvalidOperator { offset: 6 }),
+    case(">=1.0,,<2", VersionSpecParseError::EmptyConstraint { offset: 6 }),
+    case(">=", VersionSpecParseError::EmptyConstraint { offset: 2 }),
+    case("~=1", VersionSpecParseError::InvalidCompatibleRelease { offset: 2 }),
+    case(">=1.0.*", VersionSpecParseError::InvalidVersion(
+        VersionParseError::IllegalCharacter { character: '.', offset: 5 })),
+    case("==1.0rc1.*", VersionSpecParseError::InvalidVersion(
+        VersionParseError::IllegalCharacter { character: '*', offset: 9 })),
+    case(">=1.0+local", VersionSpecParseError::InvalidVersion(
+        VersionParseError::IllegalCharacter { character: '+', offset: 5 })),
+    case("<1.0,>=x", VersionSpecParseError::InvalidVersion(
+        VersionParseError::IllegalCharacter { character: 'x', offset: 7 })),
+    )]
+    fn invalid(spec: &str, error: VersionSpecParseError) {
+        assert_eq!(spec.parse::<Pep440SpecifierSet>().unwrap_err(), error);
+    }
+
+    #[test]
+    fn allows_prereleases() {
+        assert!(">=1.0a1".parse::<Pep440Specifier>().unwrap().allows_prereleases());
+        assert!(!">=1.0".parse::<Pep440Specifier>().unwrap().allows_prereleases());
+        assert!(!"!=1.0a1".parse::<Pep440Specifier>().unwrap().allows_prereleases());
+    }
+}
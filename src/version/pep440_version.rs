@@ -0,0 +1,708 @@
+//! PEP 440 version module, which provides the `Pep440Version` struct.
+//!
+//! Unlike conda versions, Python package versions follow the strict grammar of PEP 440: an
+//! optional epoch, a release made of numbers, and optional pre-release, post-release,
+//! development release and local version segments.  Alternative spellings such as `1.0-RC1` or
+//! `1.0-1` are accepted, and normalized to the canonical form `1.0rc1` and `1.0.post1`.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use super::parse_error::VersionParseError;
+use super::version_part::{VersionPart, split_parts};
+
+/// The kind of a pre-release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PreRelease {
+    /// `a`, also spelled `alpha`.
+    Alpha,
+    /// `b`, also spelled `beta`.
+    Beta,
+    /// `rc`, also spelled `c`, `pre` or `preview`.
+    ReleaseCandidate,
+}
+
+impl fmt::Display for PreRelease {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            PreRelease::Alpha => "a",
+            PreRelease::Beta => "b",
+            PreRelease::ReleaseCandidate => "rc",
+        })
+    }
+}
+
+/// A segment of a local version.  Numbers sort after strings.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LocalSegment {
+    /// An alphanumeric segment, stored in lower case.
+    Text(String),
+    /// A numeric segment.
+    Number(u64),
+}
+
+impl fmt::Display for LocalSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocalSegment::Text(text) => f.write_str(text),
+            LocalSegment::Number(number) => write!(f, "{}", number),
+        }
+    }
+}
+
+/// A version following PEP 440.
+///
+/// Versions are normalized when parsed, and displayed in their canonical form.  They are ordered
+/// the way PEP 440 specifies: `1.0.dev1 < 1.0a1 < 1.0 < 1.0+local < 1.0.post1`.  The string a
+/// version was parsed from is kept for arbitrary equality (`===`), but doesn't affect ordering.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::Pep440Version;
+///
+/// let ver: Pep440Version = "v1.0-RC1".parse().unwrap();
+///
+/// assert_eq!(ver.to_string(), "1.0rc1");
+/// assert!(ver < "1.0".parse().unwrap());
+/// assert_eq!("1.0".parse::<Pep440Version>().unwrap(), "1.0.0".parse().unwrap());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Pep440Version {
+    epoch: u64,
+    release: Vec<u64>,
+    pre: Option<(PreRelease, u64)>,
+    post: Option<u64>,
+    dev: Option<u64>,
+    local: Vec<LocalSegment>,
+    original: Option<String>,
+}
+
+/// The spellings of pre-release kinds, longest first so that `preview` isn't read as `pre`.
+const PRE_LABELS: [(&str, PreRelease); 8] = [
+    ("alpha", PreRelease::Alpha),
+    ("beta", PreRelease::Beta),
+    ("preview", PreRelease::ReleaseCandidate),
+    ("pre", PreRelease::ReleaseCandidate),
+    ("rc", PreRelease::ReleaseCandidate),
+    ("a", PreRelease::Alpha),
+    ("b", PreRelease::Beta),
+    ("c", PreRelease::ReleaseCandidate),
+];
+
+/// The spellings of post-releases.
+const POST_LABELS: [(&str, ()); 3] = [("post", ()), ("rev", ()), ("r", ())];
+
+/// A cursor over a lower case version string.
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+    base: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_separator(&mut self) -> bool {
+        match self.peek() {
+            Some(b'.') | Some(b'-') | Some(b'_') => {
+                self.pos += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn at_digit(&self) -> bool {
+        self.peek().is_some_and(|b| b.is_ascii_digit())
+    }
+
+    /// Read a number, which must start at the cursor.
+    fn number(&mut self) -> Result<u64, VersionParseError> {
+        let start = self.pos;
+        let len = self.rest().bytes().take_while(u8::is_ascii_digit).count();
+        self.pos += len;
+        self.text[start..self.pos].parse().map_err(|_| VersionParseError::NumericOverflow { offset: self.base + start })
+    }
+
+    /// Read the optional number following a pre-release, post-release or dev label, which may
+    /// be preceded by a separator.
+    fn label_number(&mut self) -> Result<u64, VersionParseError> {
+        let start = self.pos;
+        self.eat_separator();
+        if self.at_digit() {
+            self.number()
+        } else {
+            self.pos = start;
+            Ok(0)
+        }
+    }
+
+    /// Try to read a pre-release, post-release or dev segment with one of the `labels`, which
+    /// may be preceded by a separator.
+    fn segment<T: Copy>(&mut self, labels: &[(&str, T)]) -> Result<Option<(T, u64)>, VersionParseError> {
+        let start = self.pos;
+        self.eat_separator();
+        for &(label, value) in labels {
+            if self.eat(label) {
+                return Ok(Some((value, self.label_number()?)));
+            }
+        }
+        self.pos = start;
+        Ok(None)
+    }
+
+    /// Build the error for the character at the cursor.
+    fn unexpected(&self, original: &str) -> VersionParseError {
+        match original[self.pos..].chars().next() {
+            Some(character) => VersionParseError::IllegalCharacter { character, offset: self.base + self.pos },
+            None => VersionParseError::EmptyComponent { offset: self.base + self.pos },
+        }
+    }
+}
+
+/// Parse a PEP 440 version string, ignoring case and surrounding whitespace.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{Pep440Version, VersionParseError};
+///
+/// assert_eq!(
+///     "1.0foo".parse::<Pep440Version>().unwrap_err(),
+///     VersionParseError::IllegalCharacter { character: 'f', offset: 3 },
+/// );
+/// ```
+impl FromStr for Pep440Version {
+    type Err = VersionParseError;
+
+    fn from_str(version: &str) -> Result<Pep440Version, VersionParseError> {
+        let base = version.len() - version.trim_start().len();
+        let original = version.trim();
+        let lower = original.to_ascii_lowercase();
+        let mut cursor = Cursor { text: &lower, pos: 0, base };
+
+        cursor.eat("v");
+        if !cursor.at_digit() {
+            return Err(cursor.unexpected(original));
+        }
+
+        // The epoch and release
+        let mut epoch = 0;
+        let mut release = vec![cursor.number()?];
+        if cursor.eat("!") {
+            if !cursor.at_digit() {
+                return Err(cursor.unexpected(original));
+            }
+            epoch = release[0];
+            release[0] = cursor.number()?;
+        }
+        while cursor.rest().starts_with('.') && cursor.rest()[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            cursor.pos += 1;
+            release.push(cursor.number()?);
+        }
+
+        // The pre-release, post-release and dev segments
+        let pre = cursor.segment(&PRE_LABELS)?;
+        let post = if cursor.rest().starts_with('-') && cursor.rest()[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            // `1.0-1` is an implicit post-release
+            cursor.pos += 1;
+            Some(cursor.number()?)
+        } else {
+            cursor.segment(&POST_LABELS)?.map(|(_, number)| number)
+        };
+        let dev = cursor.segment(&[("dev", ())])?.map(|(_, number)| number);
+
+        // The local version
+        let mut local = Vec::new();
+        if cursor.eat("+") {
+            loop {
+                let start = cursor.pos;
+                let len = cursor.rest().bytes().take_while(u8::is_ascii_alphanumeric).count();
+                if len == 0 {
+                    return Err(cursor.unexpected(original));
+                }
+                cursor.pos += len;
+                let segment = &lower[start..cursor.pos];
+                local.push(if segment.bytes().all(|b| b.is_ascii_digit()) {
+                    LocalSegment::Number(segment.parse()
+                        .map_err(|_| VersionParseError::NumericOverflow { offset: base + start })?)
+                } else {
+                    LocalSegment::Text(segment.to_string())
+                });
+                if !cursor.eat_separator() {
+                    break;
+                }
+            }
+        }
+
+        if cursor.pos < lower.len() {
+            return Err(cursor.unexpected(original));
+        }
+        Ok(Pep440Version { epoch, release, pre, post, dev, local, original: Some(original.to_string()) })
+    }
+}
+
+impl Pep440Version {
+    /// Get the epoch, which is `0` when not given.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Get the numbers of the release segment.
+    pub fn release(&self) -> &[u64] {
+        &self.release
+    }
+
+    /// Get the pre-release kind and number.
+    pub fn pre(&self) -> Option<(PreRelease, u64)> {
+        self.pre
+    }
+
+    /// Get the post-release number.
+    pub fn post(&self) -> Option<u64> {
+        self.post
+    }
+
+    /// Get the development release number.
+    pub fn dev(&self) -> Option<u64> {
+        self.dev
+    }
+
+    /// Get the segments of the local version, which is empty when not given.
+    pub fn local(&self) -> &[LocalSegment] {
+        &self.local
+    }
+
+    /// Get the string this version was parsed from, without surrounding whitespace.  Versions
+    /// derived from another version, such as with `public`, have none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Pep440Version;
+    ///
+    /// let ver: Pep440Version = " v1.0-RC1+Local ".parse().unwrap();
+    ///
+    /// assert_eq!(ver.original(), Some("v1.0-RC1+Local"));
+    /// assert_eq!(ver.public().original(), None);
+    /// ```
+    pub fn original(&self) -> Option<&str> {
+        self.original.as_deref()
+    }
+
+    /// Check whether this is a pre-release or a development release.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Pep440Version;
+    ///
+    /// assert!("1.0rc1".parse::<Pep440Version>().unwrap().is_prerelease());
+    /// assert!("1.0.post1.dev0".parse::<Pep440Version>().unwrap().is_prerelease());
+    /// assert!(!"1.0.post1".parse::<Pep440Version>().unwrap().is_prerelease());
+    /// ```
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// Check whether this is a post-release.
+    pub fn is_postrelease(&self) -> bool {
+        self.post.is_some()
+    }
+
+    /// Get this version without its local version.
+    pub fn public(&self) -> Pep440Version {
+        Pep440Version { local: Vec::new(), original: None, ..self.clone() }
+    }
+
+    /// Get the epoch and release of this version, without any other segment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::Pep440Version;
+    ///
+    /// let ver: Pep440Version = "1!2.0rc1.post2+local".parse().unwrap();
+    ///
+    /// assert_eq!(ver.base().to_string(), "1!2.0");
+    /// ```
+    pub fn base(&self) -> Pep440Version {
+        Pep440Version {
+            epoch: self.epoch,
+            release: self.release.clone(),
+            pre: None,
+            post: None,
+            dev: None,
+            local: Vec::new(),
+            original: None,
+        }
+    }
+
+    /// The release without trailing zeros, which don't affect ordering.
+    fn trimmed_release(&self) -> &[u64] {
+        let len = self.release.iter().rposition(|&n| n != 0).map_or(0, |i| i + 1);
+        &self.release[..len]
+    }
+
+    /// Build the version parts of this version, see `pep440_parser`.
+    ///
+    /// The epoch and the release numbers come first.  The pre-release, post-release and dev
+    /// segments follow, each as its label and its number, and the local segments come last.
+    pub(crate) fn to_parts(&self) -> Vec<VersionPart<'static>> {
+        let label = |label: String| VersionPart::LexicographicString(Cow::Owned(label));
+
+        let mut parts = Vec::with_capacity(self.release.len() + 8);
+        if self.epoch != 0 {
+            parts.push(VersionPart::Epoch(self.epoch));
+        }
+        parts.extend(self.release.iter().map(|&n| VersionPart::Integer(n)));
+        if let Some((kind, number)) = self.pre {
+            parts.extend([label(kind.to_string()), VersionPart::Integer(number)]);
+        }
+        if let Some(number) = self.post {
+            parts.extend([label("post".to_string()), VersionPart::Integer(number)]);
+        }
+        if let Some(number) = self.dev {
+            parts.extend([label("dev".to_string()), VersionPart::Integer(number)]);
+        }
+        if !self.local.is_empty() {
+            parts.push(VersionPart::Local(self.local.iter().map(|segment| match segment {
+                LocalSegment::Text(text) => label(text.clone()),
+                LocalSegment::Number(number) => VersionPart::Integer(*number),
+            }).collect()));
+        }
+        parts
+    }
+}
+
+/// Get the number of an integer version part.
+fn part_number(part: &VersionPart) -> Option<u64> {
+    match *part {
+        VersionPart::Integer(number) => Some(number),
+        _ => None,
+    }
+}
+
+/// The pre-release key of a version.  A development release of a final release sorts before all
+/// of its pre-releases, and a final release after them.
+fn pre_key(pre: Option<(PreRelease, u64)>, post: Option<u64>, dev: Option<u64>) -> (u8, Option<(PreRelease, u64)>) {
+    match (pre, post, dev) {
+        (None, None, Some(_)) => (0, None),
+        (Some(pre), _, _) => (1, Some(pre)),
+        (None, _, _) => (2, None),
+    }
+}
+
+/// The development release key of a version, a missing development release sorts last.
+fn dev_key(dev: Option<u64>) -> (bool, u64) {
+    (dev.is_none(), dev.unwrap_or(0))
+}
+
+/// A local segment of version parts, ordered like `LocalSegment`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum LocalKey<'p> {
+    Text(&'p str),
+    Number(u64),
+}
+
+/// The segments of the parts built by `Pep440Version::to_parts`, read in place so that the parts
+/// can be ordered and hashed like the version they were built from without rebuilding it.
+pub(crate) struct PartsKey<'p> {
+    epoch: u64,
+    release: &'p [VersionPart<'p>],
+    pre: Option<(PreRelease, u64)>,
+    post: Option<u64>,
+    dev: Option<u64>,
+    local: &'p [VersionPart<'p>],
+}
+
+impl<'p> PartsKey<'p> {
+    /// Read the segments of `parts`: the epoch, the release numbers, each labeled segment as its
+    /// label followed by its number, and the local segments.
+    pub(crate) fn new(parts: &'p [VersionPart<'p>]) -> PartsKey<'p> {
+        let (epoch, public, local) = split_parts(parts);
+        let release_len = public.iter().position(|part| part_number(part).is_none()).unwrap_or(public.len());
+        let (release, labeled) = public.split_at(release_len);
+        let mut key = PartsKey { epoch, release, pre: None, post: None, dev: None, local };
+
+        let mut label = None;
+        for part in labeled {
+            match (part, label.take()) {
+                (VersionPart::LexicographicString(text), None) => label = Some(text),
+                (&VersionPart::Integer(number), Some(label)) => match &**label {
+                    "post" => key.post = Some(number),
+                    "dev" => key.dev = Some(number),
+                    kind => key.pre = PRE_LABELS.iter()
+                        .find(|(spelling, _)| *spelling == kind)
+                        .map(|&(_, kind)| (kind, number)),
+                },
+                _ => {},
+            }
+        }
+        key
+    }
+
+    /// The release numbers without trailing zeros, which don't affect ordering.
+    fn trimmed_release(&self) -> impl Iterator<Item = u64> + 'p {
+        let len = self.release.iter().rposition(|part| part_number(part) != Some(0)).map_or(0, |i| i + 1);
+        self.release[..len].iter().filter_map(part_number)
+    }
+
+    /// The local segments.
+    fn local(&self) -> impl Iterator<Item = LocalKey<'p>> {
+        self.local.iter().filter_map(|part| match part {
+            VersionPart::Integer(number) => Some(LocalKey::Number(*number)),
+            VersionPart::LexicographicString(text) => Some(LocalKey::Text(text)),
+            _ => None,
+        })
+    }
+}
+
+impl PartialOrd for PartsKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Ordered like `Pep440Version`.
+impl Ord for PartsKey<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.epoch.cmp(&other.epoch)
+            .then_with(|| self.trimmed_release().cmp(other.trimmed_release()))
+            .then_with(|| pre_key(self.pre, self.post, self.dev).cmp(&pre_key(other.pre, other.post, other.dev)))
+            .then_with(|| self.post.cmp(&other.post))
+            .then_with(|| dev_key(self.dev).cmp(&dev_key(other.dev)))
+            .then_with(|| self.local().cmp(other.local()))
+    }
+}
+
+impl PartialEq for PartsKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PartsKey<'_> {}
+
+impl Hash for PartsKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.epoch.hash(state);
+        self.trimmed_release().for_each(|number| number.hash(state));
+        pre_key(self.pre, self.post, self.dev).hash(state);
+        self.post.hash(state);
+        dev_key(self.dev).hash(state);
+        self.local().for_each(|segment| segment.hash(state));
+    }
+}
+
+impl fmt::Display for Pep440Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}!", self.epoch)?;
+        }
+        for (i, number) in self.release.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", number)?;
+        }
+        if let Some((kind, number)) = self.pre {
+            write!(f, "{}{}", kind, number)?;
+        }
+        if let Some(number) = self.post {
+            write!(f, ".post{}", number)?;
+        }
+        if let Some(number) = self.dev {
+            write!(f, ".dev{}", number)?;
+        }
+        for (i, segment) in self.local.iter().enumerate() {
+            f.write_str(if i == 0 { "+" } else { "." })?;
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialOrd for Pep440Version {
+    fn partial_cmp(&self, other: &Pep440Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pep440Version {
+    fn cmp(&self, other: &Pep440Version) -> Ordering {
+        self.epoch.cmp(&other.epoch)
+            .then_with(|| self.trimmed_release().cmp(other.trimmed_release()))
+            .then_with(|| pre_key(self.pre, self.post, self.dev).cmp(&pre_key(other.pre, other.post, other.dev)))
+            .then_with(|| self.post.cmp(&other.post))
+            .then_with(|| dev_key(self.dev).cmp(&dev_key(other.dev)))
+            .then_with(|| self.local.cmp(&other.local))
+    }
+}
+
+impl PartialEq for Pep440Version {
+    fn eq(&self, other: &Pep440Version) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pep440Version {}
+
+impl Hash for Pep440Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.epoch.hash(state);
+        self.trimmed_release().hash(state);
+        pre_key(self.pre, self.post, self.dev).hash(state);
+        self.post.hash(state);
+        dev_key(self.dev).hash(state);
+        self.local.hash(state);
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    use super::{PartsKey, Pep440Version};
+    use crate::version::{Pep440Scheme, Version, VersionParseError};
+
+    /// Versions in ascending order, from the test suite of the `packaging` project.
+    const ORDERED: &[&str] = &[
+        // Implicit epoch of 0
+        "1.0.dev456", "1.0a1", "1.0a2.dev456", "1.0a12.dev456", "1.0a12", "1.0b1.dev456", "1.0b2",
+        "1.0b2.post345.dev456", "1.0b2.post345", "1.0b2-346", "1.0c1.dev456", "1.0c1", "1.0rc2", "1.0c3",
+        "1.0", "1.0.post456.dev34", "1.0.post456", "1.1.dev1", "1.2+123abc", "1.2+123abc456", "1.2+abc",
+        "1.2+abc123", "1.2+abc123def", "1.2+1234.abc", "1.2+123456", "1.2.r32+123456", "1.2.rev33+123456",
+        // Explicit epoch of 1
+        "1!1.0.dev456", "1!1.0a1", "1!1.0a2.dev456", "1!1.0a12.dev456", "1!1.0a12", "1!1.0b1.dev456",
+        "1!1.0b2", "1!1.0b2.post345.dev456", "1!1.0b2.post345", "1!1.0b2-346", "1!1.0c1.dev456",
+        "1!1.0c1", "1!1.0rc2", "1!1.0c3", "1!1.0", "1!1.0.post456.dev34", "1!1.0.post456", "1!1.1.dev1",
+        "1!1.2+123abc", "1!1.2+123abc456", "1!1.2+abc", "1!1.2+abc123", "1!1.2+abc123def",
+        "1!1.2+1234.abc", "1!1.2+123456", "1!1.2.r32+123456", "1!1.2.rev33+123456",
+    ];
+
+    #[test]
+    fn ordering() {
+        let versions: Vec<Pep440Version> = ORDERED.iter().map(|v| v.parse().unwrap()).collect();
+        for (i, a) in versions.iter().enumerate() {
+            for (j, b) in versions.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{} vs {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn scheme_ordering() {
+        let versions: Vec<Version<Pep440Scheme>> = ORDERED.iter().map(|v| Version::new(v).unwrap()).collect();
+        for (i, a) in versions.iter().enumerate() {
+            for (j, b) in versions.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{} vs {}", a, b);
+            }
+        }
+
+        let version = |version: &str| Version::<Pep440Scheme>::new(version).unwrap().into_owned();
+        assert!(version("1.dev0") < version("1.0.1"));
+        assert_eq!(version("1.0"), version("1"));
+        assert!(version("1.0+dev") > version("1.0+abc"));
+    }
+
+    #[test]
+    fn parts_key() {
+        let hash = |key: &PartsKey| {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            hasher.finish()
+        };
+        let versions: Vec<Pep440Version> = ORDERED.iter().chain(&["1", "1.0.0", "1+local.2", "1.0+LOCAL-2"])
+            .map(|version| version.parse().unwrap())
+            .collect();
+        let parts: Vec<_> = versions.iter().map(Pep440Version::to_parts).collect();
+        for (a, parts_a) in versions.iter().zip(&parts) {
+            for (b, parts_b) in versions.iter().zip(&parts) {
+                let (key_a, key_b) = (PartsKey::new(parts_a), PartsKey::new(parts_b));
+                assert_eq!(key_a.cmp(&key_b), a.cmp(b), "{} vs {}", a, b);
+                if a == b {
+                    assert_eq!(hash(&key_a), hash(&key_b), "{} vs {}", a, b);
+                }
+            }
+        }
+    }
+
+    #[rstest_parametrize(version, normalized,
+    case("1.0", "1.0"),
+    case("v1.0", "1.0"),
+    case(" 1.0\n", "1.0"),
+    case("1.0-RC1", "1.0rc1"),
+    case("1.0alpha1", "1.0a1"),
+    case("1.0.beta.2", "1.0b2"),
+    case("1.0-c1", "1.0rc1"),
+    case("1.0pre1", "1.0rc1"),
+    case("1.0.preview_1", "1.0rc1"),
+    case("1.0a", "1.0a0"),
+    case("1.0_a_1", "1.0a1"),
+    case("1.0-1", "1.0.post1"),
+    case("1.0-post", "1.0.post0"),
+    case("1.0.r4", "1.0.post4"),
+    case("1.0rev4", "1.0.post4"),
+    case("1.0-dev", "1.0.dev0"),
+    case("1.0.DEV1", "1.0.dev1"),
+    case("1.0a1-1", "1.0a1.post1"),
+    case("01!02.003", "1!2.3"),
+    case("1.0+ubuntu-1", "1.0+ubuntu.1"),
+    case("1.0+Ubuntu_01", "1.0+ubuntu.1"),
+    case("1.0.post1.dev2+abc.5", "1.0.post1.dev2+abc.5"),
+    )]
+    fn normalize(version: &str, normalized: &str) {
+        assert_eq!(version.parse::<Pep440Version>().unwrap().to_string(), normalized);
+    }
+
+    #[rstest_parametrize(version, error,
+    case("", VersionParseError::EmptyComponent { offset: 0 }),
+    case("french toast", VersionParseError::IllegalCharacter { character: 'f', offset: 0 }),
+    case("1.0foo", VersionParseError::IllegalCharacter { character: 'f', offset: 3 }),
+    case("1.0.", VersionParseError::IllegalCharacter { character: '.', offset: 3 }),
+    case("1..0", VersionParseError::IllegalCharacter { character: '.', offset: 1 }),
+    case("1.0-", VersionParseError::IllegalCharacter { character: '-', offset: 3 }),
+    case("1.0+", VersionParseError::EmptyComponent { offset: 4 }),
+    case("1.0+a..b", VersionParseError::IllegalCharacter { character: '.', offset: 6 }),
+    case("1!", VersionParseError::EmptyComponent { offset: 2 }),
+    case("1.0 a1", VersionParseError::IllegalCharacter { character: ' ', offset: 3 }),
+    case("1.99999999999999999999", VersionParseError::NumericOverflow { offset: 2 }),
+    )]
+    fn invalid(version: &str, error: VersionParseError) {
+        assert_eq!(version.parse::<Pep440Version>().unwrap_err(), error);
+    }
+
+    #[test]
+    fn segments() {
+        let version: Pep440Version = "2!1.2.3rc4.post5.dev6+abc.7".parse().unwrap();
+        assert_eq!(version.epoch(), 2);
+        assert_eq!(version.release(), &[1, 2, 3]);
+        assert_eq!(version.pre(), Some((super::PreRelease::ReleaseCandidate, 4)));
+        assert_eq!(version.post(), Some(5));
+        assert_eq!(version.dev(), Some(6));
+        assert_eq!(version.local().len(), 2);
+        assert_eq!(version.public().to_string(), "2!1.2.3rc4.post5.dev6");
+    }
+}
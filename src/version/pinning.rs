@@ -0,0 +1,246 @@
+//! Pinning module, which provides the `Pin` struct.
+//!
+//! A pin turns a concrete version into a version spec bounding the versions that are compatible
+//! with it, the way conda-build's `pin_compatible` and `pin_subpackage` do for `run_exports`.
+//! With the pin `max_pin='x.x'`, version `1.16.4` becomes `>=1.16.4,<1.17.0a0`.
+
+use super::comp_op::CompOp;
+use super::parse_error::VersionParseError;
+use super::version::{Version, component_spans};
+use super::version_buf::VersionBuf;
+use super::version_spec::VersionSpec;
+
+/// The pin expressions used by conda-build when none are given.
+const DEFAULT_MIN_PIN: &str = "x.x.x.x.x.x.x";
+const DEFAULT_MAX_PIN: &str = "x";
+
+/// Options for pinning a version into a version spec.
+///
+/// Pin expressions such as `x.x` give the number of version components that are kept: the
+/// lower bound keeps `min_pin` components, and the upper bound increments the last of the
+/// `max_pin` components and appends `.0a0`, so that pre-releases of the next version are
+/// excluded.  The defaults are those of conda-build, `min_pin='x.x.x.x.x.x.x'` and
+/// `max_pin='x'`.
+///
+/// The epoch of the version is kept in both bounds, while its local version is dropped unless
+/// the lower bound would exceed the version without it.  When the version has fewer components
+/// than `max_pin`, all of its components are used.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{Pin, Version};
+///
+/// let version = Version::from("1.16.4").unwrap();
+///
+/// assert_eq!(Pin::new().max_pin(Some("x.x")).apply(&version).unwrap().to_string(), ">=1.16.4,<1.17.0a0");
+/// assert_eq!(Pin::new().apply(&version).unwrap().to_string(), ">=1.16.4,<2.0a0");
+/// assert_eq!(Pin::new().exact(true).apply(&version).unwrap().to_string(), "==1.16.4");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pin {
+    min_pin: Option<usize>,
+    max_pin: Option<usize>,
+    lower_bound: Option<VersionBuf>,
+    upper_bound: Option<VersionBuf>,
+    exact: bool,
+}
+
+/// Get the number of components a pin expression such as `x.x` keeps, or `None` for an empty
+/// expression.  Like conda-build, only the `.` separators are counted.
+fn pin_depth(pattern: Option<&str>) -> Option<usize> {
+    pattern.map(str::trim).filter(|pattern| !pattern.is_empty()).map(|pattern| pattern.split('.').count())
+}
+
+impl Pin {
+    /// Create a pin with the conda-build defaults.
+    pub fn new() -> Pin {
+        Pin {
+            min_pin: pin_depth(Some(DEFAULT_MIN_PIN)),
+            max_pin: pin_depth(Some(DEFAULT_MAX_PIN)),
+            lower_bound: None,
+            upper_bound: None,
+            exact: false,
+        }
+    }
+
+    /// Set the pin expression for the lower bound.  With `None`, there is no lower bound.
+    pub fn min_pin(mut self, pattern: Option<&str>) -> Pin {
+        self.min_pin = pin_depth(pattern);
+        self
+    }
+
+    /// Set the pin expression for the upper bound.  With `None`, there is no upper bound.
+    pub fn max_pin(mut self, pattern: Option<&str>) -> Pin {
+        self.max_pin = pin_depth(pattern);
+        self
+    }
+
+    /// Use the given version as the lower bound instead of the one given by `min_pin`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{Pin, Version};
+    ///
+    /// let pin = Pin::new().lower_bound("1.10".parse().unwrap()).upper_bound("3".parse().unwrap());
+    ///
+    /// assert_eq!(pin.apply(&Version::from("1.16.4").unwrap()).unwrap().to_string(), ">=1.10,<3");
+    /// ```
+    pub fn lower_bound(mut self, bound: VersionBuf) -> Pin {
+        self.lower_bound = Some(bound);
+        self
+    }
+
+    /// Use the given version as the upper bound instead of the one given by `max_pin`.
+    pub fn upper_bound(mut self, bound: VersionBuf) -> Pin {
+        self.upper_bound = Some(bound);
+        self
+    }
+
+    /// Pin to exactly the given version, ignoring all other options.
+    pub fn exact(mut self, exact: bool) -> Pin {
+        self.exact = exact;
+        self
+    }
+
+    /// Build the version spec that pins the given `version`.
+    ///
+    /// This fails with `NumericOverflow` when the number to increment for the upper bound is
+    /// already `u64::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{Pin, Version};
+    ///
+    /// let pin = Pin::new().min_pin(Some("x.x")).max_pin(Some("x.x"));
+    ///
+    /// assert_eq!(pin.apply(&Version::from("1!2.3.4+cuda").unwrap()).unwrap().to_string(), ">=1!2.3,<1!2.4.0a0");
+    /// assert_eq!(pin.apply(&Version::from("9").unwrap()).unwrap().to_string(), ">=9,<10.0a0");
+    /// ```
+    pub fn apply(&self, version: &Version) -> Result<VersionSpec, VersionParseError> {
+        if self.exact {
+            return Ok(VersionSpec::Compare(CompOp::Eq, version.into()));
+        }
+
+        let mut specs = Vec::with_capacity(2);
+        let lower = match self.lower_bound.clone() {
+            Some(bound) => Some(bound),
+            None => self.min_pin.map(|depth| lower_pin(version, depth)).transpose()?,
+        };
+        if let Some(lower) = lower {
+            specs.push(VersionSpec::Compare(CompOp::Ge, lower));
+        }
+        let upper = match self.upper_bound.clone() {
+            Some(bound) => Some(bound),
+            None => self.max_pin.map(|depth| upper_pin(version, depth)).transpose()?,
+        };
+        if let Some(upper) = upper {
+            specs.push(VersionSpec::Compare(CompOp::Lt, upper));
+        }
+
+        Ok(match specs.len() {
+            0 => VersionSpec::Any,
+            1 => specs.pop().unwrap(),
+            _ => VersionSpec::All(specs),
+        })
+    }
+}
+
+impl Default for Pin {
+    fn default() -> Pin {
+        Pin::new()
+    }
+}
+
+/// Build a bound with the epoch of `version` and the given public version.
+fn with_epoch(epoch: Option<&str>, public: &str) -> Result<VersionBuf, VersionParseError> {
+    let bound = match epoch {
+        Some(epoch) => format!("{}!{}", epoch, public),
+        None => public.to_string(),
+    };
+    bound.parse()
+}
+
+/// The lower bound keeping `depth` components of `version`, which must not exceed the version.
+fn lower_pin(version: &Version, depth: usize) -> Result<VersionBuf, VersionParseError> {
+    let (epoch, public, _) = version.split_str();
+    let spans = component_spans(public);
+    let end = spans.get(depth.min(spans.len()).wrapping_sub(1)).map_or(0, |&(_, end)| end);
+    let bound = with_epoch(epoch, &public[..end])?;
+
+    // Truncating `1.0a1` to `1`, or dropping the local version of `1.2+cuda`, gives a version
+    // above it
+    if *bound > *version {
+        Ok(version.into())
+    } else {
+        Ok(bound)
+    }
+}
+
+/// The upper bound incrementing the last of the first `depth` components of `version`.
+fn upper_pin(version: &Version, depth: usize) -> Result<VersionBuf, VersionParseError> {
+    let (epoch, public, _) = version.split_str();
+    let spans = component_spans(public);
+    let (start, end) = match spans.get(depth.min(spans.len()).wrapping_sub(1)) {
+        Some(&span) => span,
+        None => return with_epoch(epoch, "1.0a0"),
+    };
+
+    // Only the leading number of the component is incremented, `1k` becomes `2`
+    let component = &public[start..end];
+    let digits = component.bytes().take_while(u8::is_ascii_digit).count();
+    let number: u64 = component[..digits].parse().unwrap_or(0);
+    let offset = epoch.map_or(0, |epoch| epoch.len() + 1) + start;
+    let number = number.checked_add(1).ok_or(VersionParseError::NumericOverflow { offset })?;
+    with_epoch(epoch, &format!("{}{}.0a0", &public[..start], number))
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use super::Pin;
+    use crate::version::{Version, VersionParseError};
+
+    #[rstest_parametrize(version, min_pin, max_pin, expected,
+    case("1.16.4", Some("x.x.x.x.x.x.x"), Some("x.x"), ">=1.16.4,<1.17.0a0"),
+    case("1.16.4", Some("x.x.x.x.x.x.x"), Some("x"), ">=1.16.4,<2.0a0"),
+    case("1.16.4", Some("x.x"), Some("x.x.x"), ">=1.16,<1.16.5.0a0"),
+    case("1.16.4", None, Some("x.x"), "<1.17.0a0"),
+    case("1.16.4", Some("x.x"), None, ">=1.16"),
+    case("1.16.4", None, None, "*"),
+    case("2", Some("x.x.x"), Some("x.x"), ">=2,<3.0a0"),
+    case("1.2", Some("x"), Some("x.x.x.x"), ">=1,<1.3.0a0"),
+    case("1!2.3.4", Some("x.x"), Some("x.x"), ">=1!2.3,<1!2.4.0a0"),
+    case("1.2.3+cuda.11", Some("x.x.x.x.x.x.x"), Some("x"), ">=1.2.3+cuda.11,<2.0a0"),
+    case("1.2.3+cuda.11", Some("x.x"), Some("x"), ">=1.2,<2.0a0"),
+    case("1.1.1k", Some("x.x.x.x.x.x.x"), Some("x.x.x"), ">=1.1.1k,<1.1.2.0a0"),
+    case("1.0a1", Some("x"), Some("x"), ">=1.0a1,<2.0a0"),
+    case("1_16_4", Some("x.x.x"), Some("x.x"), ">=1_16_4,<1_17.0a0"),
+    )]
+    fn apply(version: &str, min_pin: Option<&str>, max_pin: Option<&str>, expected: &str) {
+        let pin = Pin::new().min_pin(min_pin).max_pin(max_pin);
+        let spec = pin.apply(&Version::from(version).unwrap()).unwrap();
+        assert_eq!(spec.to_string(), expected);
+        assert!(spec.contains(&Version::from(version).unwrap()));
+    }
+
+    #[test]
+    fn bounds() {
+        let version = Version::from("1.16.4").unwrap();
+        let pin = Pin::new().max_pin(Some("x.x"));
+
+        assert_eq!(pin.clone().upper_bound("1.18".parse().unwrap()).apply(&version).unwrap().to_string(), ">=1.16.4,<1.18");
+        assert_eq!(pin.clone().lower_bound("1.0".parse().unwrap()).apply(&version).unwrap().to_string(), ">=1.0,<1.17.0a0");
+        assert_eq!(pin.exact(true).apply(&version).unwrap().to_string(), "==1.16.4");
+    }
+
+    #[test]
+    fn overflow() {
+        let version = Version::from("1!2.18446744073709551615.1").unwrap();
+        let pin = Pin::new().max_pin(Some("x.x"));
+        assert_eq!(pin.apply(&version).unwrap_err(), VersionParseError::NumericOverflow { offset: 4 });
+        assert_eq!(Pin::new().apply(&version).unwrap().to_string(), ">=1!2.18446744073709551615.1,<1!3.0a0");
+    }
+}
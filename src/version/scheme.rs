@@ -0,0 +1,248 @@
+//! Version scheme module, which provides the `VersionScheme` trait and its implementations.
+//!
+//! A version scheme decides how version strings are split into parts, and how those parts are
+//! ordered, hashed and displayed.  `Version` and `VersionBuf` are generic over their scheme, so
+//! versions of different schemes are different types and can't be compared by accident.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use super::parse_error::VersionParseError;
+use super::parsers::conda::conda_parser;
+use super::parsers::debian::debian_parser;
+use super::parsers::default::default_parser;
+use super::parsers::pep440::pep440_parser;
+use super::parsers::rpm::rpm_parser;
+use super::parsers::semver::{compare_semver, hash_semver, semver_parser};
+use super::pep440_version::{PartsKey, Pep440Version};
+use super::version_part::{VersionPart, compare_components, hash_components, split_parts};
+
+/// A versioning scheme, such as conda or PEP 440.
+///
+/// Only `parse` has to be implemented.  By default, versions are ordered by their epoch, then by
+/// their public components and then by their local components, with missing components filled
+/// with `VersionPart::Empty` and components compared the way conda compares them.  The PEP 440
+/// and SemVer schemes implement `compare` and `hash` with their own ordering rules.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{SemVerScheme, Version};
+///
+/// let alpha = Version::<SemVerScheme>::new("1.0.0-alpha").unwrap();
+/// let release = Version::<SemVerScheme>::new("1.0.0").unwrap();
+///
+/// assert!(alpha < release);
+/// ```
+///
+/// Versions of different schemes can't be compared:
+///
+/// ```compile_fail
+/// use libronda::{Pep440Scheme, SemVerScheme, Version};
+///
+/// let pep440 = Version::<Pep440Scheme>::new("1.0.0").unwrap();
+/// let semver = Version::<SemVerScheme>::new("1.0.0").unwrap();
+///
+/// assert!(pep440 == semver);
+/// ```
+pub trait VersionScheme: Clone + fmt::Debug + Send + Sync + 'static {
+    /// Split the given version string in its version parts.
+    fn parse(version: &str) -> Result<Vec<VersionPart<'_>>, VersionParseError>;
+
+    /// Order the parts of two versions of this scheme.
+    fn compare(a: &[VersionPart], b: &[VersionPart]) -> Ordering {
+        let (epoch, public, local) = split_parts(a);
+        let (other_epoch, other_public, other_local) = split_parts(b);
+
+        // The epoch trumps everything, and the local version only breaks ties
+        epoch.cmp(&other_epoch)
+            .then_with(|| compare_components(public, other_public))
+            .then_with(|| compare_components(local, other_local))
+    }
+
+    /// Hash the parts of a version consistently with `compare`.
+    fn hash<H: Hasher>(parts: &[VersionPart], state: &mut H) {
+        let (epoch, public, local) = split_parts(parts);
+        epoch.hash(state);
+        hash_components(public, state);
+        hash_components(local, state);
+    }
+
+    /// Display a version of this scheme, given the string it was parsed from.
+    fn display(version: &str, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(version)
+    }
+}
+
+/// The conda versioning scheme, see `conda_parser`.  This is the default scheme of `Version`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CondaScheme;
+
+impl VersionScheme for CondaScheme {
+    fn parse(version: &str) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+        conda_parser(version)
+    }
+}
+
+/// A lexicographic versioning scheme, see `default_parser`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DefaultScheme;
+
+impl VersionScheme for DefaultScheme {
+    fn parse(version: &str) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+        default_parser(version)
+    }
+}
+
+/// The PEP 440 versioning scheme, see `pep440_parser`.
+///
+/// Versions are displayed in their normalized form.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{Pep440Scheme, Version};
+///
+/// let ver = Version::<Pep440Scheme>::new("1.0-RC.1").unwrap();
+///
+/// assert_eq!(ver.as_str(), "1.0-RC.1");
+/// assert_eq!(ver.to_string(), "1.0rc1");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Pep440Scheme;
+
+impl VersionScheme for Pep440Scheme {
+    fn parse(version: &str) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+        pep440_parser(version)
+    }
+
+    fn compare(a: &[VersionPart], b: &[VersionPart]) -> Ordering {
+        PartsKey::new(a).cmp(&PartsKey::new(b))
+    }
+
+    fn hash<H: Hasher>(parts: &[VersionPart], state: &mut H) {
+        PartsKey::new(parts).hash(state);
+    }
+
+    fn display(version: &str, f: &mut fmt::Formatter) -> fmt::Result {
+        match version.parse::<Pep440Version>() {
+            Ok(version) => write!(f, "{}", version),
+            Err(_) => f.write_str(version),
+        }
+    }
+}
+
+/// The SemVer 2.0 versioning scheme, see `semver_parser`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SemVerScheme;
+
+impl VersionScheme for SemVerScheme {
+    fn parse(version: &str) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+        semver_parser(version)
+    }
+
+    fn compare(a: &[VersionPart], b: &[VersionPart]) -> Ordering {
+        compare_semver(a, b)
+    }
+
+    fn hash<H: Hasher>(parts: &[VersionPart], state: &mut H) {
+        hash_semver(parts, state);
+    }
+}
+
+/// The Debian versioning scheme, see `debian_parser`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DebianScheme;
+
+impl VersionScheme for DebianScheme {
+    fn parse(version: &str) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+        debian_parser(version)
+    }
+}
+
+/// The RPM versioning scheme, see `rpm_parser`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RpmScheme;
+
+impl VersionScheme for RpmScheme {
+    fn parse(version: &str) -> Result<Vec<VersionPart<'_>>, VersionParseError> {
+        rpm_parser(version)
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::version::{Version, VersionBuf, VersionParseError};
+    use super::{CondaScheme, DebianScheme, DefaultScheme, Pep440Scheme, RpmScheme, SemVerScheme};
+
+    #[test]
+    fn conda_is_the_default() {
+        let a: Version<CondaScheme> = Version::new("1.0.dev1").unwrap();
+        assert_eq!(a, Version::from("1.0.dev1").unwrap());
+        // Unlike PEP 440, conda sorts a `.dev` component after a pre-release of the same version
+        assert!(a > Version::new("1.0a1").unwrap());
+    }
+
+    #[rstest_parametrize(a, b,
+    case("1.0.dev1", "1.0a1"),
+    case("1.0a1", "1.0"),
+    case("1.0", "1.0.post1"),
+    case("1.0+local", "1.0.post1"),
+    case("1.0", "1!0.1"),
+    )]
+    fn pep440_less_than(a: &str, b: &str) {
+        assert!(Version::<Pep440Scheme>::new(a).unwrap() < Version::<Pep440Scheme>::new(b).unwrap());
+    }
+
+    #[rstest_parametrize(a, b,
+    case("1.0.0-rc.1", "1.0.0"),
+    case("1.0.0-alpha.1", "1.0.0-alpha.beta"),
+    )]
+    fn semver_less_than(a: &str, b: &str) {
+        assert!(Version::<SemVerScheme>::new(a).unwrap() < Version::<SemVerScheme>::new(b).unwrap());
+    }
+
+    #[test]
+    fn other_schemes() {
+        assert!(Version::<DebianScheme>::new("1.0~rc1").unwrap() < Version::<DebianScheme>::new("1.0").unwrap());
+        assert!(Version::<RpmScheme>::new("1.0").unwrap() < Version::<RpmScheme>::new("1.0^git1").unwrap());
+        assert!(Version::<DefaultScheme>::new("1.2").unwrap() < Version::<DefaultScheme>::new("1.10").unwrap());
+    }
+
+    #[test]
+    fn scheme_parse_errors() {
+        assert_eq!(
+            Version::<SemVerScheme>::new("1.0").unwrap_err(),
+            VersionParseError::EmptyComponent { offset: 3 },
+        );
+        assert!(Version::<CondaScheme>::new("1.0").is_ok());
+    }
+
+    #[test]
+    fn pep440_display() {
+        let ver = Version::<Pep440Scheme>::new("v1.0-Alpha-1").unwrap();
+        assert_eq!(ver.as_str(), "v1.0-Alpha-1");
+        assert_eq!(ver.to_string(), "1.0a1");
+        assert_eq!(Version::<SemVerScheme>::new("1.0.0+build").unwrap().to_string(), "1.0.0+build");
+    }
+
+    #[test]
+    fn scheme_hash() {
+        let versions: HashSet<Version<Pep440Scheme>> = ["1.0", "1.0.0", "v1.0", "0!1.0"].iter()
+            .map(|v| Version::new(v).unwrap())
+            .collect();
+        assert_eq!(versions.len(), 1);
+    }
+
+    #[test]
+    fn scheme_version_buf() {
+        let buf: VersionBuf<SemVerScheme> = "1.0.0-rc.1".parse().unwrap();
+        assert!(buf < "1.0.0".parse::<VersionBuf<SemVerScheme>>().unwrap());
+        assert!(*buf < Version::<SemVerScheme>::new("1.0.0").unwrap());
+        assert!("1.0".parse::<VersionBuf<SemVerScheme>>().is_err());
+    }
+}
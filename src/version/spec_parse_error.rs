@@ -0,0 +1,124 @@
+//! Version spec parse error module.
+//!
+//! This module provides the `VersionSpecParseError` enum, which is returned when a version spec
+//! such as `>=1.2,<2` can't be parsed.  Like `VersionParseError`, every variant carries the byte
+//! offset into the spec string at which the problem was found.
+
+use std::error::Error;
+use std::fmt;
+
+use super::parse_error::VersionParseError;
+
+/// Error returned when a version spec string can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionSpecParseError {
+    /// A version in the spec is invalid.  The offset of the version error is relative to the
+    /// start of the spec string.
+    InvalidVersion(VersionParseError),
+
+    /// A constraint starts with an operator that is not one of `==`, `!=`, `<`, `<=`, `>`, `>=`,
+    /// `~=`, `=` or `===`.
+    InvalidOperator {
+        offset: usize,
+    },
+
+    /// A constraint is missing, such as the one between the commas in `>=1.2,,<2`.
+    EmptyConstraint {
+        offset: usize,
+    },
+
+    /// A parenthesis is not matched.
+    UnbalancedParenthesis {
+        offset: usize,
+    },
+
+    /// The compatible release operator `~=` is used with a glob, or with a version that has
+    /// fewer than two components.
+    InvalidCompatibleRelease {
+        offset: usize,
+    },
+}
+
+impl VersionSpecParseError {
+    /// Get the byte offset into the spec string at which the error was found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::VersionSpec;
+    ///
+    /// let err = ">=1.2,<x!2".parse::<VersionSpec>().unwrap_err();
+    ///
+    /// assert_eq!(err.offset(), 7);
+    /// ```
+    pub fn offset(&self) -> usize {
+        match *self {
+            VersionSpecParseError::InvalidVersion(ref err) => err.offset(),
+            VersionSpecParseError::InvalidOperator { offset } => offset,
+            VersionSpecParseError::EmptyConstraint { offset } => offset,
+            VersionSpecParseError::UnbalancedParenthesis { offset } => offset,
+            VersionSpecParseError::InvalidCompatibleRelease { offset } => offset,
+        }
+    }
+
+    /// Shift the offset of this error by `base` bytes, for errors found in a substring that
+    /// starts `base` bytes into the spec string.
+    pub(crate) fn shifted(self, base: usize) -> Self {
+        match self {
+            VersionSpecParseError::InvalidVersion(err) => VersionSpecParseError::InvalidVersion(err.shifted(base)),
+            VersionSpecParseError::InvalidOperator { offset } =>
+                VersionSpecParseError::InvalidOperator { offset: offset + base },
+            VersionSpecParseError::EmptyConstraint { offset } =>
+                VersionSpecParseError::EmptyConstraint { offset: offset + base },
+            VersionSpecParseError::UnbalancedParenthesis { offset } =>
+                VersionSpecParseError::UnbalancedParenthesis { offset: offset + base },
+            VersionSpecParseError::InvalidCompatibleRelease { offset } =>
+                VersionSpecParseError::InvalidCompatibleRelease { offset: offset + base },
+        }
+    }
+}
+
+impl fmt::Display for VersionSpecParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VersionSpecParseError::InvalidVersion(ref err) =>
+                write!(f, "invalid version: {}", err),
+            VersionSpecParseError::InvalidOperator { offset } =>
+                write!(f, "invalid operator (at offset {})", offset),
+            VersionSpecParseError::EmptyConstraint { offset } =>
+                write!(f, "empty version constraint (at offset {})", offset),
+            VersionSpecParseError::UnbalancedParenthesis { offset } =>
+                write!(f, "unbalanced parenthesis (at offset {})", offset),
+            VersionSpecParseError::InvalidCompatibleRelease { offset } =>
+                write!(f, "'~=' needs a version with at least two components (at offset {})", offset),
+        }
+    }
+}
+
+impl Error for VersionSpecParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            VersionSpecParseError::InvalidVersion(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use super::VersionSpecParseError;
+    use crate::version::VersionParseError;
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            format!("{}", VersionSpecParseError::InvalidVersion(VersionParseError::BadEpoch { offset: 4 })),
+            "invalid version: epoch must be an integer (at offset 4)",
+        );
+        assert_eq!(
+            format!("{}", VersionSpecParseError::UnbalancedParenthesis { offset: 0 }),
+            "unbalanced parenthesis (at offset 0)",
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Interval representation of version specs, used for set operations on `VersionSpec`.
+//!
+//! A spec is turned into a sorted list of disjoint intervals over the version order.  Interval
+//! ends are cuts between versions: just below or above a version, or just below or above all
+//! versions that start with a prefix, which is how `1.2.*` is represented.  The versions starting
+//! with a prefix form a contiguous range of the version order, so a version that doesn't start
+//! with the prefix is either below or above all of them.
+//!
+//! The one exception are versions with fewer components than a prefix ending with zeros: `2` is
+//! equal to `2.0`, but doesn't start with it.  So the intervals are kept per number of public
+//! components, and `2.0.*` only covers versions with at least two of them.
+
+use std::cmp::Ordering;
+
+use super::comp_op::CompOp;
+use super::version::Version;
+use super::version_buf::VersionBuf;
+use super::version_part::{VersionPart, split_parts};
+use super::version_spec::{VersionSpec, flatten};
+
+/// A cut between two adjacent sets of versions.
+#[derive(Clone, Debug)]
+pub(crate) enum Cut {
+    NegInf,
+    /// Below all versions that start with the prefix.
+    BelowPrefix(VersionBuf),
+    /// Below the version, and all versions equal to it.
+    Below(VersionBuf),
+    /// Above the version, and all versions equal to it.
+    Above(VersionBuf),
+    /// Above all versions that start with the prefix.
+    AbovePrefix(VersionBuf),
+    PosInf,
+}
+
+/// Whether `version` is in the range of versions starting with `prefix`, which also holds the
+/// versions equal to `prefix` that have fewer components.
+fn in_prefix(version: &Version, prefix: &Version) -> bool {
+    version.starts_with(prefix) || version == prefix
+}
+
+/// Whether two prefixes are matched by the same versions, such as `1.0` and `1.00`.  Unlike
+/// `1` and `1.0`, which are equal versions, but `1.1` only starts with the first.
+fn same_prefix(a: &Version, b: &Version) -> bool {
+    a.starts_with(b) && b.starts_with(a)
+}
+
+/// Get the number of public components a version needs to start with `prefix`, when shorter
+/// versions can be equal to it because it ends with a zero, such as `2` for `2.0`.
+fn prefix_len(prefix: &Version) -> Option<usize> {
+    let (_, public, _) = split_parts(prefix.parts());
+    match public.last() {
+        Some(last) if public.len() > 1 && *last == VersionPart::Empty => Some(public.len()),
+        _ => None,
+    }
+}
+
+impl Cut {
+    fn is_prefix(&self) -> bool {
+        matches!(self, Cut::BelowPrefix(_) | Cut::AbovePrefix(_))
+    }
+
+    fn anchor(&self) -> Option<&VersionBuf> {
+        match self {
+            Cut::BelowPrefix(v) | Cut::Below(v) | Cut::Above(v) | Cut::AbovePrefix(v) => Some(v),
+            Cut::NegInf | Cut::PosInf => None,
+        }
+    }
+
+    /// Order of the cuts around the same anchor.
+    fn rank(&self) -> u8 {
+        match self {
+            Cut::NegInf => 0,
+            Cut::BelowPrefix(_) => 1,
+            Cut::Below(_) => 2,
+            Cut::Above(_) => 3,
+            Cut::AbovePrefix(_) => 4,
+            Cut::PosInf => 5,
+        }
+    }
+
+    /// Whether this cut is within the range of versions starting with `prefix`.
+    fn in_prefix(&self, prefix: &Version) -> bool {
+        match self {
+            Cut::BelowPrefix(p) | Cut::AbovePrefix(p) => p.starts_with(prefix),
+            Cut::Below(v) | Cut::Above(v) => in_prefix(v, prefix),
+            Cut::NegInf | Cut::PosInf => false,
+        }
+    }
+
+    /// Order this prefix cut against a cut anchored at `other`, which is not a prefix of it.
+    fn cmp_prefix(&self, prefix: &Version, other: &Version) -> Ordering {
+        if in_prefix(other, prefix) {
+            // The cut is below or above the whole prefix range, which includes `other`
+            self.rank().cmp(&2)
+        } else {
+            prefix.cmp(other)
+        }
+    }
+}
+
+impl PartialEq for Cut {
+    fn eq(&self, other: &Cut) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Cut {}
+
+impl PartialOrd for Cut {
+    fn partial_cmp(&self, other: &Cut) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cut {
+    fn cmp(&self, other: &Cut) -> Ordering {
+        let (a, b) = match (self.anchor(), other.anchor()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return self.rank().cmp(&other.rank()),
+        };
+        match (self.is_prefix(), other.is_prefix()) {
+            (false, false) => a.cmp(b).then(self.rank().cmp(&other.rank())),
+            (true, false) => self.cmp_prefix(a, b),
+            (false, true) => other.cmp_prefix(b, a).reverse(),
+            // Prefixes are compared by their components, as `1.0.*` is nested in `1.*`
+            (true, true) => match (a.starts_with(b), b.starts_with(a)) {
+                (true, true) => self.rank().cmp(&other.rank()),
+                // The range of `a` is nested in the range of `b`
+                (true, false) => if other.rank() < 2 { Ordering::Greater } else { Ordering::Less },
+                (false, true) => if self.rank() < 2 { Ordering::Less } else { Ordering::Greater },
+                (false, false) => a.cmp(b).then(self.rank().cmp(&other.rank())),
+            },
+        }
+    }
+}
+
+/// The versions between two cuts.
+#[derive(Clone, Debug)]
+pub(crate) struct Interval {
+    lower: Cut,
+    upper: Cut,
+}
+
+impl Interval {
+    fn new(lower: Cut, upper: Cut) -> Interval {
+        Interval { lower, upper }
+    }
+
+    fn all() -> Interval {
+        Interval::new(Cut::NegInf, Cut::PosInf)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lower >= self.upper
+    }
+
+    /// The fewest public components of the versions in this interval, when they are all equal.
+    fn min_len(&self) -> Option<usize> {
+        match (&self.lower, &self.upper) {
+            (Cut::Below(a), Cut::Above(b)) if a == b => {
+                let (_, public, _) = split_parts(a.parts());
+                Some(public.iter().rposition(|part| *part != VersionPart::Empty).map_or(1, |i| i + 1))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// A sorted list of disjoint, non-empty intervals.
+#[derive(Clone, Debug)]
+struct Intervals(Vec<Interval>);
+
+impl Intervals {
+    fn new(mut intervals: Vec<Interval>) -> Intervals {
+        intervals.retain(|interval| !interval.is_empty());
+        intervals.sort_by(|a, b| a.lower.cmp(&b.lower));
+
+        // Merge overlapping and touching intervals
+        let mut merged: Vec<Interval> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match merged.last_mut() {
+                Some(last) if interval.lower <= last.upper => {
+                    last.upper = last.upper.clone().max(interval.upper);
+                },
+                _ => merged.push(interval),
+            }
+        }
+        Intervals(merged)
+    }
+
+    fn intersect(&self, other: &Intervals) -> Intervals {
+        let mut intervals = Vec::new();
+        for a in &self.0 {
+            for b in &other.0 {
+                intervals.push(Interval::new(a.lower.clone().max(b.lower.clone()), a.upper.clone().min(b.upper.clone())));
+            }
+        }
+        Intervals::new(intervals)
+    }
+
+    fn union(&self, other: &Intervals) -> Intervals {
+        Intervals::new(self.0.iter().chain(&other.0).cloned().collect())
+    }
+
+    /// Whether both lists have the same intervals.
+    fn same_as(&self, other: &Intervals) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().zip(&other.0).all(|(a, b)| a.lower == b.lower && a.upper == b.upper)
+    }
+
+    /// Drop the intervals of versions that all have at least `end` public components.
+    fn shorter_than(mut self, end: Option<usize>) -> Intervals {
+        if let Some(end) = end {
+            self.0.retain(|interval| interval.min_len().is_none_or(|len| len < end));
+        }
+        self
+    }
+
+    /// Convert back into a spec, or `None` if there are no intervals.
+    fn to_spec(&self) -> Option<VersionSpec> {
+        let (first, last) = (self.0.first()?, self.0.last()?);
+
+        // Gaps of a single version or a single prefix become `!=` constraints on the whole range
+        let mut holes = Vec::new();
+        for pair in self.0.windows(2) {
+            match (&pair[0].upper, &pair[1].lower) {
+                (Cut::Below(a), Cut::Above(b)) if a == b => holes.push(VersionSpec::Compare(CompOp::Ne, a.clone())),
+                (Cut::BelowPrefix(a), Cut::AbovePrefix(b)) if same_prefix(a, b) =>
+                    holes.push(VersionSpec::NotStartsWith(a.clone())),
+                _ => break,
+            }
+        }
+        if holes.len() + 1 == self.0.len() {
+            let mut specs = interval_specs(&first.lower, &last.upper);
+            if specs == [VersionSpec::Any] && !holes.is_empty() {
+                specs.clear();
+            }
+            specs.extend(holes);
+            return Some(flatten(specs, VersionSpec::All));
+        }
+
+        let specs = self.0.iter()
+            .map(|interval| flatten(interval_specs(&interval.lower, &interval.upper), VersionSpec::All))
+            .collect();
+        Some(flatten(specs, VersionSpec::AnyOf))
+    }
+}
+
+/// The intervals of a spec, for each number of public components of the versions.
+#[derive(Clone, Debug)]
+pub(crate) struct Ranges {
+    /// The intervals of the versions with at least the given number of public components, and
+    /// fewer than the number of the next level.  The first level starts at zero.
+    levels: Vec<(usize, Intervals)>,
+}
+
+impl Ranges {
+    fn flat(intervals: Vec<Interval>) -> Ranges {
+        Ranges { levels: vec![(0, Intervals::new(intervals))] }
+    }
+
+    /// Build ranges from their levels, dropping the versions that need more components than a
+    /// level has, and merging levels that end up with the same intervals.
+    fn from_levels(levels: Vec<(usize, Intervals)>) -> Ranges {
+        let ends: Vec<Option<usize>> = levels.iter().skip(1).map(|&(start, _)| Some(start)).chain(Some(None)).collect();
+        let mut levels: Vec<(usize, Intervals)> = levels.into_iter().zip(ends)
+            .map(|((start, intervals), end)| (start, intervals.shorter_than(end)))
+            .collect();
+        levels.dedup_by(|(_, intervals), (_, previous)| intervals.same_as(previous));
+        Ranges { levels }
+    }
+
+    /// Build the ranges of a spec on versions starting with `prefix`, given the `intervals` of
+    /// the versions long enough to start with it, and the intervals of `shorter` versions.
+    /// Returns `None` for a prefix with a local version.
+    fn prefixed(prefix: &Version, shorter: Vec<Interval>, intervals: Vec<Interval>) -> Option<Ranges> {
+        let (_, _, local) = split_parts(prefix.parts());
+        if !local.is_empty() {
+            return None;
+        }
+        Some(match prefix_len(prefix) {
+            Some(len) => Ranges::from_levels(vec![(0, Intervals::new(shorter)), (len, Intervals::new(intervals))]),
+            None => Ranges::flat(intervals),
+        })
+    }
+
+    /// Build the ranges of a spec, or `None` if the spec contains constraints that can't be
+    /// represented as intervals, such as globs.
+    pub(crate) fn from_spec(spec: &VersionSpec) -> Option<Ranges> {
+        Some(match spec {
+            VersionSpec::Any => Ranges::flat(vec![Interval::all()]),
+            VersionSpec::Compare(operator, v) => Ranges::flat(match operator {
+                CompOp::Eq => vec![Interval::new(Cut::Below(v.clone()), Cut::Above(v.clone()))],
+                CompOp::Ne => vec![
+                    Interval::new(Cut::NegInf, Cut::Below(v.clone())),
+                    Interval::new(Cut::Above(v.clone()), Cut::PosInf),
+                ],
+                CompOp::Lt => vec![Interval::new(Cut::NegInf, Cut::Below(v.clone()))],
+                CompOp::Le => vec![Interval::new(Cut::NegInf, Cut::Above(v.clone()))],
+                CompOp::Gt => vec![Interval::new(Cut::Above(v.clone()), Cut::PosInf)],
+                CompOp::Ge => vec![Interval::new(Cut::Below(v.clone()), Cut::PosInf)],
+            }),
+            VersionSpec::StartsWith(p) => Ranges::prefixed(p, Vec::new(), vec![
+                Interval::new(Cut::BelowPrefix(p.clone()), Cut::AbovePrefix(p.clone())),
+            ])?,
+            VersionSpec::NotStartsWith(p) => Ranges::prefixed(p, vec![Interval::all()], vec![
+                Interval::new(Cut::NegInf, Cut::BelowPrefix(p.clone())),
+                Interval::new(Cut::AbovePrefix(p.clone()), Cut::PosInf),
+            ])?,
+            VersionSpec::Compatible(v) => {
+                let prefix = spec.compatible_prefix()?;
+                Ranges::prefixed(&prefix, Vec::new(), vec![Interval::new(Cut::Below(v.clone()), Cut::AbovePrefix(prefix.clone()))])?
+            },
+            VersionSpec::Exact(_) | VersionSpec::Glob(_) => return None,
+            VersionSpec::All(specs) => {
+                let mut ranges = Ranges::flat(vec![Interval::all()]);
+                for spec in specs {
+                    ranges = ranges.intersect(&Ranges::from_spec(spec)?);
+                }
+                ranges
+            },
+            VersionSpec::AnyOf(specs) => {
+                let mut ranges = Ranges::flat(Vec::new());
+                for spec in specs {
+                    ranges = ranges.union(&Ranges::from_spec(spec)?);
+                }
+                ranges
+            },
+        })
+    }
+
+    /// Get the intervals of the versions with `len` public components.
+    fn level(&self, len: usize) -> &Intervals {
+        let i = self.levels.partition_point(|&(start, _)| start <= len);
+        &self.levels[i - 1].1
+    }
+
+    /// Get the starts of the levels of both ranges.
+    fn starts(&self, other: &Ranges) -> Vec<usize> {
+        let mut starts: Vec<usize> = self.levels.iter().chain(&other.levels).map(|&(start, _)| start).collect();
+        starts.sort_unstable();
+        starts.dedup();
+        starts
+    }
+
+    /// Combine the intervals of both ranges level by level.
+    fn combine(&self, other: &Ranges, combine: impl Fn(&Intervals, &Intervals) -> Intervals) -> Ranges {
+        Ranges::from_levels(self.starts(other).into_iter()
+            .map(|start| (start, combine(self.level(start), other.level(start))))
+            .collect())
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.levels.iter().all(|(_, intervals)| intervals.0.is_empty())
+    }
+
+    pub(crate) fn intersect(&self, other: &Ranges) -> Ranges {
+        self.combine(other, Intervals::intersect)
+    }
+
+    fn union(&self, other: &Ranges) -> Ranges {
+        self.combine(other, Intervals::union)
+    }
+
+    /// Whether both ranges cover the same versions.
+    pub(crate) fn same_as(&self, other: &Ranges) -> bool {
+        let starts = self.starts(other);
+        starts.iter().enumerate().all(|(i, &start)| {
+            let end = starts.get(i + 1).copied();
+            self.level(start).clone().shorter_than(end).same_as(&other.level(start).clone().shorter_than(end))
+        })
+    }
+
+    /// Convert back into a spec.  Returns `None` if no version is in the ranges, or if the
+    /// ranges differ by number of components in a way no spec can be written for, such as for
+    /// `2.0.*,==2.0` which doesn't hold `2`.
+    pub(crate) fn to_spec(&self) -> Option<VersionSpec> {
+        // The spec written from one level is only right if it gives back the same ranges
+        self.levels.iter().rev()
+            .filter_map(|(_, intervals)| intervals.to_spec())
+            .find(|spec| Ranges::from_spec(spec).is_some_and(|ranges| ranges.same_as(self)))
+    }
+}
+
+/// The constraints describing the versions between `lower` and `upper`.
+fn interval_specs(lower: &Cut, upper: &Cut) -> Vec<VersionSpec> {
+    match (lower, upper) {
+        (Cut::NegInf, Cut::PosInf) => return vec![VersionSpec::Any],
+        (Cut::Below(a), Cut::Above(b)) if a == b => return vec![VersionSpec::Compare(CompOp::Eq, a.clone())],
+        (Cut::BelowPrefix(a), Cut::AbovePrefix(b)) if same_prefix(a, b) =>
+            return vec![VersionSpec::StartsWith(a.clone())],
+        _ => {},
+    }
+
+    let mut specs = Vec::with_capacity(2);
+    match lower {
+        Cut::Below(v) => specs.push(VersionSpec::Compare(CompOp::Ge, v.clone())),
+        Cut::Above(v) => specs.push(VersionSpec::Compare(CompOp::Gt, v.clone())),
+        Cut::AbovePrefix(p) => {
+            specs.push(VersionSpec::Compare(CompOp::Gt, p.clone()));
+            specs.push(VersionSpec::NotStartsWith(p.clone()));
+        },
+        // When the upper end is within the prefix range, the prefix alone gives the lower end
+        Cut::BelowPrefix(p) if upper.in_prefix(p) => specs.push(VersionSpec::StartsWith(p.clone())),
+        Cut::BelowPrefix(p) => specs.push(VersionSpec::AnyOf(vec![
+            VersionSpec::StartsWith(p.clone()),
+            VersionSpec::Compare(CompOp::Ge, p.clone()),
+        ])),
+        Cut::NegInf | Cut::PosInf => {},
+    }
+    match upper {
+        Cut::Below(v) => specs.push(VersionSpec::Compare(CompOp::Lt, v.clone())),
+        Cut::Above(v) => specs.push(VersionSpec::Compare(CompOp::Le, v.clone())),
+        Cut::BelowPrefix(p) => {
+            specs.push(VersionSpec::Compare(CompOp::Lt, p.clone()));
+            specs.push(VersionSpec::NotStartsWith(p.clone()));
+        },
+        Cut::AbovePrefix(p) if lower.in_prefix(p) => specs.push(VersionSpec::StartsWith(p.clone())),
+        Cut::AbovePrefix(p) => specs.push(VersionSpec::AnyOf(vec![
+            VersionSpec::StartsWith(p.clone()),
+            VersionSpec::Compare(CompOp::Le, p.clone()),
+        ])),
+        Cut::NegInf | Cut::PosInf => {},
+    }
+    specs
+}
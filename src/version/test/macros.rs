@@ -1,7 +1,7 @@
 // some helpful discussion on how this works is at https://github.com/la10736/rstest/issues/66
 
-/// Paste identifiers within a macro invocation that expands to one or more
-/// macro_rules macros or items containing macros.
+// Paste identifiers within a macro invocation that expands to one or more
+// macro_rules macros or items containing macros.
 
 macro_rules! parametrize_versions {
         ( $test:ident ) => {
@@ -15,9 +15,6 @@ macro_rules! parametrize_versions {
             case("0.0.0", 3),
             case("1.0.0", 3),
             case("0.0.1", 3),
-            case("", 0),
-            case(".", 0),
-            case("...", 0),
             case("1.2.dev", 3),
             case("1.2-dev", 3),
             case("1.2.alpha.4", 4),
@@ -38,11 +35,18 @@ macro_rules! parametrize_versions_errors {
     ( $test:ident ) => {
         paste::item! {
             #[rstest_parametrize(v_string, n_parts,
-            case("abc", 1),
-            case("alpha.dev.snapshot", 3),
             case("test. .snapshot", 3),
-            // TODO: broken case, decide what to do here
-            // case("$", 1),
+            case("$", 1),
+            case("x!1.0", 2),
+            case("1!2!3", 2),
+            case("1.0+a+b", 4),
+            case("1.99999999999999999999a", 2),
+            case(".", 0),
+            case("...", 0),
+            case("1.0+", 2),
+            case("1.0_1-2", 3),
+            // conda rejects blank versions
+            case("", 0),
             )]
             fn [< _ $test >] (v_string: &str, n_parts: usize) {
                 $test(v_string, n_parts)
@@ -62,12 +66,7 @@ macro_rules! parametrize_versions_set {
             case::equal_zeros("0", "0", &CompOp::Eq),
             case::zero_more_zeros_left("0.0.0", "0", &CompOp::Eq),
             case::zero_more_zeros_left("0", "0.0.0", &CompOp::Eq),
-            case::blank_eq_blank("", "", &CompOp::Eq),
-            // Not true for conda - empty string is its own special type that is lowest priority
-            // case::blank_eq_zero("", "0.0", &CompOp::Eq),
-            // case::zero_eq_blank("0.0", "", &CompOp::Eq),
-            case::blank_lt_0_1("", "0.1", &CompOp::Lt),
-            case::_0_1_gt_blank("0.1", "", &CompOp::Gt),
+            // Blank versions are invalid for conda, so they can't be compared
             case::bugfix_increment("1.2.3", "1.2.4", &CompOp::Lt),
             case::fourth_place_greater("1.0.0.1", "1.0.0.0", &CompOp::Gt),
             case::fourth_place_lower("1.0.0.0", "1.0.0.1", &CompOp::Lt),
@@ -79,12 +78,11 @@ macro_rules! parametrize_versions_set {
             case::dev_version_gtr_than_previous_release("1.1.2", "1.1.30-dev", &CompOp::Lt),
             case::alpha_version_lower_than_same_release("1.2.3", "1.2.3.alpha", &CompOp::Gt),
             case::dev_version_lower_than_same_release("1.2.3", "1.2.3-dev", &CompOp::Gt),
-            // lexicographic sorting makes dev higher than alpha.  Other version part implementations
-            //    may change this in their comparison implementations.
-            case::dev_version_gtr_than_alpha_dots("1.2.3.dev", "1.2.3.alpha", &CompOp::Gt),
-            case::dev_version_gtr_than_alpha_dashes("1.2.3-dev", "1.2.3-alpha", &CompOp::Gt),
-            case::dev_version_gtr_than_alpha_dots_post_dev("1.2.3.dev.1", "1.2.3.alpha", &CompOp::Gt),
-            case::dev_version_gtr_than_alpha_dashes_post_dev("1.2.3-dev-1", "1.2.3-alpha", &CompOp::Gt),
+            // conda sorts dev before all other strings, so it is lower than alpha
+            case::dev_version_lt_alpha_dots("1.2.3.dev", "1.2.3.alpha", &CompOp::Lt),
+            case::dev_version_lt_alpha_dashes("1.2.3-dev", "1.2.3-alpha", &CompOp::Lt),
+            case::dev_version_lt_alpha_dots_post_dev("1.2.3.dev.1", "1.2.3.alpha", &CompOp::Lt),
+            case::dev_version_lt_alpha_dashes_post_dev("1.2.3-dev-1", "1.2.3-alpha", &CompOp::Lt),
             // case::full_string_compared_with_short("version-compare 3.2.0 / build 0932", "3.2.5", &CompOp::Lt),
             // case::full_string_compared_with_short_gtr("version-compare 3.2.0 / build 0932", "3.1.1", &CompOp::Gt),
 //            case::full_string_eq(
@@ -5,14 +5,16 @@
 //! is made. This struct provides many methods and features for easy comparison, probing and other
 //! things.
 
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt;
-use std::iter::Peekable;
-use std::slice::Iter;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 
 use super::comp_op::CompOp;
-use super::version_part::{VersionPart, ProvideEmptyImpl};
-use super::parsers::conda::conda_parser;
+use super::parse_error::VersionParseError;
+use super::scheme::{CondaScheme, VersionScheme};
+use super::version_part::{VersionPart, compare_components, split_parts, starts_with_components};
 
 /// Version struct, which is a representation for a parsed version string.
 ///
@@ -24,33 +26,44 @@ use super::parsers::conda::conda_parser;
 /// representation, the returned value is generated.
 ///
 /// The struct provides many methods for comparison and probing.
-pub struct Version<'a> {
-    version: &'a str,
+///
+/// A `Version` borrows from the string it was parsed from.  Use `into_owned` or `VersionBuf` when
+/// the version has to outlive that string.
+///
+/// The `S` type parameter is the `VersionScheme` used to parse, order and display the version,
+/// which defaults to conda.  Versions of different schemes can't be compared with each other.
+#[derive(Clone)]
+pub struct Version<'a, S: VersionScheme = CondaScheme> {
+    version: Cow<'a, str>,
     parts: Vec<VersionPart<'a>>,
+    scheme: PhantomData<S>,
 }
 
 impl<'a> Version<'a> {
     /// Create a `Version` instance from a version string.
     ///
-    /// The version string should be passed to the `version` parameter.
+    /// The version string should be passed to the `version` parameter.  An error describing
+    /// where and why parsing failed is returned if the version string is invalid.
     ///
     /// # Examples
     ///
     /// ```
-    /// use libronda::{CompOp, Version};
+    /// use libronda::{CompOp, Version, VersionParseError};
     ///
     /// let ver = Version::from("1.2.3").unwrap();
     ///
     /// assert_eq!(ver.compare(&Version::from("1.2.3").unwrap()), CompOp::Eq);
+    /// assert_eq!(Version::from("x!1.0").unwrap_err(), VersionParseError::BadEpoch { offset: 0 });
     /// ```
-    pub fn from(version: &'a str) -> Option<Version> {
-        Version::parse(version, &conda_parser)
+    pub fn from(version: &'a str) -> Result<Version<'a>, VersionParseError> {
+        Version::new(version)
     }
 
     /// Create a `Version` instance from a version string with the given `parser` function.
     ///
     /// The version string should be passed to the `version` parameter.  Additional parsers
-    /// are in the "parsers" module.  This is the primary means of customizing behavior.
+    /// are in the "parsers" module.  Whatever the parser, the version is ordered like a conda
+    /// version, which orders the parts of the other parsers wrongly.
     ///
     /// # Examples
     ///
@@ -61,19 +74,66 @@ impl<'a> Version<'a> {
     ///
     /// assert_eq!(ver.compare(&Version::from("1.2.3").unwrap()), CompOp::Eq);
     /// ```
-    pub fn parse(version: &'a str, parser: &dyn Fn(&'a str) -> Option<Vec<VersionPart<'a>>>) -> Option<Self> {
-        let parts: Option<Vec<VersionPart<'a>>> = parser(version);
-
-        if parts.is_none() {
-            return None;
-        }
+    #[deprecated(note = "the parser doesn't change how the version is ordered, use `Version::new` with a `VersionScheme`")]
+    pub fn parse(
+        version: &'a str,
+        parser: &dyn Fn(&'a str) -> Result<Vec<VersionPart<'a>>, VersionParseError>,
+    ) -> Result<Self, VersionParseError> {
+        let parts: Vec<VersionPart<'a>> = parser(version)?;
+
+        Ok(Self {
+            version: Cow::Borrowed(version),
+            parts,
+            scheme: PhantomData,
+        })
+    }
+}
 
-        Some(Self {
-            version,
-            parts: parts.unwrap(),
+impl<'a, S: VersionScheme> Version<'a, S> {
+    /// Create a `Version` instance from a version string, parsed by the version scheme `S`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{Pep440Scheme, Version};
+    ///
+    /// let ver = Version::<Pep440Scheme>::new("1.0.post1").unwrap();
+    ///
+    /// assert!(ver > Version::new("1.0").unwrap());
+    /// assert!(Version::<Pep440Scheme>::new("1.0-").is_err());
+    /// ```
+    pub fn new(version: &'a str) -> Result<Self, VersionParseError> {
+        Ok(Self {
+            version: Cow::Borrowed(version),
+            parts: S::parse(version)?,
+            scheme: PhantomData,
         })
     }
 
+    /// Convert this version into one that owns its version string and all of its parts, so that
+    /// it no longer borrows from the string it was parsed from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{CompOp, Version};
+    ///
+    /// let owned: Version<'static> = {
+    ///     let text = String::from("1.2.3");
+    ///     Version::from(&text).unwrap().into_owned()
+    /// };
+    ///
+    /// assert_eq!(owned.as_str(), "1.2.3");
+    /// assert_eq!(owned.compare(&Version::from("1.2.3").unwrap()), CompOp::Eq);
+    /// ```
+    pub fn into_owned(self) -> Version<'static, S> {
+        Version {
+            version: Cow::Owned(self.version.into_owned()),
+            parts: self.parts.into_iter().map(VersionPart::into_owned).collect(),
+            scheme: PhantomData,
+        }
+    }
+
     /// Get the original version string.
     ///
     /// # Examples
@@ -103,6 +163,7 @@ impl<'a> Version<'a> {
     /// assert_eq!(ver.part(1), Ok(&VersionPart::Integer(2)));
     /// assert_eq!(ver.part(2), Ok(&VersionPart::Integer(3)));
     /// ```
+    #[allow(clippy::result_unit_err)]
     pub fn part(&self, index: usize) -> Result<&VersionPart<'a>, ()> {
         // Make sure the index is in-bound
         if index >= self.parts.len() {
@@ -128,7 +189,7 @@ impl<'a> Version<'a> {
     ///     VersionPart::Integer(3)
     /// ]);
     /// ```
-    pub fn parts(&self) -> &Vec<VersionPart> {
+    pub fn parts(&self) -> &Vec<VersionPart<'a>> {
         &self.parts
     }
 
@@ -169,9 +230,8 @@ impl<'a> Version<'a> {
     /// assert_eq!(Version::from("0.3.0.0").unwrap().compare(&Version::from("0.3").unwrap()), CompOp::Eq);
     /// assert_eq!(Version::from("2").unwrap().compare(&Version::from("1.7.3").unwrap()), CompOp::Gt);
     /// ```
-    pub fn compare(&self, other: &'a Version) -> CompOp {
-        // Compare the versions with their peekable iterators
-        Self::compare_iter(self.parts.iter().peekable(), other.parts.iter().peekable())
+    pub fn compare(&self, other: &Version<'_, S>) -> CompOp {
+        CompOp::from_ord(self.ordering(other))
     }
 
     /// Compare this version to the given `other` version,
@@ -189,93 +249,105 @@ impl<'a> Version<'a> {
     /// assert!(Version::from("1.2").unwrap().compare_to(&Version::from("1.2").unwrap(), &CompOp::Eq));
     /// assert!(Version::from("1.2").unwrap().compare_to(&Version::from("1.2").unwrap(), &CompOp::Le));
     /// ```
-    pub fn compare_to(&self, other: &Version, operator: &CompOp) -> bool {
+    pub fn compare_to(&self, other: &Version<'_, S>, operator: &CompOp) -> bool {
         // Get the comparison result
-        let result = self.compare(&other);
+        let result = self.compare(other);
 
         // Match the result against the given operator
         match result {
-            CompOp::Eq => match operator {
-                &CompOp::Eq | &CompOp::Le | &CompOp::Ge => true,
-                _ => false,
-            },
-            CompOp::Lt => match operator {
-                &CompOp::Ne | &CompOp::Lt | &CompOp::Le => true,
-                _ => false,
-            },
-            CompOp::Gt => match operator {
-                &CompOp::Ne | &CompOp::Gt | &CompOp::Ge => true,
-                _ => false,
-            },
+            CompOp::Eq => matches!(operator, CompOp::Eq | CompOp::Le | CompOp::Ge),
+            CompOp::Lt => matches!(operator, CompOp::Ne | CompOp::Lt | CompOp::Le),
+            CompOp::Gt => matches!(operator, CompOp::Ne | CompOp::Gt | CompOp::Ge),
             _ => unreachable!(),
         }
     }
 
-    /// Compare two version numbers based on the iterators of their version parts.
+    /// Check whether this version starts with the given `prefix` version, which is how conda
+    /// matches specs like `1.2.*` and `=1.2`.
     ///
-    /// This method returns one of the following comparison operators:
+    /// All components of the prefix but the last must be equal to those of this version, and the
+    /// last one must match the corresponding component of this version.  A string at the very
+    /// end of the prefix only needs to be a prefix of the string in this version.
     ///
-    /// * `Lt`
-    /// * `Eq`
-    /// * `Gt`
+    /// # Examples
     ///
-    /// Other comparison operators can be used when comparing, but aren't returned by this method.
-    fn compare_iter(
-        mut iter: Peekable<Iter<VersionPart>>,
-        mut other_iter: Peekable<Iter<VersionPart>>,
-    ) -> CompOp {
-        // Iterate over the iterator, without consuming it
-        let _last_i1: &VersionPart = {
-            &iter.peek().unwrap()
-        };
-        let _last_i2: &VersionPart = {
-            &other_iter.peek().unwrap()
+    /// ```
+    /// use libronda::Version;
+    ///
+    /// let prefix = Version::from("1.2").unwrap();
+    ///
+    /// assert!(Version::from("1.2").unwrap().starts_with(&prefix));
+    /// assert!(Version::from("1.2.3").unwrap().starts_with(&prefix));
+    /// assert!(!Version::from("1.20").unwrap().starts_with(&prefix));
+    /// assert!(Version::from("1.2rc1").unwrap().starts_with(&Version::from("1.2r").unwrap()));
+    /// ```
+    pub fn starts_with(&self, prefix: &Version<'_, S>) -> bool {
+        let (epoch, public, local) = self.split_parts();
+        let (prefix_epoch, prefix_public, prefix_local) = prefix.split_parts();
+
+        if epoch != prefix_epoch {
+            return false;
+        }
+        if prefix_local.is_empty() {
+            return starts_with_components(public, prefix_public);
+        }
+        compare_components(public, prefix_public) == Ordering::Equal
+            && starts_with_components(local, prefix_local)
+    }
+
+    /// Order this version against the given `other` version, which may borrow from a string with
+    /// a different lifetime.
+    fn ordering(&self, other: &Version<'_, S>) -> Ordering {
+        S::compare(&self.parts, &other.parts)
+    }
+
+    /// Split the version string into its epoch, public version and local version, without the
+    /// `!` and `+` separators.
+    pub(crate) fn split_str(&self) -> (Option<&str>, &str, Option<&str>) {
+        let version = self.version.trim();
+        let (epoch, rest) = match version.split_once('!') {
+            Some((epoch, rest)) => (Some(epoch), rest),
+            None => (None, version),
         };
-        loop {
-            let i1 = &iter.next();
-            let i2 = &other_iter.next();
-            // println!("Comparing: {} to {}",
-            //          match i1 {Some(i)=> i, _ => &VersionPart::Empty},
-            //          match i2 {Some(i)=> i, _ => &VersionPart::Empty});
-            // println!("Prospective empties are: {} and {}", &_last_i1.get_empty(), &_last_i2.get_empty());
-            let _cmp = match (i1, i2) {
-                (Some(i), None) => match i.partial_cmp(&&_last_i2.get_empty()) {
-                    Some(Ordering::Less) => return CompOp::Lt,
-                    Some(Ordering::Greater) => return CompOp::Gt,
-                    Some(Ordering::Equal) => return CompOp::Eq,
-                    _ => panic!()
-                },
-                (None, Some(j)) => match &_last_i1.get_empty().partial_cmp(j) {
-                    Some(Ordering::Less) => return CompOp::Lt,
-                    Some(Ordering::Greater) => return CompOp::Gt,
-                    Some(Ordering::Equal) => return CompOp::Eq,
-                    _ => panic!()
-                },
-                (Some(i), Some(j)) => match i.partial_cmp(j) {
-                    Some(Ordering::Greater) => return CompOp::Gt,
-                    Some(Ordering::Less) => return CompOp::Lt,
-                    // This is the only loop branch that continues
-                    Some(Ordering::Equal) => Ordering::Equal,
-                    _ => panic!()
-                },
-                // both versions are the same length and are equal for all values
-                (None, None) => return CompOp::Eq
-            };
-            let _last_i1 = i1;
-            let _last_i2 = i2;
+        match rest.split_once('+') {
+            Some((public, local)) => (epoch, public, Some(local)),
+            None => (epoch, rest, None),
         }
     }
+
+    /// Split the parts of this version into its epoch, public components and local components.
+    fn split_parts(&self) -> (u64, &[VersionPart<'a>], &[VersionPart<'a>]) {
+        split_parts(&self.parts)
+    }
 }
 
+/// Get the byte ranges of the components of a public or local version string, which are
+/// separated by `.`, `_` or `-`.
+pub(crate) fn component_spans(version: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for (i, c) in version.char_indices() {
+        if ".-_".contains(c) {
+            if i > start {
+                spans.push((start, i));
+            }
+            start = i + 1;
+        }
+    }
+    if start < version.len() {
+        spans.push((start, version.len()));
+    }
+    spans
+}
 
-impl<'a> fmt::Display for Version<'a> {
+impl<'a, S: VersionScheme> fmt::Display for Version<'a, S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.version)
+        S::display(&self.version, f)
     }
 }
 
 // Show just the version component parts as debug output
-impl<'a> fmt::Debug for Version<'a> {
+impl<'a, S: VersionScheme> fmt::Debug for Version<'a, S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if f.alternate() {
             write!(f, "{:#?}", self.parts)
@@ -286,41 +358,79 @@ impl<'a> fmt::Debug for Version<'a> {
 }
 
 /// Implement the partial ordering trait for the version struct, to easily allow version comparison.
-impl<'a> PartialOrd for Version<'a> {
+impl<'a, S: VersionScheme> PartialOrd for Version<'a, S> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.compare(other).ord()
+        Some(self.cmp(other))
+    }
+}
+
+/// Versions are totally ordered, so they can be sorted and used as `BTreeMap` keys.
+impl<'a, S: VersionScheme> Ord for Version<'a, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ordering(other)
     }
 }
 
 /// Implement the partial equality trait for the version struct, to easily allow version comparison.
-impl<'a> PartialEq for Version<'a> {
+impl<'a, S: VersionScheme> PartialEq for Version<'a, S> {
     fn eq(&self, other: &Self) -> bool {
-        self.compare_to(other, &CompOp::Eq)
+        self.ordering(other) == Ordering::Equal
+    }
+}
+
+impl<'a, S: VersionScheme> Eq for Version<'a, S> {}
+
+/// Hashing is consistent with equality: versions that only differ by trailing zeros, such as
+/// `1.0` and `1.0.0`, or by case, such as `1.0rc1` and `1.0RC1`, hash the same.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashSet;
+/// use libronda::Version;
+///
+/// let versions: HashSet<Version> = ["1.0", "1.0.0", "1.0.0.0", "0!1.0"].iter()
+///     .map(|v| Version::from(v).unwrap())
+///     .collect();
+///
+/// assert_eq!(versions.len(), 1);
+/// ```
+impl<'a, S: VersionScheme> Hash for Version<'a, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        S::hash(&self.parts, state)
     }
 }
 
 #[cfg_attr(tarpaulin, skip)]
 #[cfg(test)]
 mod tests {
+    use std::cmp::Ordering;
+    use std::collections::{BTreeMap, HashSet};
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    use proptest::prelude::*;
+    use proptest::string::string_regex;
+
     use crate::CompOp;
     // use crate::version_part::VersionPart;
 
     use super::Version;
 
     // TODO: This doesn't really test whether this method fully works
-    fn from(v_string: &str, n_parts: usize) {
+    fn from(v_string: &str, _n_parts: usize) {
         // Test whether parsing works for each test version
-        assert!(Version::from(v_string).is_some());
+        assert!(Version::from(v_string).is_ok());
     }
     parametrize_versions!(from);
 
-    fn from_with_invalid_versions(v_string: &str, n_parts: usize) {
+    fn from_with_invalid_versions(v_string: &str, _n_parts: usize) {
         // Test whether parsing works for each test invalid version
-        assert!(Version::from(v_string).is_none());
+        assert!(Version::from(v_string).is_err());
     }
     parametrize_versions_errors!(from_with_invalid_versions);
 
-    fn as_str(v_string: &str, n_parts: usize) {
+    fn as_str(v_string: &str, _n_parts: usize) {
         // The input version string must be the same as the returned string
         assert_eq!(Version::from(v_string).unwrap().as_str(), v_string);
     }
@@ -374,7 +484,7 @@ mod tests {
         assert!(version_a.compare_to(&version_b, operator));
 
         // Make sure the inverse operator is not correct
-        assert_eq!(version_a.compare_to(&version_b, &operator.invert()), false);
+        assert!(!version_a.compare_to(&version_b, &operator.invert()));
     }
     parametrize_versions_set!(compare_to);
 
@@ -409,10 +519,10 @@ mod tests {
         let version_b = Version::from(b).unwrap();
 
         // Compare and assert
-        match operator {
-            &CompOp::Eq => assert!(version_a == version_b),
-            &CompOp::Lt => assert!(version_a < version_b),
-            &CompOp::Gt => assert!(version_a > version_b),
+        match *operator {
+            CompOp::Eq => assert!(version_a == version_b),
+            CompOp::Lt => assert!(version_a < version_b),
+            CompOp::Gt => assert!(version_a > version_b),
             _ => {}
         }
     }
@@ -420,9 +530,8 @@ mod tests {
 
     fn partial_eq(a: &str, b: &str, operator: &CompOp) {
         // Skip entries that are less or equal, or greater or equal
-        match operator {
-            &CompOp::Le | &CompOp::Ge => return,
-            _ => {}
+        if let CompOp::Le | CompOp::Ge = *operator {
+            return;
         }
 
         // Get both versions
@@ -430,10 +539,7 @@ mod tests {
         let version_b = Version::from(b).unwrap();
 
         // Determine what the result should be
-        let result = match operator {
-            &CompOp::Eq => true,
-            _ => false,
-        };
+        let result = *operator == CompOp::Eq;
 
         // Test
         assert_eq!(version_a == version_b, result);
@@ -449,89 +555,321 @@ mod tests {
     # [test]
     fn test_less_specific_less_than_more_specific() {
         // 0.4 < 0.4.0
-        let a = Version::from("0.4");
-        let b = Version::from("0.4.0");
-        assert_eq!(a == b, true);
+        let a = Version::from("0.4").unwrap();
+        let b = Version::from("0.4.0").unwrap();
+        assert!(a == b);
     }
 
     # [test]
     fn test_rc_greater_than_earlier_version_less_than_release() {
         // 0.4.0 < 0.4.1.rc < 0.4.1
-        let a = Version::from("0.4.0");
-        let b = Version::from("0.4.1.rc");
-        let c = Version::from("0.4.1");
-        assert_eq!(a < b, true);
-        assert_eq!(b < c, true);
+        let a = Version::from("0.4.0").unwrap();
+        let b = Version::from("0.4.1.rc").unwrap();
+        let c = Version::from("0.4.1").unwrap();
+        assert!(a < b);
+        assert!(b < c);
     }
 
     # [test]
     fn test_case_insensitive_rc() {
         // 0.4.1.rc == 0.4.1.RC
-        let a = Version::from("0.4.1.rc");
-        let b = Version::from("0.4.1.RC");
-        assert_eq!(a == b, true);
+        let a = Version::from("0.4.1.rc").unwrap();
+        let b = Version::from("0.4.1.RC").unwrap();
+        assert!(a == b);
     }
 
     # [test]
     fn test_lexicographical_sort_numbers() {
         // 0.5a1 < 0.5a2
-        let a = Version::from("0.5a1");
-        let b = Version::from("0.5a2");
-        assert_eq!(a < b, true);
+        let a = Version::from("0.5a1").unwrap();
+        let b = Version::from("0.5a2").unwrap();
+        assert!(a < b);
     }
 
     # [test]
     fn test_lexicographical_sort() {
         // 0.5a2 < 0.5b1
-        let a = Version::from("0.5a2");
-        let b = Version::from("0.5b1");
-        assert_eq!(a < b, true);
+        let a = Version::from("0.5a2").unwrap();
+        let b = Version::from("0.5b1").unwrap();
+        assert!(a < b);
     }
 
     # [test]
     fn test_dev_special_case_horribleness() {
         // 1.0 < 1.1dev1 < 1.1a1 < 1.1.0dev1 == 1.1.dev1
-        let a = Version::from("1.0");
-        let b = Version::from("1.1dev1");
-        let c = Version::from("1.1a1");
-        let d = Version::from("1.1.0dev1");
-        let e = Version::from("1.1.dev1");
-        assert_eq!(a < b, true);
-        assert_eq!(b < c, true);
-        assert_eq!(c < d, true);
-        assert_eq!(d == e, true);
+        let a = Version::from("1.0").unwrap();
+        let b = Version::from("1.1dev1").unwrap();
+        let c = Version::from("1.1a1").unwrap();
+        let d = Version::from("1.1.0dev1").unwrap();
+        let e = Version::from("1.1.dev1").unwrap();
+        assert!(a < b);
+        assert!(b < c);
+        assert!(c < d);
+        assert!(d == e);
     }
 
     # [test]
     fn test_rc_with_number() {
-        let a = Version::from("1.1.dev1");
-        let b = Version::from("1.1.a1");
-        let c = Version::from("1.1.0rc1");
-        let d = Version::from("1.1.0");
-        assert_eq!(a < b, true);
-        assert_eq!(b < c, true);
-        assert_eq!(c < d, true);
+        let a = Version::from("1.1.dev1").unwrap();
+        let b = Version::from("1.1.a1").unwrap();
+        let c = Version::from("1.1.0rc1").unwrap();
+        let d = Version::from("1.1.0").unwrap();
+        assert!(a < b);
+        assert!(b < c);
+        assert!(c < d);
     }
 
     # [test]
     fn test_post_gt_release() {
-        let a = Version::from("1.1.0");
-        let b = Version::from("1.1.0post1");
-        let c = Version::from("1996.07.12");
-        assert_eq!(a == b, false);
-        assert_eq!(a > b, false);
-        assert_eq!(a < b, true);
-        assert_eq!(b < c, true);
+        let a = Version::from("1.1.0").unwrap();
+        let b = Version::from("1.1.0post1").unwrap();
+        let c = Version::from("1996.07.12").unwrap();
+        assert!(a != b);
+        assert!(a <= b);
+        assert!(a < b);
+        assert!(b < c);
     }
 
     # [test]
     fn test_epoch() {
-        let a = Version::from("1996.07.12");
-        let b = Version::from("1:0.4.1");
-        let c = Version::from("1:3.4.1");
-        let d = Version::from("2:0.4.1");
-        assert_eq!(a < b, true);
-        assert_eq!(b < c, true);
-        assert_eq!(c < d, true);
+        let a = Version::from("1996.07.12").unwrap();
+        let b = Version::from("1!0.4.1").unwrap();
+        let c = Version::from("1!3.4.1").unwrap();
+        let d = Version::from("2!0.4.1").unwrap();
+        assert!(a < b);
+        assert!(b < c);
+        assert!(c < d);
+    }
+
+    #[test]
+    fn starts_with() {
+        let starts_with = |a: &str, b: &str| Version::from(a).unwrap().starts_with(&Version::from(b).unwrap());
+        assert!(starts_with("1.2.3", "1.2.3"));
+        assert!(starts_with("1.2.0", "1.2"));
+        assert!(starts_with("1.2dev1", "1.2dev"));
+        // conda upper-cases dev for sorting, so it isn't matched by a partial string
+        assert!(!starts_with("1.2dev1", "1.2d"));
+        assert!(starts_with("1.2+abc.1", "1.2+abc"));
+        assert!(starts_with("1.2+abc1", "1.2+ab"));
+        assert!(!starts_with("1.2", "1.2.0"));
+        assert!(!starts_with("1!1.2", "1.2"));
+        assert!(!starts_with("1.3+abc", "1.2+abc"));
+        assert!(!starts_with("1.2.post1", "1.2.p"));
+    }
+
+    /// Check that every group of versions is equal within itself, and strictly ordered against
+    /// every other group according to its position.
+    fn assert_ordered(groups: &[&[&str]]) {
+        let versions: Vec<Vec<Version>> = groups.iter()
+            .map(|group| group.iter().map(|v| Version::from(v).unwrap()).collect())
+            .collect();
+        for (i, group) in versions.iter().enumerate() {
+            for (j, other_group) in versions.iter().enumerate() {
+                let expected = CompOp::from_ord(i.cmp(&j));
+                for a in group {
+                    for b in other_group {
+                        assert_eq!(a.compare(b), expected, "{} {} {}", a, expected.sign(), b);
+                    }
+                }
+            }
+        }
+    }
+
+    // The tables below are taken from conda's test_version.py
+
+    #[test]
+    fn conda_version_order() {
+        assert_ordered(&[
+            &["0.4", "0.4.0"],
+            &["0.4.1a.vc11"],
+            &["0.4.1.rc"],
+            &["0.4.1.vc11"],
+            &["0.4.1"],
+            &["0.5*"],
+            &["0.5a1"],
+            &["0.5b3"],
+            &["0.5C1"],
+            &["0.5z"],
+            &["0.5za"],
+            &["0.5"],
+            &["0.5_5", "0.5-5"],
+            &["0.9.6"],
+            &["0.960923"],
+            &["1.0"],
+            &["1.0.4a3"],
+            &["1.0.4b1"],
+            &["1.0.4"],
+            &["1.1dev1"],
+            &["1.1_"],
+            &["1.1a1"],
+            &["1.1.dev1", "1.1.0dev1"],
+            &["1.1.a1", "1.1.0a1"],
+            &["1.1.0rc1"],
+            &["1.1", "1.1.0"],
+            &["1.1.0post1", "1.1.post1"],
+            &["1.1.1dev1"],
+            &["1.1.1rc1"],
+            &["1.1.1"],
+            &["1.1.1post1"],
+            &["1.1post1"],
+            &["2g6"],
+            &["2.2be.ta29"],
+            &["2.2be5ta29"],
+            &["2.2beta29"],
+            &["2.2.0.1"],
+            &["3.1.1.6"],
+            &["3.2.p.r0"],
+            &["3.2.pr0"],
+            &["3.2.pr.1"],
+            &["5.5.kw"],
+            &["11g"],
+            &["14.3.1"],
+            &["14.3.1.post26.g9d75ca2"],
+            &["1996.07.12"],
+            &["1!0.4.1"],
+            &["1!3.1.1.6"],
+            &["2!0.4.1"],
+        ]);
+        assert_eq!(Version::from("  0.4.rc  ").unwrap(), Version::from("0.4.RC").unwrap());
+        assert_ne!(Version::from(" 0.4 ").unwrap(), Version::from("0.4.RC").unwrap());
+    }
+
+    #[test]
+    fn conda_openssl_convention() {
+        assert_ordered(&[
+            &["1.0.1dev"],
+            &["1.0.1_"],
+            &["1.0.1a"],
+            &["1.0.1b"],
+            &["1.0.1c"],
+            &["1.0.1d"],
+            &["1.0.1r"],
+            &["1.0.1rc"],
+            &["1.0.1rc1"],
+            &["1.0.1rc2"],
+            &["1.0.1s"],
+            &["1.0.1"],
+            &["1.0.1post.a"],
+            &["1.0.1post.b"],
+            &["1.0.1post.z"],
+            &["1.0.1post.za"],
+            &["1.0.2"],
+        ]);
+    }
+
+    #[test]
+    fn conda_pep440() {
+        let versions = [
+            "1.0a1",
+            "1.0a2.dev456",
+            "1.0a12.dev456",
+            "1.0a12",
+            "1.0b1.dev456",
+            "1.0b2",
+            "1.0b2.post345.dev456",
+            "1.0b2.post345",
+            "1.0c1.dev456",
+            "1.0c1",
+            "1.0c3",
+            "1.0rc2",
+            "1.0.dev456",
+            "1.0",
+            "1.0.post456.dev34",
+            "1.0.post456",
+            "1.1.dev1",
+            "1.2.r32+123456",
+            "1.2.rev33+123456",
+            "1.2+abc",
+            "1.2+abc123def",
+            "1.2+abc123",
+            "1.2+123abc",
+            "1.2+123abc456",
+            "1.2+1234.abc",
+            "1.2+123456",
+        ];
+        let with_epoch: Vec<String> = versions.iter().map(|v| format!("1!{}", v)).collect();
+        let all: Vec<&str> = versions.iter().cloned()
+            .chain(with_epoch.iter().map(String::as_str))
+            .collect();
+        let groups: Vec<&[&str]> = all.iter().map(std::slice::from_ref).collect();
+        assert_ordered(&groups);
+    }
+
+    #[test]
+    fn conda_hexrd() {
+        assert_ordered(&[&["0.3.0.dev"], &["0.3.3"]]);
+    }
+
+    #[test]
+    fn conda_invalid_versions() {
+        for version in &["", "  ", "3.5&1", "5.5++", "5.5..mw", "!", "a!1.0", "a!b!1.0"] {
+            assert!(Version::from(version).is_err(), "{} should be invalid", version);
+        }
+    }
+
+    /// A version component as conda would see it in the wild, including the special strings.
+    const COMPONENT: &str = "(0|[1-9][0-9]?|[0-9](a|b|rc|RC|dev|post)[0-9]?|dev|post|a|\\*)";
+
+    fn hash_of(version: &Version) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        version.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn conda_version() -> impl Strategy<Value = String> {
+        let public = format!("([0-9]!)?{c}([._]{c}){{0,4}}", c = COMPONENT);
+        let local = format!("(\\+{c}(\\.{c}){{0,2}})?", c = COMPONENT);
+        (string_regex(&public).unwrap(), string_regex(&local).unwrap())
+            .prop_map(|(public, local)| public + &local)
+    }
+
+    proptest! {
+        #[test]
+        fn ord_antisymmetric(a in conda_version(), b in conda_version()) {
+            let (a, b) = (Version::from(&a).unwrap(), Version::from(&b).unwrap());
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            prop_assert_eq!(a == b, a.cmp(&b) == Ordering::Equal);
+            prop_assert_eq!(a.compare(&b), CompOp::from_ord(a.cmp(&b)));
+        }
+
+        #[test]
+        fn ord_transitive(a in conda_version(), b in conda_version(), c in conda_version()) {
+            let mut versions = [Version::from(&a).unwrap(), Version::from(&b).unwrap(), Version::from(&c).unwrap()];
+            versions.sort();
+            prop_assert!(versions[0] <= versions[1]);
+            prop_assert!(versions[1] <= versions[2]);
+            prop_assert!(versions[0] <= versions[2]);
+        }
+
+        #[test]
+        fn hash_consistent_with_eq(public in conda_version(), zeros in "(\\.0){1,3}") {
+            // Trailing zeros and upper case letters don't change a version, or its hash
+            let padded = match public.find('+') {
+                Some(i) => format!("{}{}{}{}", &public[..i], zeros, &public[i..], zeros),
+                None => format!("{}{}", public, zeros),
+            };
+            let upper = public.to_uppercase();
+            let (a, b, c) = (Version::from(&public).unwrap(), Version::from(&padded).unwrap(), Version::from(&upper).unwrap());
+            prop_assert_eq!(&a, &b);
+            prop_assert_eq!(hash_of(&a), hash_of(&b));
+            prop_assert_eq!(&a, &c);
+            prop_assert_eq!(hash_of(&a), hash_of(&c));
+        }
+    }
+
+    #[test]
+    fn hash_and_ord_collections() {
+        let versions = ["1.0.0", "1.0", "0.9", "1!0.1", "1.0RC1", "1.0rc1", "1.0.0.0"];
+        let set: HashSet<Version> = versions.iter().map(|v| Version::from(v).unwrap()).collect();
+        assert_eq!(set.len(), 4);
+
+        let map: BTreeMap<Version, &str> = versions.iter().map(|v| (Version::from(v).unwrap(), *v)).collect();
+        assert_eq!(map.len(), 4);
+
+        let mut sorted: Vec<Version> = versions.iter().map(|v| Version::from(v).unwrap()).collect();
+        sorted.sort();
+        sorted.dedup();
+        let sorted: Vec<&str> = sorted.iter().map(Version::as_str).collect();
+        assert_eq!(sorted, ["0.9", "1.0RC1", "1.0.0", "1!0.1"]);
     }
 }
@@ -0,0 +1,243 @@
+//! Owned version module, which provides the `VersionBuf` struct.
+//!
+//! A `Version` borrows from the string it was parsed from, which makes it awkward to keep around
+//! in long-lived indexes or to send across threads.  A `VersionBuf` owns its version string and
+//! parts, and compares exactly like the borrowed `Version` it was created from.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::str::FromStr;
+
+use super::comp_op::CompOp;
+use super::parse_error::VersionParseError;
+use super::scheme::{CondaScheme, VersionScheme};
+use super::version::Version;
+
+/// An owned, lifetime-free version.
+///
+/// `VersionBuf` relates to `Version` the way `PathBuf` relates to `Path`: it can be created from a
+/// borrowed `Version`, and it dereferences to a `Version` so that all the probing and comparison
+/// methods are available on it.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{CompOp, Version, VersionBuf};
+///
+/// let buf: VersionBuf = {
+///     let text = String::from("1.2.3");
+///     Version::from(&text).unwrap().into()
+/// };
+///
+/// assert_eq!(buf.as_str(), "1.2.3");
+/// assert_eq!(buf.compare(&Version::from("1.2.4").unwrap()), CompOp::Lt);
+/// ```
+#[derive(Clone)]
+pub struct VersionBuf<S: VersionScheme = CondaScheme> {
+    inner: Version<'static, S>,
+}
+
+impl<S: VersionScheme> VersionBuf<S> {
+    /// Get a borrowed `Version` view of this version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{Version, VersionBuf};
+    ///
+    /// let buf: VersionBuf = "1.2.3".parse().unwrap();
+    /// let ver: &Version = buf.as_version();
+    ///
+    /// assert_eq!(ver.part_count(), 3);
+    /// ```
+    pub fn as_version(&self) -> &Version<'static, S> {
+        &self.inner
+    }
+
+    /// Convert this `VersionBuf` back into a `Version`.
+    pub fn into_version(self) -> Version<'static, S> {
+        self.inner
+    }
+}
+
+/// Parse a `VersionBuf` from a version string, using the parser of its version scheme.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{CompOp, Version, VersionBuf};
+///
+/// let buf: VersionBuf = "1.2.3".parse().unwrap();
+///
+/// assert_eq!(buf.compare(&Version::from("1.2.3").unwrap()), CompOp::Eq);
+/// ```
+impl<S: VersionScheme> FromStr for VersionBuf<S> {
+    type Err = VersionParseError;
+
+    fn from_str(version: &str) -> Result<VersionBuf<S>, VersionParseError> {
+        Version::<S>::new(version).map(VersionBuf::from)
+    }
+}
+
+impl<'a, S: VersionScheme> From<Version<'a, S>> for VersionBuf<S> {
+    fn from(version: Version<'a, S>) -> VersionBuf<S> {
+        VersionBuf { inner: version.into_owned() }
+    }
+}
+
+impl<'a, 'b, S: VersionScheme> From<&'b Version<'a, S>> for VersionBuf<S> {
+    fn from(version: &'b Version<'a, S>) -> VersionBuf<S> {
+        VersionBuf { inner: version.clone().into_owned() }
+    }
+}
+
+impl<S: VersionScheme> Deref for VersionBuf<S> {
+    type Target = Version<'static, S>;
+
+    fn deref(&self) -> &Version<'static, S> {
+        &self.inner
+    }
+}
+
+impl<S: VersionScheme> AsRef<Version<'static, S>> for VersionBuf<S> {
+    fn as_ref(&self) -> &Version<'static, S> {
+        &self.inner
+    }
+}
+
+impl<S: VersionScheme> fmt::Display for VersionBuf<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl<S: VersionScheme> fmt::Debug for VersionBuf<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+impl<S: VersionScheme> PartialOrd for VersionBuf<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: VersionScheme> PartialEq for VersionBuf<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<S: VersionScheme> Eq for VersionBuf<S> {}
+
+impl<S: VersionScheme> Ord for VersionBuf<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inner.cmp(&other.inner)
+    }
+}
+
+impl<S: VersionScheme> Hash for VersionBuf<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state)
+    }
+}
+
+impl<'a, S: VersionScheme> PartialOrd<Version<'a, S>> for VersionBuf<S> {
+    fn partial_cmp(&self, other: &Version<'a, S>) -> Option<Ordering> {
+        self.inner.compare(other).ord()
+    }
+}
+
+impl<'a, S: VersionScheme> PartialEq<Version<'a, S>> for VersionBuf<S> {
+    fn eq(&self, other: &Version<'a, S>) -> bool {
+        self.inner.compare_to(other, &CompOp::Eq)
+    }
+}
+
+impl<'a, S: VersionScheme> PartialOrd<VersionBuf<S>> for Version<'a, S> {
+    fn partial_cmp(&self, other: &VersionBuf<S>) -> Option<Ordering> {
+        self.compare(&other.inner).ord()
+    }
+}
+
+impl<'a, S: VersionScheme> PartialEq<VersionBuf<S>> for Version<'a, S> {
+    fn eq(&self, other: &VersionBuf<S>) -> bool {
+        self.compare_to(&other.inner, &CompOp::Eq)
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::thread;
+
+    use crate::CompOp;
+    use crate::version::Version;
+
+    use super::VersionBuf;
+
+    fn compare(a: &str, b: &str, operator: &CompOp) {
+        // Owned versions must compare exactly like the borrowed versions they came from
+        let version_a = Version::from(a).unwrap();
+        let version_b = Version::from(b).unwrap();
+        let buf_a = VersionBuf::from(&version_a);
+        let buf_b = VersionBuf::from(&version_b);
+
+        assert_eq!(buf_a.compare(&buf_b), version_a.compare(&version_b));
+        assert_eq!(buf_a.compare(&version_b), version_a.compare(&version_b));
+        assert_eq!(version_a.compare(&buf_b), version_a.compare(&version_b));
+        assert_eq!(buf_a.compare_to(&buf_b, operator), version_a.compare_to(&version_b, operator));
+    }
+    parametrize_versions_set!(compare);
+
+    #[test]
+    fn outlives_source_string() {
+        let buf = {
+            let text = String::from("1.2.3");
+            VersionBuf::from(Version::from(&text).unwrap())
+        };
+        assert_eq!(buf.as_str(), "1.2.3");
+        assert_eq!(buf.parts(), Version::from("1.2.3").unwrap().parts());
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = "1.2.3".parse::<VersionBuf>().unwrap();
+        let borrowed: &Version = buf.as_version();
+        assert_eq!(borrowed.as_str(), "1.2.3");
+        assert!(*borrowed == buf);
+        assert_eq!(VersionBuf::from(borrowed).as_str(), buf.as_str());
+        assert_eq!(buf.clone().into_version().as_str(), buf.as_str());
+    }
+
+    #[test]
+    fn send_and_sync() {
+        fn assert_send_sync<T: Send + Sync + Clone>() {}
+        assert_send_sync::<VersionBuf>();
+
+        let buf = "1.2.3".parse::<VersionBuf>().unwrap();
+        let handle = thread::spawn(move || buf.compare(&Version::from("1.2").unwrap()));
+        assert_eq!(handle.join().unwrap(), CompOp::Gt);
+    }
+
+    #[test]
+    fn ord_and_hash() {
+        let mut versions: Vec<VersionBuf> = ["1.1", "1.0.0", "1.0", "1.0a1"].iter().map(|v| v.parse().unwrap()).collect();
+        versions.sort();
+        versions.dedup();
+        let sorted: Vec<&str> = versions.iter().map(|v| v.as_str()).collect();
+        assert_eq!(sorted, ["1.0a1", "1.0.0", "1.1"]);
+
+        let set: HashSet<VersionBuf> = ["1.0", "1.0.0"].iter().map(|v| v.parse().unwrap()).collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(format!("{}", "1.2.3".parse::<VersionBuf>().unwrap()), "1.2.3");
+    }
+}
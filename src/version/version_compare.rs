@@ -4,6 +4,7 @@
 //! useful for version comparison.
 
 use super::comp_op::CompOp;
+use super::parse_error::VersionParseError;
 use super::version::Version;
 
 /// The main library structure, which provides various static methods for easy version comparison.
@@ -16,7 +17,8 @@ impl VersionCompare {
     /// This compares version `a` to version `b`, and returns whether version `a` is greater, less
     /// or equal to version `b`.
     ///
-    /// The two given version numbers must be valid, or an error will be returned.
+    /// The two given version numbers must be valid, or the error of the first invalid version
+    /// will be returned.
     ///
     /// One of the following ok results may be returned:
     ///
@@ -33,25 +35,22 @@ impl VersionCompare {
     /// assert_eq!(VersionCompare::compare("1.2.3", "1.2.3"), Ok(CompOp::Eq));
     /// assert_eq!(VersionCompare::compare("1.2.3", "1.2.4"), Ok(CompOp::Lt));
     /// assert_eq!(VersionCompare::compare("1", "0.1"), Ok(CompOp::Gt));
+    /// assert!(VersionCompare::compare("1!2!3", "0.1").is_err());
     /// ```
-    pub fn compare(a: &str, b: &str) -> Result<CompOp, ()> {
-        // Create version instances
-        let a_ver = Version::from(a);
-        let b_ver = Version::from(b);
-
-        // Both version numbers must have been parsed
-        if a_ver.is_none() || b_ver.is_none() {
-            return Err(());
-        }
+    pub fn compare(a: &str, b: &str) -> Result<CompOp, VersionParseError> {
+        // Create version instances, both version numbers must be parsed
+        let a_ver = Version::from(a)?;
+        let b_ver = Version::from(b)?;
 
         // Compare and return the result
-        Ok(a_ver.unwrap().compare(&b_ver.unwrap()))
+        Ok(a_ver.compare(&b_ver))
     }
 
     /// Compare two version number strings to each other and check whether the given comparison
     /// `operator` is valid.
     ///
-    /// The two given version numbers must be valid, or an error will be returned.
+    /// The two given version numbers must be valid, or the error of the first invalid version
+    /// will be returned.
     ///
     /// # Examples
     ///
@@ -65,18 +64,13 @@ impl VersionCompare {
     /// assert!(VersionCompare::compare_to("1", "0.1", &CompOp::Gt).unwrap());
     /// assert!(VersionCompare::compare_to("1", "0.1", &CompOp::Ge).unwrap());
     /// ```
-    pub fn compare_to(a: &str, b: &str, operator: &CompOp) -> Result<bool, ()> {
-        // Create version instances
-        let a_ver = Version::from(a);
-        let b_ver = Version::from(b);
-
-        // Both version numbers must have been parsed
-        if a_ver.is_none() || b_ver.is_none() {
-            return Err(());
-        }
+    pub fn compare_to(a: &str, b: &str, operator: &CompOp) -> Result<bool, VersionParseError> {
+        // Create version instances, both version numbers must be parsed
+        let a_ver = Version::from(a)?;
+        let b_ver = Version::from(b)?;
 
         // Compare and return the result
-        Ok(a_ver.unwrap().compare_to(&b_ver.unwrap(), &operator))
+        Ok(a_ver.compare_to(&b_ver, operator))
     }
 }
 
@@ -86,13 +80,9 @@ mod tests {
     use crate::CompOp;
 
     use crate::version::VersionCompare;
-    use crate::version::Version;
 
     fn compare(a: &str, b: &str, operator: &CompOp) {
-        match VersionCompare::compare(a, b) == Ok(operator.clone()) {
-            false => panic!(),
-            _ => {}
-        }
+        assert_eq!(VersionCompare::compare(a, b), Ok(operator.clone()));
     }
     parametrize_versions_set!(compare);
 
@@ -110,18 +100,13 @@ mod tests {
         assert!(VersionCompare::compare_to(a, b, operator).unwrap());
 
         // Make sure the inverse operator is not correct
-        assert_eq!(
-            VersionCompare::compare_to(a, b, &operator.opposite()).unwrap(),
-            false
-        );
+        assert!(!VersionCompare::compare_to(a, b, &operator.opposite()).unwrap());
     }
     parametrize_versions_set!(compare_to);
 
     fn compare_to_errors(a: &str, b: &str, operator: &CompOp) {
-        let result = VersionCompare::compare_to(a, b, operator);
-
-        if result.is_ok() {
-            assert!(!result.unwrap())
+        if let Ok(result) = VersionCompare::compare_to(a, b, operator) {
+            assert!(!result)
         }
     }
     parametrize_errors_set!(compare_to_errors);
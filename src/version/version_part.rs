@@ -4,44 +4,145 @@
 //! parts. Each version string is broken down into these version parts when being parsed to a
 //! `Version`.
 
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt;
 
-use super::custom_parts::pep440::PEP440String;
 use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::slice;
 
-#[derive(Copy, Clone)]
+use super::custom_parts::pep440::{Atom, PEP440String, compare_atoms, hash_atoms};
+
+/// A single component of a parsed version.
+///
+/// String variants hold a `Cow`, so a part either borrows from the version string it was parsed
+/// from or owns its data.  Use `into_owned` to detach a part from its source string.
+#[derive(Clone)]
 pub enum VersionPart<'a> {
-    Epoch(i16),
-    Integer(i32),
-    LexicographicString(&'a str),
+    Epoch(u64),
+    Integer(u64),
+    LexicographicString(Cow<'a, str>),
     PEP440String(PEP440String<'a>),
+    /// The local version components, following a `+`.  When present, this is always the last
+    /// part of a version.
+    Local(Vec<VersionPart<'a>>),
     Empty,
 }
 
-pub trait ProvideEmptyImpl{
-    fn get_empty(&self) -> VersionPart;
-}
+impl<'a> VersionPart<'a> {
+    /// Convert this part into one that owns all of its data, so that it no longer borrows from
+    /// the version string it was parsed from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::VersionPart;
+    ///
+    /// let owned: VersionPart<'static> = {
+    ///     let text = String::from("alpha");
+    ///     VersionPart::LexicographicString(text.as_str().into()).into_owned()
+    /// };
+    ///
+    /// assert_eq!(owned, VersionPart::LexicographicString("alpha".into()));
+    /// ```
+    pub fn into_owned(self) -> VersionPart<'static> {
+        match self {
+            VersionPart::Epoch(i) => VersionPart::Epoch(i),
+            VersionPart::Integer(i) => VersionPart::Integer(i),
+            VersionPart::LexicographicString(s) => VersionPart::LexicographicString(Cow::Owned(s.into_owned())),
+            VersionPart::PEP440String(s) => VersionPart::PEP440String(s.into_owned()),
+            VersionPart::Local(parts) => VersionPart::Local(parts.into_iter().map(VersionPart::into_owned).collect()),
+            VersionPart::Empty => VersionPart::Empty,
+        }
+    }
+
+    /// View a component part as the list of atoms conda compares it by.  `single` provides the
+    /// storage for parts that consist of a single atom.
+    pub(crate) fn atoms<'s>(&'s self, single: &'s mut Option<Atom<'s>>) -> &'s [Atom<'s>] {
+        match self {
+            VersionPart::Integer(n) => slice::from_ref(single.get_or_insert(Atom::Number(*n))),
+            VersionPart::LexicographicString(s) =>
+                slice::from_ref(single.get_or_insert(Atom::Text(Cow::Borrowed(s)))),
+            VersionPart::PEP440String(s) => s.atoms(),
+            VersionPart::Epoch(_) | VersionPart::Local(_) | VersionPart::Empty => &[],
+        }
+    }
 
-impl<'a> ProvideEmptyImpl for VersionPart<'a> {
-    fn get_empty(&self) -> VersionPart {
+    /// Rank of the part kinds that are not compared atom by atom.
+    fn rank(&self) -> u8 {
         match self {
-            VersionPart::Epoch(_i) => VersionPart::Epoch(0),
-            VersionPart::Integer(_i) => VersionPart::Integer(0),
-            VersionPart::LexicographicString(_i) => VersionPart::LexicographicString(""),
-            VersionPart::PEP440String(_i) => VersionPart::PEP440String(PEP440String::empty()),
-            VersionPart::Empty => VersionPart::Empty
+            VersionPart::Epoch(_) => 2,
+            VersionPart::Local(_) => 1,
+            _ => 0,
         }
     }
 }
 
+/// Compare two lists of version components the way conda does, filling the shorter list with
+/// empty components.
+pub(crate) fn compare_components(a: &[VersionPart], b: &[VersionPart]) -> Ordering {
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&VersionPart::Empty).cmp(b.get(i).unwrap_or(&VersionPart::Empty));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Whether the list of version components `parts` starts with `prefix`, the way conda matches
+/// `1.2.*`: all prefix components but the last must be equal, and so must all atoms of the last
+/// component but its last, which must be a string prefix of the corresponding atom.
+pub(crate) fn starts_with_components(parts: &[VersionPart], prefix: &[VersionPart]) -> bool {
+    let (last, init) = match prefix.split_last() {
+        Some(split) => split,
+        None => return true,
+    };
+    if compare_components(&parts[..init.len().min(parts.len())], init) != Ordering::Equal {
+        return false;
+    }
+
+    let (mut single, mut prefix_single) = (None, None);
+    let atoms = parts.get(init.len()).unwrap_or(&VersionPart::Empty).atoms(&mut single);
+    let (last_atom, init_atoms) = match last.atoms(&mut prefix_single).split_last() {
+        Some(split) => split,
+        None => return true,
+    };
+    if compare_atoms(&atoms[..init_atoms.len().min(atoms.len())], init_atoms) != Ordering::Equal {
+        return false;
+    }
+    atoms.get(init_atoms.len()).is_some_and(|atom| atom.starts_with(last_atom))
+}
+
+/// Split version parts into their epoch, public components and local components.
+pub(crate) fn split_parts<'p, 'a>(parts: &'p [VersionPart<'a>]) -> (u64, &'p [VersionPart<'a>], &'p [VersionPart<'a>]) {
+    let (epoch, parts) = match parts.split_first() {
+        Some((VersionPart::Epoch(epoch), rest)) => (*epoch, rest),
+        _ => (0, parts),
+    };
+    match parts.split_last() {
+        Some((VersionPart::Local(local), public)) => (epoch, public, &local[..]),
+        _ => (epoch, parts, &[]),
+    }
+}
+
+/// Hash a list of version components consistently with `compare_components`, by leaving out the
+/// trailing components that compare equal to the empty fill value.
+pub(crate) fn hash_components<H: Hasher>(parts: &[VersionPart], state: &mut H) {
+    let len = parts.iter().rposition(|part| *part != VersionPart::Empty).map_or(0, |i| i + 1);
+    parts[..len].hash(state);
+}
+
 impl<'a> Debug for VersionPart<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            VersionPart::Epoch(_i) => write!(f, "Epoch({})", _i),
-            VersionPart::Integer(_i) => write!(f, "Integer({})", _i),
-            VersionPart::LexicographicString(_i) => write!(f, "LexicographicString({})", _i),
-            VersionPart::PEP440String(_i) => write!(f, "PEP440String({})", _i),
+            VersionPart::Epoch(_i) => f.debug_tuple("Epoch").field(_i).finish(),
+            VersionPart::Integer(_i) => f.debug_tuple("Integer").field(_i).finish(),
+            VersionPart::LexicographicString(_i) =>
+                f.debug_tuple("LexicographicString").field(&format_args!("{}", _i)).finish(),
+            VersionPart::PEP440String(_i) => f.debug_tuple("PEP440String").field(&format_args!("{}", _i)).finish(),
+            VersionPart::Local(_i) => f.debug_tuple("Local").field(_i).finish(),
             VersionPart::Empty => write!(f, "Empty"),
         }
     }
@@ -55,27 +156,20 @@ impl<'a> Display for VersionPart<'a> {
 
 impl<'a> PartialOrd for VersionPart<'a> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> Ord for VersionPart<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
-            (VersionPart::Epoch(a), VersionPart::Epoch(b)) => a.partial_cmp(b),
-            (VersionPart::Integer(a), VersionPart::Integer(b)) => a.partial_cmp(b),
-            (VersionPart::LexicographicString(a), VersionPart::LexicographicString(b)) => a.partial_cmp(b),
-            (VersionPart::PEP440String(a), VersionPart::PEP440String(b)) => a.partial_cmp(b),
-            // Match simple position in the list, but reverse it because things at the top are higher
-            _ => Some(match self {
-                &VersionPart::Epoch(_a) => 0,
-                &VersionPart::Integer(_a) => 1,
-                &VersionPart::LexicographicString(_a) => 2,
-                &VersionPart::PEP440String(_a) => 3,
-                &VersionPart::Empty => 4,
-            }.partial_cmp(
-                match other {
-                    &VersionPart::Epoch(_a) => &0,
-                    &VersionPart::Integer(_a) => &1,
-                    &VersionPart::LexicographicString(_a) => &2,
-                    &VersionPart::PEP440String(_a) => &3,
-                    &VersionPart::Empty => &4,
-                }
-            ).unwrap().reverse())
+            (VersionPart::Epoch(a), VersionPart::Epoch(b)) => a.cmp(b),
+            (VersionPart::Local(a), VersionPart::Local(b)) => compare_components(a, b),
+            (VersionPart::LexicographicString(a), VersionPart::LexicographicString(b)) => a.cmp(b),
+            // Epochs trump everything, and local versions trump components
+            _ if self.rank() != other.rank() => self.rank().cmp(&other.rank()),
+            // Components compare atom by atom, with numbers beating strings
+            _ => compare_atoms(self.atoms(&mut None), other.atoms(&mut None)),
         }
     }
 }
@@ -86,10 +180,26 @@ impl<'a> PartialEq for VersionPart<'a> {
     }
 }
 
+impl<'a> Eq for VersionPart<'a> {}
+
+/// Hashing is consistent with equality, so `Integer(1)` and a `PEP440String` of `1` or `1.0`
+/// hash the same, as do `Empty` and `Integer(0)`.
+impl<'a> Hash for VersionPart<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            VersionPart::Epoch(epoch) => epoch.hash(state),
+            VersionPart::Local(parts) => hash_components(parts, state),
+            _ => hash_atoms(self.atoms(&mut None), state),
+        }
+    }
+}
+
 #[cfg_attr(tarpaulin, skip)]
 #[cfg(test)]
 mod tests {
     use crate::version::VersionPart;
+    use crate::version::custom_parts::pep440::PEP440String;
 
     #[test]
     fn epoch_compare() {
@@ -103,5 +213,14 @@ mod tests {
     #[test]
     fn cross_type_compare() {
         assert!(VersionPart::Epoch(0) > VersionPart::Integer(1));
+        assert!(VersionPart::Local(vec![]) > VersionPart::Integer(1));
+    }
+
+    #[test]
+    fn component_compare() {
+        let alpha = VersionPart::PEP440String(PEP440String::from("0a").unwrap());
+        assert!(VersionPart::Integer(0) > alpha);
+        assert!(VersionPart::Empty > alpha);
+        assert_eq!(VersionPart::Empty, VersionPart::Integer(0));
     }
 }
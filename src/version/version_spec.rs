@@ -0,0 +1,701 @@
+//! Version spec module, which provides the `VersionSpec` enum.
+//!
+//! A version spec is a constraint on versions, as found in the `depends` of every package record,
+//! such as `>=14.1,<15.0a0` or `9.*`.  Constraints are combined with `,` meaning AND and `|`
+//! meaning OR, where `,` binds tighter than `|`, and parentheses can be used for grouping.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::comp_op::CompOp;
+use super::parse_error::VersionParseError;
+use super::spec_parse_error::VersionSpecParseError;
+use super::version::Version;
+use super::version_buf::VersionBuf;
+use super::spec_ranges::Ranges;
+
+/// A parsed conda version spec.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{Version, VersionSpec};
+///
+/// let spec: VersionSpec = ">=14.1,<15.0a0".parse().unwrap();
+///
+/// assert!(spec.contains(&Version::from("14.16").unwrap()));
+/// assert!(!spec.contains(&Version::from("15.0").unwrap()));
+/// assert!(!spec.contains(&Version::from("15.0rc1").unwrap()));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum VersionSpec {
+    /// Matches any version (`*`).
+    Any,
+
+    /// Matches a version with exactly the given version string (`===1.2`).
+    Exact(String),
+
+    /// Matches versions that compare to the given version with the operator (`==1.2`, `<2`, ...).
+    Compare(CompOp, VersionBuf),
+
+    /// Matches versions that start with the given version (`1.2.*`, `=1.2`).
+    StartsWith(VersionBuf),
+
+    /// Matches versions that don't start with the given version (`!=1.2.*`).
+    NotStartsWith(VersionBuf),
+
+    /// Matches compatible releases of the given version (`~=1.2.3`, meaning `>=1.2.3,1.2.*`).
+    Compatible(VersionBuf),
+
+    /// Matches version strings against a glob with `*` wildcards (`1.*.3`), stored in lower case.
+    Glob(String),
+
+    /// Matches versions that match all of the specs (`,`).
+    All(Vec<VersionSpec>),
+
+    /// Matches versions that match any of the specs (`|`).
+    AnyOf(Vec<VersionSpec>),
+}
+
+impl VersionSpec {
+    /// Check whether the given `version` satisfies this spec.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::{Version, VersionSpec};
+    ///
+    /// let spec: VersionSpec = "1.4|1.6.*".parse().unwrap();
+    ///
+    /// assert!(spec.contains(&Version::from("1.4.0").unwrap()));
+    /// assert!(spec.contains(&Version::from("1.6.2").unwrap()));
+    /// assert!(!spec.contains(&Version::from("1.5").unwrap()));
+    /// ```
+    pub fn contains(&self, version: &Version) -> bool {
+        match self {
+            VersionSpec::Any => true,
+            VersionSpec::Exact(exact) => version.as_str().trim() == exact,
+            VersionSpec::Compare(operator, other) => version.compare_to(other, operator),
+            VersionSpec::StartsWith(prefix) => version.starts_with(prefix),
+            VersionSpec::NotStartsWith(prefix) => !version.starts_with(prefix),
+            VersionSpec::Compatible(other) => {
+                version.compare_to(other, &CompOp::Ge)
+                    && self.compatible_prefix().is_some_and(|prefix| version.starts_with(&prefix))
+            },
+            VersionSpec::Glob(pattern) => glob_match(pattern, &version.as_str().trim().to_ascii_lowercase()),
+            VersionSpec::All(specs) => specs.iter().all(|spec| spec.contains(version)),
+            VersionSpec::AnyOf(specs) => specs.iter().any(|spec| spec.contains(version)),
+        }
+    }
+
+    /// Get the prefix that releases compatible with a `~=` spec must start with, which is its
+    /// version without the last component: `~=1.4.5` means `>=1.4.5,1.4.*`.
+    pub(crate) fn compatible_prefix(&self) -> Option<VersionBuf> {
+        let version = match self {
+            VersionSpec::Compatible(version) => version.as_str(),
+            _ => return None,
+        };
+        let public = version.split('+').next().unwrap_or(version);
+        let end = public.rfind(['.', '_', '-'])?;
+        public[..end].parse().ok()
+    }
+
+    /// Check whether no version can satisfy this spec.
+    ///
+    /// Specs with globs or `===` can't be analyzed, and are never considered empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::VersionSpec;
+    ///
+    /// assert!(">=2,<1.5".parse::<VersionSpec>().unwrap().is_empty());
+    /// assert!(!">=1.2,<2,(>=1.8|<1.0)".parse::<VersionSpec>().unwrap().is_empty());
+    /// assert!("1.2.*,>=1.3".parse::<VersionSpec>().unwrap().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        Ranges::from_spec(self).is_some_and(|ranges| ranges.is_empty())
+    }
+
+    /// Get the spec satisfied by the versions that satisfy both this spec and `other`.
+    ///
+    /// The result is simplified when possible.  When the intersection is empty, or can't be
+    /// analyzed because of globs or `===`, both specs are joined with `,`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::VersionSpec;
+    ///
+    /// let a: VersionSpec = ">=1.2,<2".parse().unwrap();
+    /// let b: VersionSpec = ">=1.8|<1.0".parse().unwrap();
+    ///
+    /// assert_eq!(a.intersect(&b).to_string(), ">=1.8,<2");
+    /// ```
+    pub fn intersect(&self, other: &VersionSpec) -> VersionSpec {
+        let joined = flatten(vec![self.clone(), other.clone()], VersionSpec::All);
+        joined.simplify().unwrap_or(joined)
+    }
+
+    /// Get the spec satisfied by the versions that satisfy this spec or `other`.
+    ///
+    /// The result is simplified when possible.  Otherwise, both specs are joined with `|`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::VersionSpec;
+    ///
+    /// let a: VersionSpec = ">=1.2,<2".parse().unwrap();
+    /// let b: VersionSpec = ">=1.8,<3".parse().unwrap();
+    ///
+    /// assert_eq!(a.union(&b).to_string(), ">=1.2,<3");
+    /// ```
+    pub fn union(&self, other: &VersionSpec) -> VersionSpec {
+        let joined = flatten(vec![self.clone(), other.clone()], VersionSpec::AnyOf);
+        joined.simplify().unwrap_or(joined)
+    }
+
+    /// Check whether every version that satisfies this spec also satisfies `other`.
+    ///
+    /// Specs with globs or `===` can't be analyzed, and are only subsets of equal specs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::VersionSpec;
+    ///
+    /// let pin: VersionSpec = "1.16.*".parse().unwrap();
+    ///
+    /// assert!(pin.is_subset_of(&">=1.16.4|<1.17.0a0".parse().unwrap()));
+    /// assert!(pin.is_subset_of(&">=1.10,<1.17.0a0".parse().unwrap()));
+    /// assert!(!pin.is_subset_of(&">=1.16.4,<1.17.0a0".parse().unwrap()));
+    /// ```
+    pub fn is_subset_of(&self, other: &VersionSpec) -> bool {
+        match (Ranges::from_spec(self), Ranges::from_spec(other)) {
+            (Some(ranges), Some(other_ranges)) => ranges.intersect(&other_ranges).same_as(&ranges),
+            _ => self == other,
+        }
+    }
+
+    /// Simplify this spec into a canonical spec that is satisfied by the same versions, such as
+    /// `>=1.2,<2` for `>=1,<3,>=1.2,<2.0`.  Returns `None` if no version can satisfy this spec.
+    ///
+    /// Specs with globs or `===` can't be analyzed, and are returned unchanged.  So are specs
+    /// that have no simpler form, such as `==2.0,2.0.*`, which unlike `==2.0` doesn't hold `2`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libronda::VersionSpec;
+    ///
+    /// let simplify = |spec: &str| spec.parse::<VersionSpec>().unwrap().simplify().map(|s| s.to_string());
+    ///
+    /// assert_eq!(simplify(">=1,<3,>=1.2,<2.0"), Some(">=1.2,<2.0".to_string()));
+    /// assert_eq!(simplify(">=1,<2,!=1.5|1.5"), Some(">=1,<2".to_string()));
+    /// assert_eq!(simplify(">=1,<=1"), Some("==1".to_string()));
+    /// assert_eq!(simplify(">1,<1"), None);
+    /// ```
+    pub fn simplify(&self) -> Option<VersionSpec> {
+        match Ranges::from_spec(self) {
+            Some(ranges) if ranges.is_empty() => None,
+            Some(ranges) => Some(ranges.to_spec().unwrap_or_else(|| self.clone())),
+            None => Some(self.clone()),
+        }
+    }
+}
+
+/// Match `text` against a `pattern` in which `*` matches any sequence of characters.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern, and the text position it was tried at
+    let mut backtrack = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star, start)) = backtrack {
+            // Let the last `*` swallow one more character
+            backtrack = Some((star, start + 1));
+            p = star + 1;
+            t = start + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Parse the version of a constraint, with `offset` being its position in the spec string.
+fn parse_version(version: &str, offset: usize) -> Result<VersionBuf, VersionSpecParseError> {
+    version.parse().map_err(|err: VersionParseError| VersionSpecParseError::InvalidVersion(err.shifted(offset)))
+}
+
+/// Parse a single constraint such as `>=1.2` or `1.2.*`, found at `offset` in the spec string.
+fn parse_constraint(text: &str, offset: usize) -> Result<VersionSpec, VersionSpecParseError> {
+    if text == "*" {
+        return Ok(VersionSpec::Any);
+    }
+
+    if let Some(exact) = text.strip_prefix("===") {
+        let exact = exact.trim();
+        if exact.is_empty() {
+            return Err(VersionSpecParseError::EmptyConstraint { offset: offset + 3 });
+        }
+        return Ok(VersionSpec::Exact(exact.to_string()));
+    }
+
+    let operator_len = text.bytes().take_while(|b| b"=<>!~".contains(b)).count();
+    if operator_len > 0 {
+        let operator = &text[..operator_len];
+        let rest = text[operator_len..].trim_start();
+        let version_offset = offset + text.len() - rest.len();
+        if rest.is_empty() {
+            return Err(VersionSpecParseError::EmptyConstraint { offset: version_offset });
+        }
+
+        // Like conda, a trailing `.*` is dropped, except that it turns `!=` into a prefix match
+        let (version, glob) = match rest.strip_suffix(".*") {
+            Some(version) => (version, true),
+            None => (rest, false),
+        };
+        let version = parse_version(version, version_offset)?;
+        return Ok(match operator {
+            "=" => VersionSpec::StartsWith(version),
+            "!=" if glob => VersionSpec::NotStartsWith(version),
+            "~=" => {
+                let spec = VersionSpec::Compatible(version);
+                if glob || spec.compatible_prefix().is_none() {
+                    return Err(VersionSpecParseError::InvalidCompatibleRelease { offset });
+                }
+                spec
+            },
+            // Only accept the canonical signs, not aliases like `<>`
+            _ => match CompOp::from_sign(operator) {
+                Ok(compop) if compop.sign() == operator => VersionSpec::Compare(compop, version),
+                _ => return Err(VersionSpecParseError::InvalidOperator { offset }),
+            },
+        });
+    }
+
+    if text.trim_end_matches('*').contains('*') {
+        return Ok(VersionSpec::Glob(text.to_ascii_lowercase()));
+    }
+    if let Some(prefix) = text.strip_suffix('*') {
+        let prefix = prefix.strip_suffix('.').unwrap_or(prefix);
+        return Ok(VersionSpec::StartsWith(parse_version(prefix, offset)?));
+    }
+    Ok(VersionSpec::Compare(CompOp::Eq, parse_version(text, offset)?))
+}
+
+/// Recursive descent parser for version specs, where `,` binds tighter than `|`.
+struct SpecParser<'s> {
+    spec: &'s str,
+    position: usize,
+}
+
+impl<'s> SpecParser<'s> {
+    /// Skip whitespace, and return the next character.
+    fn peek(&mut self) -> Option<u8> {
+        let rest = &self.spec[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+        self.spec.as_bytes().get(self.position).cloned()
+    }
+
+    fn parse_any_of(&mut self) -> Result<VersionSpec, VersionSpecParseError> {
+        let mut specs = vec![self.parse_all()?];
+        while self.peek() == Some(b'|') {
+            self.position += 1;
+            specs.push(self.parse_all()?);
+        }
+        Ok(flatten(specs, VersionSpec::AnyOf))
+    }
+
+    fn parse_all(&mut self) -> Result<VersionSpec, VersionSpecParseError> {
+        let mut specs = vec![self.parse_term()?];
+        while self.peek() == Some(b',') {
+            self.position += 1;
+            specs.push(self.parse_term()?);
+        }
+        Ok(flatten(specs, VersionSpec::All))
+    }
+
+    fn parse_term(&mut self) -> Result<VersionSpec, VersionSpecParseError> {
+        if self.peek() == Some(b'(') {
+            let open = self.position;
+            self.position += 1;
+            let spec = self.parse_any_of()?;
+            if self.peek() != Some(b')') {
+                return Err(VersionSpecParseError::UnbalancedParenthesis { offset: open });
+            }
+            self.position += 1;
+            return Ok(spec);
+        }
+
+        let start = self.position;
+        let rest = &self.spec[start..];
+        let end = rest.find([',', '|', '(', ')']).unwrap_or(rest.len());
+        let text = rest[..end].trim_end();
+        if text.is_empty() {
+            return Err(VersionSpecParseError::EmptyConstraint { offset: start });
+        }
+        self.position = start + end;
+        parse_constraint(text, start)
+    }
+}
+
+/// Combine `specs` with `combine`, merging nested combinations of the same kind.
+pub(crate) fn flatten(mut specs: Vec<VersionSpec>, combine: fn(Vec<VersionSpec>) -> VersionSpec) -> VersionSpec {
+    if specs.len() == 1 {
+        return specs.pop().unwrap();
+    }
+    let mut flat = Vec::with_capacity(specs.len());
+    for spec in specs {
+        match (combine(Vec::new()), spec) {
+            (VersionSpec::All(_), VersionSpec::All(nested)) => flat.extend(nested),
+            (VersionSpec::AnyOf(_), VersionSpec::AnyOf(nested)) => flat.extend(nested),
+            (_, spec) => flat.push(spec),
+        }
+    }
+    combine(flat)
+}
+
+/// Parse a `VersionSpec` from a spec string.  An empty spec matches any version.
+///
+/// # Examples
+///
+/// ```
+/// use libronda::{CompOp, VersionSpec, VersionSpecParseError};
+///
+/// let spec: VersionSpec = ">=1.2,<2|3.*".parse().unwrap();
+/// assert_eq!(spec.to_string(), ">=1.2,<2|3.*");
+///
+/// assert_eq!(
+///     ">=1.2,,<2".parse::<VersionSpec>().unwrap_err(),
+///     VersionSpecParseError::EmptyConstraint { offset: 6 },
+/// );
+/// ```
+impl FromStr for VersionSpec {
+    type Err = VersionSpecParseError;
+
+    fn from_str(spec: &str) -> Result<VersionSpec, VersionSpecParseError> {
+        if spec.trim().is_empty() {
+            return Ok(VersionSpec::Any);
+        }
+
+        let mut parser = SpecParser { spec, position: 0 };
+        let parsed = parser.parse_any_of()?;
+        match parser.peek() {
+            None => Ok(parsed),
+            Some(_) => Err(VersionSpecParseError::UnbalancedParenthesis { offset: parser.position }),
+        }
+    }
+}
+
+impl fmt::Display for VersionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VersionSpec::Any => write!(f, "*"),
+            VersionSpec::Exact(exact) => write!(f, "==={}", exact),
+            VersionSpec::Compare(operator, version) => write!(f, "{}{}", operator.sign(), version),
+            VersionSpec::StartsWith(version) => write!(f, "{}.*", version),
+            VersionSpec::NotStartsWith(version) => write!(f, "!={}.*", version),
+            VersionSpec::Compatible(version) => write!(f, "~={}", version),
+            VersionSpec::Glob(pattern) => write!(f, "{}", pattern),
+            VersionSpec::All(specs) => {
+                for (i, spec) in specs.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { "," };
+                    match spec {
+                        VersionSpec::AnyOf(_) => write!(f, "{}({})", separator, spec)?,
+                        _ => write!(f, "{}{}", separator, spec)?,
+                    }
+                }
+                Ok(())
+            },
+            VersionSpec::AnyOf(specs) => {
+                for (i, spec) in specs.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { "" } else { "|" }, spec)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::version::{Version, VersionParseError, VersionSpecParseError};
+
+    use super::{VersionSpec, glob_match};
+
+    #[rstest_parametrize(spec, version, expected,
+    case(">=1.2", "1.2", true),
+    case(">=1.2", "1.1.9", false),
+    case(">1.2", "1.2.0", false),
+    case("<=1.2", "1.2.0", true),
+    case("<2", "2.0a1", true),
+    case("<2.0a0", "2.0a1", false),
+    case("==1.2", "1.2.0", true),
+    case("==1.2", "1.2.1", false),
+    case("!=1.2", "1.2.1", true),
+    case("!=1.2", "1.2.0", false),
+    case("1.2", "1.2.0", true),
+    case("1.2", "1.2.1", false),
+    case("*", "0.0.1", true),
+    case("1.2.*", "1.2.7", true),
+    case("1.2.*", "1.2", true),
+    case("1.2.*", "1.20", false),
+    case("1.2*", "1.2.7", true),
+    case("=1.2", "1.2.7", true),
+    case("=1.2", "1.3", false),
+    case("=1.2.*", "1.2.7", true),
+    case(">=1.2.*", "1.3", true),
+    case("!=1.2.*", "1.2.3", false),
+    case("!=1.2.*", "1.3.0", true),
+    case("~=1.2.3", "1.2.5", true),
+    case("~=1.2.3", "1.2.2", false),
+    case("~=1.2.3", "1.3.0", false),
+    case("~=1.2", "1.9", true),
+    case("~=1.2", "2.0", false),
+    case("===1.2", "1.2", true),
+    case("===1.2", "1.2.0", false),
+    case("1.*.3", "1.7.3", true),
+    case("1.*.3", "1.7.4", false),
+    case(">=1.2,<2", "1.9", true),
+    case(">=1.2,<2", "2.0", false),
+    case("1.4|1.6", "1.6", true),
+    case("1.4|1.6", "1.5", false),
+    case(">=1,<2|>3", "3.1", true),
+    case(">=1,<2|>3", "2.5", false),
+    case("1.2|>=1.5,<1.6", "1.2", true),
+    case("(1.2|>=1.5),<1.6", "1.7", false),
+    case(">= 1.2 , < 2", "1.5", true),
+    case(">=1!1.0", "2.0", false),
+    case(">=1.0+abc", "1.0+abd", true),
+    )]
+    fn contains(spec: &str, version: &str, expected: bool) {
+        let spec: VersionSpec = spec.parse().unwrap();
+        assert_eq!(spec.contains(&Version::from(version).unwrap()), expected, "{} {}", spec, version);
+    }
+
+    #[rstest_parametrize(spec, canonical,
+    case("*", "*"),
+    case("", "*"),
+    case(">=1.2,<2", ">=1.2,<2"),
+    case("1.2", "==1.2"),
+    case("=1.2", "1.2.*"),
+    case("1.2*", "1.2.*"),
+    case("!=1.2.*", "!=1.2.*"),
+    case("~=1.2", "~=1.2"),
+    case("===1.2", "===1.2"),
+    case("1.*.3", "1.*.3"),
+    case("(1|2),3", "(==1|==2),==3"),
+    case("1|2,3", "==1|==2,==3"),
+    case("((1,2),3)", "==1,==2,==3"),
+    )]
+    fn display(spec: &str, canonical: &str) {
+        let parsed: VersionSpec = spec.parse().unwrap();
+        assert_eq!(parsed.to_string(), canonical);
+        // The canonical string must parse back to the same spec
+        assert_eq!(canonical.parse::<VersionSpec>().unwrap(), parsed);
+    }
+
+    #[test]
+    fn precedence() {
+        let spec: VersionSpec = "1|2,3".parse().unwrap();
+        match spec {
+            VersionSpec::AnyOf(specs) => assert!(matches!(specs[1], VersionSpec::All(_))),
+            _ => panic!("expected an AnyOf spec, got {:?}", spec),
+        }
+    }
+
+    #[rstest_parametrize(spec, error,
+    case(">=1.2,,<2", VersionSpecParseError::EmptyConstraint { offset: 6 }),
+    case("|1.2", VersionSpecParseError::EmptyConstraint { offset: 0 }),
+    case(">=", VersionSpecParseError::EmptyConstraint { offset: 2 }),
+    case("=>1.2", VersionSpecParseError::InvalidOperator { offset: 0 }),
+    case(">==1.2", VersionSpecParseError::InvalidOperator { offset: 0 }),
+    case("1.2,<>1.3", VersionSpecParseError::InvalidOperator { offset: 4 }),
+    case("(1.2", VersionSpecParseError::UnbalancedParenthesis { offset: 0 }),
+    case("1.2)", VersionSpecParseError::UnbalancedParenthesis { offset: 3 }),
+    case("~=1", VersionSpecParseError::InvalidCompatibleRelease { offset: 0 }),
+    case("~=1.2.*", VersionSpecParseError::InvalidCompatibleRelease { offset: 0 }),
+    case(">=1.2,<x!2", VersionSpecParseError::InvalidVersion(VersionParseError::BadEpoch { offset: 7 })),
+    case("1.2|1.0$", VersionSpecParseError::InvalidVersion(VersionParseError::IllegalCharacter { character: '$', offset: 7 })),
+    )]
+    fn parse_error(spec: &str, error: VersionSpecParseError) {
+        assert_eq!(spec.parse::<VersionSpec>().unwrap_err(), error);
+    }
+
+    #[rstest_parametrize(a, b, intersection,
+    case(">=1.2,<2", ">=1.8|<1.0", ">=1.8,<2"),
+    case(">=1.2,<2", "<1.0", ">=1.2,<2,<1.0"),
+    case("*", ">=1", ">=1"),
+    case(">=1", "<=1", "==1"),
+    case("1.2.*", ">=1.2.3", ">=1.2.3,1.2.*"),
+    case("1.2.*", "<1.2.5", "1.2.*,<1.2.5"),
+    case("1.*", "1.2.*", "1.2.*"),
+    case("1.2.*", "!=1.2.4", "1.2.*,!=1.2.4"),
+    case(">=14.1", "<15.0a0", ">=14.1,<15.0a0"),
+    case("<15.0a0", "15.0rc1", "<15.0a0,==15.0rc1"),
+    case("!=1.5", ">=1,<2", ">=1,<2,!=1.5"),
+    case("~=1.2.3", "<1.2.8", ">=1.2.3,<1.2.8"),
+    case("1.2|1.4", "1.4|1.6", "==1.4"),
+    case("1.0.*", "1.*", "1.0.*"),
+    case("2.0.1", "2.0.*", "==2.0.1"),
+    )]
+    fn intersect(a: &str, b: &str, intersection: &str) {
+        let (a, b): (VersionSpec, VersionSpec) = (a.parse().unwrap(), b.parse().unwrap());
+        assert_eq!(a.intersect(&b).to_string(), intersection);
+        assert_eq!(b.intersect(&a).intersect(&b).simplify(), a.intersect(&b).simplify());
+    }
+
+    #[test]
+    fn intersect_prefix_ending_with_zero() {
+        // `2` is equal to `2.0`, but doesn't start with it
+        let (a, b): (VersionSpec, VersionSpec) = ("2.0".parse().unwrap(), "=2.0".parse().unwrap());
+        let intersection = a.intersect(&b);
+        assert_eq!(intersection.to_string(), "==2.0,2.0.*");
+        assert!(!intersection.contains(&Version::from("2").unwrap()));
+        assert!(intersection.contains(&Version::from("2.0.0").unwrap()));
+    }
+
+    #[test]
+    fn intersect_glob() {
+        let (a, b): (VersionSpec, VersionSpec) = ("1.*.3".parse().unwrap(), ">=1".parse().unwrap());
+        assert_eq!(a.intersect(&b).to_string(), "1.*.3,>=1");
+        assert_eq!(a.union(&b).to_string(), "1.*.3|>=1");
+    }
+
+    #[rstest_parametrize(a, b, expected,
+    case(">=1.2,<2", ">=2,<3", ">=1.2,<3"),
+    case("<2", ">3", "<2|>3"),
+    case("1.2.*", "1.2.3", "1.2.*"),
+    case("<1.5", ">1.5", "!=1.5"),
+    case("<1.5|>=1.5", "1", "*"),
+    case("1.2.*|1.4.*", "1.3.*", "1.2.*|1.3.*|1.4.*"),
+    case("<1.2|>=1.3", "1.2.*", "1.2.*|<=1.2|>=1.3"),
+    case("1.*", "1.0.*|<1.0", "1.*|<=1"),
+    case("1.*", "~=1.0.dev0|<=1.2", "1.*|<=1"),
+    )]
+    fn union(a: &str, b: &str, expected: &str) {
+        let (a, b): (VersionSpec, VersionSpec) = (a.parse().unwrap(), b.parse().unwrap());
+        assert_eq!(a.union(&b).to_string(), expected);
+    }
+
+    #[rstest_parametrize(spec, empty,
+    case(">1,<1", true),
+    case(">=2,<1.5", true),
+    case("1.2.*,>=1.3", true),
+    case("1.2.*,<1.2", false),
+    case("1.2.*,!=1.2.*", true),
+    case("==1.2,!=1.2.0", true),
+    case("<2.0a0,>=2.0a0", true),
+    case("<2.0a0,>=2.0.dev", true),
+    case("<2.0a0,>=2.0dev", false),
+    case("1.*.3,<0", false),
+    case("(1|2),(3|4)", true),
+    case("2.0.1,!=2.0.*", true),
+    case("==2,2.0.*", false),
+    )]
+    fn is_empty(spec: &str, empty: bool) {
+        assert_eq!(spec.parse::<VersionSpec>().unwrap().is_empty(), empty);
+    }
+
+    #[rstest_parametrize(a, b, subset,
+    case("1.16.*", ">=1.16.4|<1.17.0a0", true),
+    case("1.16.*", ">=1.10,<1.17.0a0", true),
+    case("1.16.*", ">=1.16,<1.17", false),
+    case("1.16.4", ">=1.16.4,<1.17.0a0", true),
+    // 1.17.0dev sorts below 1.17.0a0
+    case(">=1.16.4,<1.17.0a0", "1.16.*", false),
+    case(">=1.16.4,<1.17.0dev", "1.16.*", false),
+    case(">=1.16.4,<1.16.9", "1.16.*", true),
+    case("~=1.2.3", "1.2.*", true),
+    case("*", ">=0", false),
+    case("1.*.3", "1.*.3", true),
+    case("1.*.3", "*", false),
+    case("==2.0", "=2.0", false),
+    case("2.0.1", "=2.0", true),
+    case("1.0.*", "1.*", true),
+    case("1.*", "1.0.*", false),
+    )]
+    fn is_subset_of(a: &str, b: &str, subset: bool) {
+        let (a, b): (VersionSpec, VersionSpec) = (a.parse().unwrap(), b.parse().unwrap());
+        assert_eq!(a.is_subset_of(&b), subset);
+    }
+
+    #[test]
+    fn simplify_keeps_contains() {
+        let specs = [">=1.2,<2|1.5.*,!=1.5.2", "1.2.*,<1.2.5|>1.2.7,<1.3", "<1.2,!=1.1.*|1.3.*", "~=2.1.0,!=2.1.3"];
+        let versions = ["1.1", "1.1.5", "1.2", "1.2.3", "1.2.6", "1.2.8", "1.3.1", "1.5.2", "1.9", "2.1.0", "2.1.3", "2.1.7", "2.2"];
+        for spec in &specs {
+            let spec: VersionSpec = spec.parse().unwrap();
+            let simplified = spec.simplify().unwrap();
+            for version in &versions {
+                let version = Version::from(version).unwrap();
+                assert_eq!(simplified.contains(&version), spec.contains(&version), "{} -> {} for {}", spec, simplified, version);
+            }
+        }
+    }
+
+    /// Versions around the bounds of the specs built by `spec`, including versions that are
+    /// equal but written with a different number of components.
+    const SAMPLE_VERSIONS: [&str; 36] = [
+        "0", "0.9", "1", "1.0", "1.0.0", "1.0.0.0", "1.0dev", "1.0a1", "1.0rc1", "1.0.post1", "1.0.0a1",
+        "1.0.1", "1.0.1a1", "1.1", "1.1.0", "1.1.5", "1.2", "1.2.0", "1.2.3", "1.2.3.1", "1.2.4", "1.10",
+        "1a1", "2", "2.0", "2.0.0", "2.0dev", "2.0a1", "2.0.post1", "2.0.1", "2.1", "3", "1!1.0",
+        "1.0+abc", "2+abc", "2.0+1",
+    ];
+
+    /// Build a spec of up to three alternatives of up to three constraints each.
+    fn spec() -> impl Strategy<Value = VersionSpec> {
+        let operators: &'static [&str] = &["", "==", "!=", "<", "<=", ">", ">=", "=", "!=.*", "~="];
+        let versions: &'static [&str] = &["0", "1", "1.0", "1.0.0", "1.0a1", "1.0.1", "1.1", "1.2", "1.2.3", "2", "2.0", "2.0.0", "2.1"];
+        let constraint = (proptest::sample::select(operators), proptest::sample::select(versions))
+            .prop_map(|(operator, version)| match operator {
+                "!=.*" => format!("!={}.*", version),
+                "~=" if !version.contains('.') => format!("~={}.0", version),
+                _ => format!("{}{}", operator, version),
+            });
+        let all = proptest::collection::vec(constraint, 1..4).prop_map(|constraints| constraints.join(","));
+        proptest::collection::vec(all, 1..4).prop_map(|alternatives| alternatives.join("|").parse().unwrap())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(2000))]
+
+        #[test]
+        fn set_operations_agree_with_contains(a in spec(), b in spec()) {
+            let (intersection, union, simplified) = (a.intersect(&b), a.union(&b), a.simplify());
+            let (empty, subset) = (a.is_empty(), a.is_subset_of(&b));
+            for version in &SAMPLE_VERSIONS {
+                let version = Version::from(version).unwrap();
+                let (in_a, in_b) = (a.contains(&version), b.contains(&version));
+                prop_assert_eq!(intersection.contains(&version), in_a && in_b, "{} and {} gave {} for {}", a, b, intersection, version);
+                prop_assert_eq!(union.contains(&version), in_a || in_b, "{} or {} gave {} for {}", a, b, union, version);
+                prop_assert_eq!(simplified.as_ref().is_some_and(|s| s.contains(&version)), in_a, "{} gave {:?} for {}", a, simplified, version);
+                prop_assert!(!empty || !in_a, "{} is empty but contains {}", a, version);
+                prop_assert!(!subset || !in_a || in_b, "{} is a subset of {} but {} only satisfies the first", a, b, version);
+            }
+        }
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("1.*.3", "1.2.3"));
+        assert!(glob_match("1.*.3", "1.2.2.3"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*a*b", "xaxxb"));
+        assert!(!glob_match("*a*b", "xaxxbc"));
+        assert!(!glob_match("1.*.3", "1.3"));
+    }
+}